@@ -0,0 +1,70 @@
+//! SSH-агент для программ на базе OpenSSH (ssh, scp, rsync, Ansible)
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+use colored::Colorize;
+use zeroize::Zeroize;
+
+use crate::config::{self, UnlockedVault};
+use crate::error::{Result, SecureSshError};
+use crate::ssh;
+use crate::watchdog;
+
+use super::prompt_password;
+
+pub fn run(socket: Option<PathBuf>) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    let socket = match socket {
+        Some(socket) => socket,
+        None => ssh::default_agent_socket()?,
+    };
+
+    let mut password = prompt_password()?;
+
+    print!("{}", t!(DECRYPTING_KEY).cyan());
+    io::stdout().flush()?;
+
+    let loaded = UnlockedVault::unlock(config::data_storage()?, password.as_bytes());
+    password.zeroize();
+    // Агенту нужен только ключ
+    let (private_key, _) = match loaded {
+        Ok(vault) => vault.into_parts(),
+        Err(e) => {
+            println!("{}", t!(FAILED).red());
+            return Err(e);
+        }
+    };
+    println!("{}", t!(DONE).green());
+
+    let watchdog = watchdog::create_watchdog();
+
+    println!();
+    println!("{} {}", t!(AGENT_STARTED).cyan(), socket.display().to_string().bold());
+    println!();
+    println!("  export SSH_AUTH_SOCK={}", socket.display());
+    println!();
+    println!("{}", t!(AGENT_EXPORT_HINT).dimmed());
+    if watchdog.is_some() {
+        println!("{}", t!(AGENT_WATCHDOG).dimmed());
+    }
+    println!("{}", t!(AGENT_STOP_HINT).dimmed());
+
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| SecureSshError::Other(t!(RUNTIME_FAILED, e)))?;
+
+    let result = runtime.block_on(ssh::run_agent(&socket, &private_key, watchdog));
+
+    drop(private_key);
+
+    match result {
+        Ok(()) => {
+            println!();
+            println!("{}", t!(AGENT_STOPPED).green());
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
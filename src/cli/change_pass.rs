@@ -2,8 +2,8 @@
 
 use colored::Colorize;
 
-use crate::config;
-use crate::crypto::{self, KeyPair};
+use crate::config::{self, UnlockedVault};
+use crate::crypto;
 use crate::error::Result;
 
 use super::{prompt_new_password, prompt_password};
@@ -13,20 +13,19 @@ pub fn run() -> Result<()> {
         return Err(crate::error::SecureSshError::NotInitialized);
     }
 
-    println!("{}", "=== Смена мастер-пароля ===".cyan().bold());
+    println!("{}", t!(CHANGE_PASS_TITLE).cyan().bold());
     println!();
 
     // Получить текущий пароль
-    println!("Введите текущий пароль:");
+    println!("{}", t!(CHANGE_PASS_CURRENT));
     let old_password = prompt_password()?;
 
     // Загрузить и расшифровать всё текущим паролем
-    print!("{}", "Проверка текущего пароля... ".cyan());
+    print!("{}", t!(CHANGE_PASS_CHECKING).cyan());
     std::io::Write::flush(&mut std::io::stdout())?;
 
-    let (private_key, old_salt) = config::load_encrypted_key(old_password.as_bytes())?;
-    let servers = config::load_servers(old_password.as_bytes(), &old_salt)?;
-    println!("{}", "готово".green());
+    let mut vault = UnlockedVault::unlock(config::data_storage()?, old_password.as_bytes())?;
+    println!("{}", t!(DONE).green());
 
     // Получить новый пароль
     println!();
@@ -34,31 +33,26 @@ pub fn run() -> Result<()> {
     println!();
 
     // Вычислить новый ключ шифрования
-    print!("{}", "Вычисление нового ключа шифрования... ".cyan());
+    print!("{}", t!(CHANGE_PASS_DERIVING).cyan());
     std::io::Write::flush(&mut std::io::stdout())?;
 
-    let new_derived_key = crypto::derive_key(new_password.as_bytes(), None)?;
-    println!("{}", "готово".green());
+    // Параметры Argon2id сохраняются, меняются только пароль и соль
+    let new_derived_key = crypto::derive_key(new_password.as_bytes(), None, &vault.kdf_params())?;
+    println!("{}", t!(DONE).green());
 
-    // Восстановить keypair для получения публичного ключа
-    let keypair = KeyPair::from_private_key(private_key)?;
-    let public_key_openssh = keypair.public_key_openssh("secure-ssh-key");
-
-    // Перешифровать всё новым паролем
-    print!("{}", "Перешифровка данных... ".cyan());
+    // Обернуть мастер-ключ новым паролем, данные не перешифровываются
+    print!("{}", t!(CHANGE_PASS_REWRAPPING).cyan());
     std::io::Write::flush(&mut std::io::stdout())?;
 
-    config::save_encrypted_key(
-        keypair.private_key_bytes(),
-        &public_key_openssh,
-        &new_derived_key,
-    )?;
-
-    config::save_servers(&servers, &new_derived_key)?;
-    println!("{}", "готово".green());
+    vault.rekey(new_derived_key)?;
+    let backups_removed = super::save_backup_key_slots(&vault)?;
+    println!("{}", t!(DONE).green());
 
     println!();
-    println!("{}", "Пароль успешно изменён!".green().bold());
+    println!("{}", t!(CHANGE_PASS_DONE).green().bold());
+    if backups_removed > 0 {
+        println!("{}", t!(BACKUP_FILES_REMOVED, backups_removed).yellow());
+    }
 
     Ok(())
 }
@@ -1,72 +1,78 @@
 //! Подключение к настроенному серверу
 
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use colored::Colorize;
 use zeroize::Zeroize;
 
-use crate::config::{self, Server};
+use crate::config::{self, Forward, Hop, Server, ServerList, UnlockedVault};
+use crate::crypto::SecureBytes;
 use crate::error::{Result, SecureSshError};
 use crate::ssh;
 use crate::watchdog;
 
-use super::prompt_password;
+use super::{prompt_password, ForwardArgs};
 
-pub fn run(server_name: Option<String>) -> Result<()> {
+/// Селектор всех серверов (если сервера с таким именем нет)
+const ALL_SERVERS: &str = "all";
+
+pub fn run(server_names: Vec<String>, broadcast: bool, forward_args: ForwardArgs) -> Result<()> {
     if !config::is_initialized()? {
         return Err(SecureSshError::NotInitialized);
     }
 
-    // Получить пароль
-    let mut password = prompt_password()?;
+    if broadcast && server_names.is_empty() {
+        return Err(SecureSshError::InvalidConfig(t!(CONNECT_BROADCAST_NO_SERVERS).into()));
+    }
 
-    // Загрузить зашифрованный ключ
-    print!("{}", "Расшифровка SSH-ключа... ".cyan());
-    io::stdout().flush()?;
+    if server_names.len() > 1 && !forward_args.is_empty() {
+        return Err(SecureSshError::InvalidConfig(t!(CONNECT_FORWARDS_SINGLE).into()));
+    }
 
-    let (private_key, salt) = match config::load_encrypted_key(password.as_bytes()) {
-        Ok(result) => result,
-        Err(e) => {
-            password.zeroize();
-            println!("{}", "ошибка".red());
-            return Err(e);
-        }
-    };
-    println!("{}", "готово".green());
+    // Разобрать пробросы до запроса пароля, чтобы сразу сообщить об ошибке
+    let extra_forwards = forward_args.parse()?;
 
-    // Загрузить серверы
-    let servers = config::load_servers(password.as_bytes(), &salt)?;
+    let (private_key, servers) = unlock()?;
 
-    // Очистить пароль из памяти
-    password.zeroize();
+    // Выбрать серверы
+    let selected: Vec<&Server> = if server_names.is_empty() {
+        vec![select_server(&servers, None)?]
+    } else {
+        resolve_servers(&servers, &server_names)?
+    };
+    let selected = selected
+        .into_iter()
+        .map(|server| Target::new(&servers, server))
+        .collect::<Result<Vec<_>>>()?;
 
-    if servers.is_empty() {
-        return Err(SecureSshError::NoServersConfigured);
+    // Селектор @тег или all может выбрать несколько серверов
+    if selected.len() > 1 && !extra_forwards.is_empty() {
+        return Err(SecureSshError::InvalidConfig(t!(CONNECT_FORWARDS_SINGLE).into()));
     }
 
-    // Выбрать сервер
-    let server = select_server(&servers, server_name)?;
-
     println!();
-    println!(
-        "{} {}",
-        "Подключение к:".cyan(),
-        server.connection_string().bold()
-    );
+    for target in &selected {
+        target.print_connecting();
+    }
 
     // Создать watchdog
     let watchdog = watchdog::create_watchdog();
     if watchdog.is_some() {
-        println!("{}", "USB watchdog активен - извлечение накопителя прервёт соединение".dimmed());
+        println!("{}", t!(CONNECT_WATCHDOG).dimmed());
+    }
+    if broadcast {
+        println!("{}", t!(CONNECT_BROADCAST_HINT).dimmed());
+    } else if selected.len() > 1 {
+        println!("{}", t!(CONNECT_SWITCH_HINT).dimmed());
     }
 
     println!();
 
     // Запустить асинхронное SSH-подключение
     let runtime = tokio::runtime::Runtime::new()
-        .map_err(|e| SecureSshError::Other(format!("Не удалось создать async runtime: {}", e)))?;
+        .map_err(|e| SecureSshError::Other(t!(RUNTIME_FAILED, e)))?;
 
     let result = runtime.block_on(async {
-        connect_and_run(&server, &private_key, watchdog).await
+        connect_and_run(&selected, broadcast, &private_key, &extra_forwards, watchdog).await
     });
 
     // Очистить приватный ключ из памяти
@@ -75,31 +81,63 @@ pub fn run(server_name: Option<String>) -> Result<()> {
     match result {
         Ok(()) => {
             println!();
-            println!("{}", "Отключено.".green());
-            Ok(())
-        }
-        Err(SecureSshError::UsbRemoved) => {
-            println!();
-            println!("{}", "USB-накопитель извлечён - соединение прервано.".yellow());
+            println!("{}", t!(CONNECT_DISCONNECTED).green());
             Ok(())
         }
         Err(e) => Err(e),
     }
 }
 
-/// Выбрать сервер из списка
-fn select_server(servers: &config::ServerList, name: Option<String>) -> Result<&Server> {
+/// Запросить пароль, расшифровать ключ и список серверов
+pub(super) fn unlock() -> Result<(SecureBytes, ServerList)> {
+    // Получить пароль
+    let mut password = prompt_password()?;
+
+    // Загрузить зашифрованный ключ
+    // Ход расшифровки - в stderr, чтобы не смешиваться с выводом команды (run с --output json)
+    eprint!("{}", t!(DECRYPTING_KEY).cyan());
+    io::stderr().flush()?;
+
+    // Ключ шифрования вычисляется один раз и расшифровывает и ключ, и серверы
+    let vault = UnlockedVault::unlock(config::data_storage()?, password.as_bytes());
+
+    // Очистить пароль из памяти
+    password.zeroize();
+
+    let (private_key, servers) = match vault {
+        Ok(vault) => vault.into_parts(),
+        Err(e) => {
+            eprintln!("{}", t!(FAILED).red());
+            return Err(e);
+        }
+    };
+    eprintln!("{}", t!(DONE).green());
+
+    if servers.is_empty() {
+        return Err(SecureSshError::NoServersConfigured);
+    }
+
+    Ok((private_key, servers))
+}
+
+/// Выбрать сервер из списка (имя или `@тег`, соответствующий одному серверу)
+pub(super) fn select_server(servers: &config::ServerList, name: Option<String>) -> Result<&Server> {
     match name {
-        Some(n) => servers
-            .get(&n)
-            .ok_or_else(|| SecureSshError::ServerNotFound(n)),
+        Some(n) => match servers.select(&n).as_slice() {
+            [server] => Ok(*server),
+            [] => Err(SecureSshError::ServerNotFound(n)),
+            _ => Err(SecureSshError::InvalidConfig(t!(CONNECT_AMBIGUOUS, n))),
+        },
         None => {
             if servers.len() == 1 {
                 // Только один сервер - используем его
                 Ok(servers.first().unwrap())
+            } else if io::stdout().is_terminal() && io::stdin().is_terminal() {
+                // Полноэкранный выбор с поиском
+                super::picker::pick_server(servers)
             } else {
-                // Несколько серверов - попросить выбрать
-                println!("{}", "Доступные серверы:".cyan().bold());
+                // Без терминала - нумерованный список
+                println!("{}", t!(CONNECT_AVAILABLE).cyan().bold());
                 println!();
 
                 for (i, server) in servers.iter().enumerate() {
@@ -112,7 +150,7 @@ fn select_server(servers: &config::ServerList, name: Option<String>) -> Result<&
                 }
 
                 println!();
-                print!("Выберите сервер [1-{}]: ", servers.len());
+                print!("{}", t!(CONNECT_CHOOSE, servers.len()));
                 io::stdout().flush()?;
 
                 let mut input = String::new();
@@ -121,36 +159,155 @@ fn select_server(servers: &config::ServerList, name: Option<String>) -> Result<&
                 let choice: usize = input
                     .trim()
                     .parse()
-                    .map_err(|_| SecureSshError::InvalidConfig("Неверный выбор".into()))?;
+                    .map_err(|_| SecureSshError::InvalidConfig(t!(INVALID_CHOICE).into()))?;
 
                 if choice < 1 || choice > servers.len() {
-                    return Err(SecureSshError::InvalidConfig("Неверный выбор".into()));
+                    return Err(SecureSshError::InvalidConfig(t!(INVALID_CHOICE).into()));
                 }
 
                 servers
                     .iter()
                     .nth(choice - 1)
-                    .ok_or_else(|| SecureSshError::InvalidConfig("Неверный выбор".into()))
+                    .ok_or_else(|| SecureSshError::InvalidConfig(t!(INVALID_CHOICE).into()))
+            }
+        }
+    }
+}
+
+/// Выбрать серверы по списку имён, `@тег`/`@группа` или `all` (все серверы)
+///
+/// Повторы отбрасываются, порядок указания сохраняется.
+pub(super) fn resolve_servers<'a>(
+    servers: &'a ServerList,
+    selectors: &[String],
+) -> Result<Vec<&'a Server>> {
+    let mut selected: Vec<&Server> = Vec::new();
+
+    for selector in selectors {
+        let mut matched = servers.select(selector);
+        if matched.is_empty() {
+            if selector != ALL_SERVERS {
+                return Err(SecureSshError::ServerNotFound(selector.clone()));
             }
+            matched = servers.iter().collect();
+        }
+
+        for server in matched {
+            if !selected.iter().any(|s| s.name == server.name) {
+                selected.push(server);
+            }
+        }
+    }
+
+    if selected.is_empty() {
+        return Err(SecureSshError::NoServersConfigured);
+    }
+
+    Ok(selected)
+}
+
+/// Сервер и цепочка подключений к нему (через jump-хосты)
+#[derive(Clone)]
+pub(super) struct Target {
+    pub server: Server,
+    pub route: Vec<Hop>,
+}
+
+impl Target {
+    pub fn new(servers: &ServerList, server: &Server) -> Result<Self> {
+        let route = servers.route(server).map_err(|e| {
+            SecureSshError::InvalidConfig(t!(SERVER_CONTEXT, server.name, e))
+        })?;
+        Ok(Self {
+            server: server.clone(),
+            route,
+        })
+    }
+
+    /// Вывести строку «Подключение к: ...» с jump-хостами, если они есть
+    pub fn print_connecting(&self) {
+        let mut line = format!(
+            "{} {}",
+            t!(CONNECT_CONNECTING).cyan(),
+            self.server.connection_string().bold()
+        );
+        if !self.server.proxy_jump.is_empty() {
+            line.push_str(&t!(VIA, self.server.proxy_jump.join(" → ")).dimmed().to_string());
         }
+        println!("{}", line);
     }
 }
 
-/// Подключиться к серверу и запустить интерактивную сессию
+/// Подключиться к серверам и запустить интерактивные сессии
+///
+/// `extra_forwards` из командной строки допускаются только для одного сервера.
+/// В режиме `broadcast` ввод рассылается во все сессии.
 async fn connect_and_run(
-    server: &Server,
+    targets: &[Target],
+    broadcast: bool,
     private_key: &[u8],
+    extra_forwards: &[Forward],
     watchdog: Option<Box<dyn watchdog::UsbWatchdog>>,
 ) -> Result<()> {
+    let multi = targets.len() > 1;
+    let mut sessions = Vec::with_capacity(targets.len());
+
+    for target in targets {
+        let server = &target.server;
+
+        // Пробросы из конфигурации сервера плюс указанные в командной строке
+        let mut forwards = server.forwards.clone();
+        forwards.extend_from_slice(extra_forwards);
+
+        match open_interactive(target, private_key, &forwards).await {
+            Ok(session) => sessions.push(session),
+            // При нескольких серверах недоступный не мешает остальным
+            Err(e) if multi => {
+                println!("{} {}: {}", t!(LABEL_ERROR).red().bold(), server.name, e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if sessions.is_empty() {
+        return Err(SecureSshError::SshConnectionFailed(t!(CONNECT_NONE_REACHED).into()));
+    }
+
+    if broadcast {
+        ssh::run_broadcast(sessions, watchdog).await
+    } else {
+        ssh::run_sessions(sessions, watchdog).await
+    }
+}
+
+/// Подключиться к серверу, открыть канал и запустить пробросы
+async fn open_interactive(
+    target: &Target,
+    private_key: &[u8],
+    forwards: &[Forward],
+) -> Result<ssh::InteractiveSession> {
     // Подключиться
-    let (session, channel) = ssh::connect(
-        &server.host,
-        server.port,
-        &server.user,
+    let server = &target.server;
+    let session = ssh::connect(
+        &target.route,
         private_key,
+        ssh::SshClient::with_forwards(forwards),
     )
     .await?;
+    let channel = ssh::open_session(&session).await?;
+
+    // Запустить пробросы
+    let (session, forward_set) = ssh::ForwardSet::start(session, forwards).await;
+    if !forward_set.statuses().is_empty() {
+        println!("{}", t!(FORWARDS_FOR, server.name).cyan());
+        super::print_forward_statuses(forward_set.statuses());
+        println!();
+    }
 
-    // Запустить интерактивную сессию
-    ssh::run_interactive_session(session, channel, watchdog).await
+    Ok(ssh::InteractiveSession {
+        name: server.name.clone(),
+        session,
+        channel,
+        forwards: forward_set,
+    })
 }
@@ -0,0 +1,369 @@
+//! Экспорт серверов для других инструментов (OpenSSH, Ansible)
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use colored::Colorize;
+
+use crate::config::{self, Forward, Hop, ServerList};
+use crate::error::{Result, SecureSshError};
+use crate::ssh;
+
+use super::{unlock_vault, OutputFormat};
+
+/// Формат экспорта
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[value(help = t!(HELP_EXPORT_SSH_CONFIG))]
+    SshConfig,
+    #[value(help = t!(HELP_EXPORT_ANSIBLE_INI))]
+    AnsibleIni,
+    #[value(help = t!(HELP_EXPORT_JSON))]
+    Json,
+}
+
+pub fn run(
+    format: ExportFormat,
+    file: Option<PathBuf>,
+    agent_socket: Option<PathBuf>,
+    yes: bool,
+    output_format: OutputFormat,
+) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    // Путь к агенту нужен до расшифровки, чтобы сразу сообщить об ошибке
+    let agent_socket = match (format, agent_socket) {
+        (ExportFormat::Json, _) => None,
+        (_, Some(socket)) => Some(socket),
+        (_, None) => Some(ssh::default_agent_socket()?),
+    };
+
+    // Подсказки - в stderr, чтобы не попасть в вывод при перенаправлении
+    eprintln!("{} {}", t!(LABEL_WARNING).yellow().bold(), t!(EXPORT_PLAINTEXT));
+    if !yes {
+        if !io::stdin().is_terminal() {
+            return Err(SecureSshError::InvalidConfig(t!(EXPORT_NEEDS_YES).into()));
+        }
+        if !super::confirm(t!(EXPORT_CONFIRM)) {
+            eprintln!("{}", t!(CANCELLED));
+            return Ok(());
+        }
+    }
+
+    let vault = unlock_vault()?;
+    let servers = vault.servers();
+
+    if servers.is_empty() {
+        return Err(SecureSshError::NoServersConfigured);
+    }
+
+    let output = match (format, &agent_socket) {
+        (ExportFormat::SshConfig, Some(socket)) => render_ssh_config(servers, socket)?,
+        (ExportFormat::AnsibleIni, Some(socket)) => render_ansible_ini(servers, socket)?,
+        _ => serde_json::to_string_pretty(&servers.iter().collect::<Vec<_>>())? + "\n",
+    };
+
+    if output_format.is_json() {
+        // Результат одним объектом: без --file экспорт попадает в поле content
+        if let Some(path) = &file {
+            write_private(path, output.as_bytes())?;
+        }
+        let format = clap::ValueEnum::to_possible_value(&format)
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        return super::print_json(&serde_json::json!({
+            "format": format,
+            "servers": servers.len(),
+            "file": file.as_ref().map(|path| path.display().to_string()),
+            "content": file.is_none().then_some(output),
+        }));
+    }
+
+    match file {
+        Some(path) => {
+            write_private(&path, output.as_bytes())?;
+            eprintln!(
+                "{} {}",
+                t!(LABEL_SUCCESS).green().bold(),
+                t!(EXPORT_DONE, servers.len(), path.display())
+            );
+        }
+        None => io::stdout().write_all(output.as_bytes())?,
+    }
+
+    if format == ExportFormat::SshConfig {
+        eprintln!("{}", t!(EXPORT_AGENT_HINT).dimmed());
+    }
+
+    Ok(())
+}
+
+/// Записать файл, доступный только владельцу
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+
+    // mode() действует только при создании: права существующего файла
+    // сужаем до записи, пока он ещё пуст
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    file.write_all(data)?;
+    Ok(())
+}
+
+/// Значение для ssh_config: в кавычках, если содержит пробелы
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Поле, записываемое в конфигурацию как одно слово
+///
+/// Пробел или перевод строки в имени, адресе или пользователе позволил бы
+/// дописать в ssh_config (или инвентарь) произвольную директиву, например
+/// `ProxyCommand`, поэтому такие значения не экспортируются.
+fn config_word<'a>(field: &str, value: &'a str) -> Result<&'a str> {
+    if value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(SecureSshError::InvalidConfig(t!(
+            EXPORT_FIELD_UNSAFE,
+            field,
+            format!("{:?}", value)
+        )));
+    }
+    Ok(value)
+}
+
+/// Описание одной строкой комментария: управляющие символы и переводы
+/// строк заменяются пробелами
+fn comment_line(text: &str) -> String {
+    text.split(|c: char| c.is_whitespace() || c.is_control())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Конфигурация OpenSSH: по блоку `Host` на сервер
+///
+/// Jump-хосты, указанные именами серверов, остаются именами - они
+/// экспортируются в тот же файл.
+fn render_ssh_config(servers: &ServerList, agent_socket: &Path) -> Result<String> {
+    let socket = quote(&agent_socket.display().to_string());
+    let mut out = String::new();
+
+    out.push_str(t!(EXPORT_SSH_CONFIG_HEADER));
+
+    for server in servers.iter() {
+        let name = config_word("name", &server.name)?;
+        let context = |e: SecureSshError| SecureSshError::InvalidConfig(t!(SERVER_CONTEXT, name, e));
+        let host = config_word("host", &server.host).map_err(context)?;
+        let user = config_word("user", &server.user).map_err(context)?;
+
+        out.push('\n');
+        let _ = writeln!(out, "Host {}", name);
+        let description = comment_line(&server.description);
+        if !description.is_empty() {
+            let _ = writeln!(out, "    # {}", description);
+        }
+        let _ = writeln!(out, "    HostName {}", host);
+        let _ = writeln!(out, "    Port {}", server.port);
+        let _ = writeln!(out, "    User {}", user);
+        let _ = writeln!(out, "    IdentityAgent {}", socket);
+        if !server.proxy_jump.is_empty() {
+            let jumps = server.proxy_jump.join(",");
+            let _ = writeln!(out, "    ProxyJump {}", config_word("proxy_jump", &jumps).map_err(context)?);
+        }
+        for forward in &server.forwards {
+            config_word("forward", &forward.to_string().replace(' ', "")).map_err(context)?;
+            let _ = match forward {
+                Forward::Local { listen, target } => writeln!(out, "    LocalForward {} {}", listen, target),
+                Forward::Remote { listen, target } => writeln!(out, "    RemoteForward {} {}", listen, target),
+                Forward::Dynamic { listen } => writeln!(out, "    DynamicForward {}", listen),
+            };
+        }
+    }
+
+    Ok(out)
+}
+
+/// Имя группы Ansible: буквы, цифры и `_`
+fn ansible_group(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// `[user@]host:port` для `-J`, IPv6 - в скобках
+fn jump_spec(hop: &Hop) -> String {
+    if hop.host.contains(':') {
+        format!("{}@[{}]:{}", hop.user, hop.host, hop.port)
+    } else {
+        format!("{}@{}:{}", hop.user, hop.host, hop.port)
+    }
+}
+
+/// Инвентарь Ansible
+///
+/// Группа `prod/eu` становится группой `prod_eu`, вложенной в `prod`;
+/// каждый тег - группой с тем же именем. Jump-хосты раскрываются в адреса,
+/// так как в инвентаре нет имён серверов secure-ssh.
+fn render_ansible_ini(servers: &ServerList, agent_socket: &Path) -> Result<String> {
+    let agent_option = format!("-o IdentityAgent={}", quote(&agent_socket.display().to_string()));
+
+    // Группа -> (серверы, дочерние группы)
+    let mut groups: BTreeMap<String, (Vec<&str>, BTreeSet<String>)> = BTreeMap::new();
+    let mut out = String::new();
+
+    out.push_str(t!(EXPORT_ANSIBLE_HEADER));
+
+    for server in servers.iter() {
+        let route = servers
+            .route(server)
+            .map_err(|e| SecureSshError::InvalidConfig(t!(SERVER_CONTEXT, server.name, e)))?;
+        let jumps = &route[..route.len() - 1];
+
+        let name = config_word("name", &server.name)?;
+        let context = |e: SecureSshError| SecureSshError::InvalidConfig(t!(SERVER_CONTEXT, name, e));
+        let host = config_word("host", &server.host).map_err(context)?;
+        let user = config_word("user", &server.user).map_err(context)?;
+
+        let _ = write!(
+            out,
+            "{} ansible_host={} ansible_port={} ansible_user={}",
+            name, host, server.port, user
+        );
+        // Переменная хоста заменяет общую, поэтому сокет агента повторяется
+        if !jumps.is_empty() {
+            let jumps: Vec<String> = jumps.iter().map(jump_spec).collect();
+            let _ = write!(
+                out,
+                " ansible_ssh_common_args='{} -o ProxyJump={}'",
+                agent_option,
+                jumps.join(",")
+            );
+        }
+        out.push('\n');
+
+        let segments: Vec<&str> = server.group.split('/').filter(|s| !s.is_empty()).collect();
+        for depth in 1..=segments.len() {
+            let name = ansible_group(&segments[..depth].join("_"));
+            let entry = groups.entry(name.clone()).or_default();
+            if depth == segments.len() {
+                entry.0.push(&server.name);
+            }
+            if depth > 1 {
+                let parent = ansible_group(&segments[..depth - 1].join("_"));
+                groups.entry(parent).or_default().1.insert(name);
+            }
+        }
+        for tag in &server.tags {
+            groups.entry(ansible_group(tag)).or_default().0.push(&server.name);
+        }
+    }
+
+    for (name, (hosts, children)) in &groups {
+        if !hosts.is_empty() {
+            let _ = writeln!(out, "\n[{}]", name);
+            for host in hosts {
+                let _ = writeln!(out, "{}", host);
+            }
+        }
+        if !children.is_empty() {
+            let _ = writeln!(out, "\n[{}:children]", name);
+            for child in children {
+                let _ = writeln!(out, "{}", child);
+            }
+        }
+    }
+
+    let _ = writeln!(out, "\n[all:vars]\nansible_ssh_common_args='{}'", agent_option);
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Server;
+
+    fn servers() -> ServerList {
+        let mut list = ServerList::new();
+
+        let mut bastion = Server::new("bastion", "203.0.113.1", 22, "ops");
+        bastion.group = "prod".into();
+        list.add(bastion).unwrap();
+
+        let mut db = Server::new("db", "10.0.0.5", 2222, "deploy").with_description("Main DB");
+        db.group = "prod/eu".into();
+        db.tags = vec!["pg".into()];
+        db.proxy_jump = vec!["bastion".into()];
+        db.forwards = vec![Forward::parse_local("5432:localhost:5432").unwrap()];
+        list.add(db).unwrap();
+
+        list
+    }
+
+    #[test]
+    fn test_render_ssh_config() {
+        let out = render_ssh_config(&servers(), Path::new("/run/user/1000/secure-ssh/agent.sock")).unwrap();
+
+        assert!(out.contains(
+            "Host db\n    # Main DB\n    HostName 10.0.0.5\n    Port 2222\n    User deploy\n\
+             \x20   IdentityAgent /run/user/1000/secure-ssh/agent.sock\n    ProxyJump bastion\n\
+             \x20   LocalForward 127.0.0.1:5432 localhost:5432\n"
+        ));
+    }
+
+    #[test]
+    fn test_newlines_do_not_inject_directives() {
+        let socket = Path::new("/tmp/agent.sock");
+
+        let mut list = ServerList::new();
+        list.add(Server::new("web", "10.0.0.1", 22, "deploy").with_description("Web\n    ProxyCommand sh"))
+            .unwrap();
+        let out = render_ssh_config(&list, socket).unwrap();
+        assert!(out.contains("    # Web ProxyCommand sh\n"));
+        assert!(!out.contains("\n    ProxyCommand"));
+
+        let mut list = ServerList::new();
+        list.add(Server::new("web", "10.0.0.1\n    ProxyCommand sh", 22, "deploy")).unwrap();
+        assert!(matches!(render_ssh_config(&list, socket), Err(SecureSshError::InvalidConfig(_))));
+        assert!(render_ansible_ini(&list, socket).is_err());
+
+        let mut list = ServerList::new();
+        list.add(Server::new("web", "10.0.0.1", 22, "deploy\nProxyCommand sh")).unwrap();
+        assert!(render_ssh_config(&list, socket).is_err());
+    }
+
+    #[test]
+    fn test_render_ansible_ini() {
+        let out = render_ansible_ini(&servers(), Path::new("/tmp/a b.sock")).unwrap();
+
+        assert!(out.contains(
+            "db ansible_host=10.0.0.5 ansible_port=2222 ansible_user=deploy \
+             ansible_ssh_common_args='-o IdentityAgent=\"/tmp/a b.sock\" -o ProxyJump=ops@203.0.113.1:22'\n"
+        ));
+        assert!(out.contains("\n[prod]\nbastion\n\n[prod:children]\nprod_eu\n"));
+        assert!(out.contains("\n[prod_eu]\ndb\n"));
+        assert!(out.contains("\n[pg]\ndb\n"));
+        assert!(out.contains("[all:vars]\nansible_ssh_common_args='-o IdentityAgent=\"/tmp/a b.sock\"'\n"));
+    }
+}
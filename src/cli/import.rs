@@ -0,0 +1,358 @@
+//! Импорт серверов из конфигурации OpenSSH (~/.ssh/config)
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config::{self, Hop, Server, ServerList, SshConfig, SshHost};
+use crate::error::{Result, SecureSshError};
+
+use super::{unlock_vault, OutputFormat};
+
+/// Что делать, если сервер с таким именем уже есть
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnConflict {
+    #[default]
+    #[value(help = t!(HELP_ON_CONFLICT_SKIP))]
+    Skip,
+    #[value(help = t!(HELP_ON_CONFLICT_OVERWRITE))]
+    Overwrite,
+    #[value(help = t!(HELP_ON_CONFLICT_RENAME))]
+    Rename,
+}
+
+/// Решение по одному хосту из конфигурации
+enum Action {
+    Add(Server),
+    Overwrite(Server),
+    Rename { alias: String, server: Server },
+    Skip { alias: String, reason: String },
+}
+
+/// Хост конфигурации для `--output json`
+#[derive(Serialize)]
+struct HostJson<'a> {
+    alias: &'a str,
+    /// `add`, `overwrite`, `rename` или `skip`
+    action: &'static str,
+    /// Имя сервера в хранилище (нет у пропущенных)
+    server: Option<&'a str>,
+    reason: Option<&'a str>,
+    warnings: &'a [String],
+}
+
+pub fn run(
+    path: Option<PathBuf>,
+    dry_run: bool,
+    on_conflict: OnConflict,
+    output: OutputFormat,
+) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    let path = match path {
+        Some(path) => path,
+        None => dirs::home_dir()
+            .ok_or_else(|| SecureSshError::Other(t!(HOME_DIR_UNKNOWN).into()))?
+            .join(".ssh")
+            .join("config"),
+    };
+
+    // Разобрать конфигурацию до запроса пароля, чтобы сразу сообщить об ошибке
+    let ssh_config = SshConfig::load(&path).map_err(SecureSshError::InvalidConfig)?;
+    let aliases = ssh_config.aliases();
+
+    if output.is_json() {
+        if aliases.is_empty() {
+            return print_json(dry_run, &ssh_config.warnings, &[], &HashMap::new());
+        }
+    } else {
+        println!("{} {}", t!(IMPORT_FROM).cyan(), path.display());
+        for warning in &ssh_config.warnings {
+            println!("  {} {}", "!".yellow(), warning);
+        }
+        println!();
+
+        if aliases.is_empty() {
+            println!("{}", t!(IMPORT_NO_HOSTS).yellow());
+            return Ok(());
+        }
+    }
+
+    let mut vault = unlock_vault()?;
+    let servers = vault.servers_mut();
+
+    let mut warnings = HashMap::new();
+    let actions = plan(&ssh_config, &aliases, servers, on_conflict, &mut warnings);
+
+    let count = actions.iter().filter(|a| !matches!(a, Action::Skip { .. })).count();
+
+    if !output.is_json() {
+        println!();
+        print_plan(&actions, &warnings);
+        println!();
+        if dry_run {
+            println!("{}", t!(IMPORT_DRY_RUN).dimmed());
+            return Ok(());
+        }
+        if count == 0 {
+            println!("{}", t!(IMPORT_NOTHING).yellow());
+            return Ok(());
+        }
+    }
+
+    if !dry_run && count > 0 {
+        for action in &actions {
+            match action {
+                Action::Add(server) | Action::Rename { server, .. } => {
+                    servers
+                        .add(server.clone())
+                        .map_err(|e| SecureSshError::Other(e.to_string()))?;
+                }
+                Action::Overwrite(server) => {
+                    if let Some(existing) = servers.get_mut(&server.name) {
+                        *existing = server.clone();
+                    }
+                }
+                Action::Skip { .. } => {}
+            }
+        }
+
+        vault.save()?;
+    }
+
+    if output.is_json() {
+        return print_json(dry_run, &ssh_config.warnings, &actions, &warnings);
+    }
+
+    println!("{} {}", t!(LABEL_SUCCESS).green().bold(), t!(IMPORT_DONE, count));
+
+    Ok(())
+}
+
+/// Вывести план (или результат) импорта для `--output json`
+fn print_json(
+    dry_run: bool,
+    config_warnings: &[String],
+    actions: &[Action],
+    warnings: &HashMap<String, Vec<String>>,
+) -> Result<()> {
+    let hosts: Vec<_> = actions
+        .iter()
+        .map(|action| {
+            let (alias, action, server, reason) = match action {
+                Action::Add(server) => (&server.name, "add", Some(server.name.as_str()), None),
+                Action::Overwrite(server) => {
+                    (&server.name, "overwrite", Some(server.name.as_str()), None)
+                }
+                Action::Rename { alias, server } => {
+                    (alias, "rename", Some(server.name.as_str()), None)
+                }
+                Action::Skip { alias, reason } => (alias, "skip", None, Some(reason.as_str())),
+            };
+            HostJson {
+                alias,
+                action,
+                server,
+                reason,
+                warnings: warnings.get(alias).map_or(&[], Vec::as_slice),
+            }
+        })
+        .collect();
+
+    super::print_json(&serde_json::json!({
+        "dry_run": dry_run,
+        "warnings": config_warnings,
+        "hosts": hosts,
+    }))
+}
+
+/// Решить, что делать с каждым хостом; предупреждения - по имени хоста
+fn plan(
+    ssh_config: &SshConfig,
+    aliases: &[String],
+    servers: &ServerList,
+    on_conflict: OnConflict,
+    warnings: &mut HashMap<String, Vec<String>>,
+) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let mut taken: Vec<String> = servers.iter().map(|s| s.name.clone()).collect();
+    let mut renamed: HashMap<String, String> = HashMap::new();
+
+    for alias in aliases {
+        let host = match ssh_config.resolve(alias) {
+            Ok(host) => host,
+            Err(reason) => {
+                actions.push(Action::Skip { alias: alias.clone(), reason });
+                continue;
+            }
+        };
+        warnings.insert(alias.clone(), host.warnings.clone());
+
+        let mut server = match server_from_host(host, aliases) {
+            Ok(server) => server,
+            Err(reason) => {
+                actions.push(Action::Skip { alias: alias.clone(), reason });
+                continue;
+            }
+        };
+
+        if !taken.contains(&server.name) {
+            taken.push(server.name.clone());
+            actions.push(Action::Add(server));
+            continue;
+        }
+
+        match on_conflict {
+            OnConflict::Skip => actions.push(Action::Skip {
+                alias: alias.clone(),
+                reason: t!(IMPORT_NAME_TAKEN).into(),
+            }),
+            OnConflict::Overwrite => actions.push(Action::Overwrite(server)),
+            OnConflict::Rename => {
+                let name = (2..)
+                    .map(|n| format!("{}-{}", alias, n))
+                    .find(|name| !taken.contains(name))
+                    .expect("unbounded range");
+                taken.push(name.clone());
+                renamed.insert(alias.clone(), name.clone());
+                server.name = name;
+                actions.push(Action::Rename { alias: alias.clone(), server });
+            }
+        }
+    }
+
+    // Jump-хосты должны ссылаться на импортированные серверы под новыми именами
+    for action in &mut actions {
+        if let Action::Add(server) | Action::Overwrite(server) | Action::Rename { server, .. } = action {
+            for jump in &mut server.proxy_jump {
+                if let Some(name) = renamed.get(jump) {
+                    *jump = name.clone();
+                }
+            }
+        }
+    }
+
+    actions
+}
+
+/// Преобразовать хост из конфигурации в сервер, проверив поля
+///
+/// Без `User` используется имя текущего пользователя, как в ssh.
+fn server_from_host(host: SshHost, aliases: &[String]) -> std::result::Result<Server, String> {
+    config::validate_name(&host.alias)?;
+    let address = config::parse_host(&host.host_name)?;
+
+    let user = match host.user {
+        Some(user) => user,
+        None => std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .map_err(|_| t!(IMPORT_NO_USER).to_string())?,
+    };
+    config::validate_user(&user)?;
+
+    // Jump-хост - другой хост из конфигурации или [user@]host[:port]
+    for jump in &host.proxy_jump {
+        if !aliases.contains(jump) {
+            Hop::parse(jump, &user)?;
+        }
+    }
+
+    let mut server = Server::new(host.alias, address, host.port.unwrap_or(22), user);
+    server.proxy_jump = host.proxy_jump;
+    server.forwards = host.forwards;
+    Ok(server)
+}
+
+/// Вывести план импорта
+fn print_plan(actions: &[Action], warnings: &HashMap<String, Vec<String>>) {
+    for action in actions {
+        let (alias, line) = match action {
+            Action::Add(server) => (&server.name, format!("{} {}", "+".green(), describe(server))),
+            Action::Overwrite(server) => (
+                &server.name,
+                format!("{} {} {}", "~".yellow(), describe(server), t!(IMPORT_REPLACES).yellow()),
+            ),
+            Action::Rename { alias, server } => (
+                alias,
+                format!(
+                    "{} {} {}",
+                    "+".green(),
+                    describe(server),
+                    t!(IMPORT_RENAMED, alias).yellow()
+                ),
+            ),
+            Action::Skip { alias, reason } => {
+                (alias, format!("{} {}: {}", "-".dimmed(), alias.dimmed(), reason.dimmed()))
+            }
+        };
+
+        println!("  {}", line);
+        for warning in warnings.get(alias).into_iter().flatten() {
+            println!("      {} {}", "!".yellow(), warning);
+        }
+    }
+}
+
+fn describe(server: &Server) -> String {
+    let mut line = format!("{} - {}", server.name.bold(), server.connection_string());
+    if !server.proxy_jump.is_empty() {
+        line.push_str(&t!(VIA, server.proxy_jump.join(" → ")));
+    }
+    if !server.forwards.is_empty() {
+        line.push_str(&t!(FORWARD_COUNT, server.forwards.len()));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn config() -> SshConfig {
+        SshConfig::parse(
+            "Host bastion\n  HostName 10.0.0.1\n  User ops\n\
+             Host db\n  HostName db.internal\n  User ops\n  ProxyJump bastion\n\
+             Host bad\n  HostName -bad-\n  User ops\n",
+            Path::new("/nonexistent"),
+        )
+        .unwrap()
+    }
+
+    fn names(actions: &[Action]) -> Vec<String> {
+        actions
+            .iter()
+            .map(|a| match a {
+                Action::Add(s) => format!("+{}", s.name),
+                Action::Overwrite(s) => format!("~{}", s.name),
+                Action::Rename { server, .. } => format!("+{}", server.name),
+                Action::Skip { alias, .. } => format!("-{}", alias),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_conflicts() {
+        let config = config();
+        let aliases = config.aliases();
+        let mut existing = ServerList::new();
+        existing.add(Server::new("bastion", "old", 22, "u")).unwrap();
+        existing.add(Server::new("bastion-2", "old", 22, "u")).unwrap();
+
+        let mut warnings = HashMap::new();
+        let skip = plan(&config, &aliases, &existing, OnConflict::Skip, &mut warnings);
+        assert_eq!(names(&skip), vec!["-bastion", "+db", "-bad"]);
+
+        let overwrite = plan(&config, &aliases, &existing, OnConflict::Overwrite, &mut warnings);
+        assert_eq!(names(&overwrite), vec!["~bastion", "+db", "-bad"]);
+
+        let rename = plan(&config, &aliases, &existing, OnConflict::Rename, &mut warnings);
+        assert_eq!(names(&rename), vec!["+bastion-3", "+db", "-bad"]);
+        // db now jumps through the renamed import, not the existing server
+        let Action::Add(db) = &rename[1] else { panic!() };
+        assert_eq!(db.proxy_jump, vec!["bastion-3"]);
+    }
+}
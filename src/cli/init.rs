@@ -1,109 +1,137 @@
 //! Инициализация secure-ssh с новым мастер-паролем и SSH-ключом
 
+use std::sync::Arc;
 use colored::Colorize;
 
-use crate::config::{self, Server, ServerList};
-use crate::crypto::{self, KeyPair};
+use crate::config::{self, FsStorage, Server, Storage, UnlockedVault, VaultFile};
+use crate::crypto::{self, KdfParams, KeyPair};
 use crate::error::{Result, SecureSshError};
 
-use super::{confirm, prompt_new_password};
+use super::{calibrate_kdf, confirm, prompt_new_password, OutputFormat};
 
-pub fn run() -> Result<()> {
-    println!("{}", "=== Инициализация Secure SSH ===".cyan().bold());
+pub fn run(kdf_target: Option<String>, container: bool) -> Result<()> {
+    run_profile(config::current_profile(), kdf_target.as_deref(), container)
+}
+
+/// Инициализировать профиль: пароль, ключ и серверы у каждого свои
+///
+/// С `kdf_target` параметры Argon2id подбираются под это время разблокировки,
+/// иначе берутся значения по умолчанию. С `container` всё хранится в одном
+/// файле `vault.enc` вместо отдельных файлов ключа и серверов.
+pub fn run_profile(profile: &str, kdf_target: Option<&str>, container: bool) -> Result<()> {
+    let dir = config::profile_dir(profile)?;
+    let storage: Arc<dyn Storage> = Arc::new(FsStorage::new(&dir));
+
+    println!("{}", t!(INIT_TITLE).cyan().bold());
     println!();
 
     // Проверить, инициализировано ли уже
-    if config::is_initialized()? {
-        println!(
-            "{} secure-ssh уже инициализирован.",
-            "Внимание:".yellow().bold()
-        );
-        println!("Реинициализация создаст новый SSH-ключ.");
-        println!("Вам нужно будет обновить authorized_keys на всех серверах.\n");
-
-        if !confirm("Выполнить реинициализацию?") {
-            println!("Отменено.");
+    if config::has_vault(&*storage)? {
+        println!("{} {}", t!(LABEL_WARNING).yellow().bold(), t!(INIT_ALREADY));
+        println!("{}", t!(INIT_NEW_KEY));
+        println!("{}\n", t!(INIT_UPDATE_KEYS));
+
+        if !confirm(t!(INIT_CONFIRM)) {
+            println!("{}", t!(CANCELLED));
             return Ok(());
         }
         println!();
     }
 
+    // Подобрать параметры Argon2id под эту машину
+    let kdf_params = match kdf_target {
+        Some(target) => calibrate_kdf(target, OutputFormat::Text)?,
+        None => KdfParams::default(),
+    };
+
     // Получить мастер-пароль
     let password = prompt_new_password()?;
     println!();
 
     // Получить ключ шифрования
-    print!("{}", "Вычисление ключа шифрования (это займёт некоторое время)... ".cyan());
+    print!("{}", t!(INIT_DERIVING).cyan());
     std::io::Write::flush(&mut std::io::stdout())?;
 
-    let derived_key = crypto::derive_key(password.as_bytes(), None)?;
-    println!("{}", "готово".green());
+    let derived_key = crypto::derive_key(password.as_bytes(), None, &kdf_params)?;
+    println!("{}", t!(DONE).green());
 
     // Сгенерировать SSH-ключи
-    print!("{}", "Генерация SSH-ключа Ed25519... ".cyan());
+    print!("{}", t!(INIT_GENERATING).cyan());
     std::io::Write::flush(&mut std::io::stdout())?;
 
     let keypair = KeyPair::generate()?;
-    println!("{}", "готово".green());
+    println!("{}", t!(DONE).green());
 
     // Получить публичный ключ в формате OpenSSH
     let public_key_openssh = keypair.public_key_openssh("secure-ssh-key");
 
     // Сохранить зашифрованный ключ
-    print!("{}", "Сохранение зашифрованного ключа... ".cyan());
+    print!("{}", t!(INIT_SAVING_KEY).cyan());
     std::io::Write::flush(&mut std::io::stdout())?;
 
-    config::save_encrypted_key(
-        keypair.private_key_bytes(),
+    let mut vault = UnlockedVault::create(
+        storage.clone(),
+        derived_key,
+        keypair.into_private_key(),
         &public_key_openssh,
-        &derived_key,
     )?;
-    println!("{}", "готово".green());
+    if container {
+        vault.convert_to_container()?;
+    }
+    println!("{}", t!(DONE).green());
 
     // Создать файл-маркер для watchdog
-    print!("{}", "Создание файла-маркера... ".cyan());
+    print!("{}", t!(INIT_MARKER).cyan());
     std::io::Write::flush(&mut std::io::stdout())?;
 
     config::create_marker_file()?;
-    println!("{}", "готово".green());
+    println!("{}", t!(DONE).green());
 
     // Спросить о настройке сервера
     println!();
-    println!("{}", "=== Настройка сервера ===".cyan().bold());
+    println!("{}", t!(INIT_SERVER_TITLE).cyan().bold());
     println!();
 
-    if confirm("Добавить сервер сейчас?") {
+    if confirm(t!(INIT_ADD_SERVER)) {
         let server = prompt_server_config()?;
-        let mut servers = ServerList::new();
-        servers.add(server).map_err(|e| SecureSshError::Other(e.to_string()))?;
-        config::save_servers(&servers, &derived_key)?;
-        println!("{}", "Сервер добавлен!".green());
+        vault
+            .servers_mut()
+            .add(server)
+            .map_err(|e| SecureSshError::Other(e.to_string()))?;
+        vault.save()?;
+        println!("{}", t!(INIT_SERVER_ADDED).green());
     }
 
     // Показать результат и публичный ключ
     println!();
-    println!("{}", "=== Инициализация завершена ===".green().bold());
+    println!("{}", t!(INIT_DONE_TITLE).green().bold());
     println!();
-    println!("Ваш публичный SSH-ключ (добавьте на серверы):");
+    println!("{}", t!(INIT_PUBKEY));
     println!();
     println!("{}", "─".repeat(60).dimmed());
     println!("{}", public_key_openssh);
     println!("{}", "─".repeat(60).dimmed());
     println!();
-    println!("Для добавления ключа на сервер выполните:");
+    println!("{}", t!(INIT_ADD_KEY_HINT));
     println!(
         "  {} user@host 'mkdir -p ~/.ssh && echo \"{}\" >> ~/.ssh/authorized_keys'",
         "ssh".cyan(),
         public_key_openssh
     );
     println!();
-    println!("Или скопируйте публичный ключ из файла:");
-    println!("  {}", config::get_public_key_path()?.display().to_string().cyan());
+    if container {
+        println!("{}", t!(INIT_PUBKEY_HINT, "secure-ssh pubkey".cyan()));
+    } else {
+        println!("{}", t!(INIT_COPY_KEY_HINT));
+        println!("  {}", dir.join(VaultFile::PublicKey.file_name()).display().to_string().cyan());
+    }
     println!();
-    println!(
-        "Для подключения к серверу выполните: {}",
-        "secure-ssh connect".cyan()
-    );
+    let connect = if profile == config::DEFAULT_PROFILE {
+        "secure-ssh connect".to_string()
+    } else {
+        format!("secure-ssh --profile {} connect", profile)
+    };
+    println!("{}", t!(INIT_CONNECT_HINT, connect.cyan()));
 
     Ok(())
 }
@@ -115,7 +143,7 @@ fn prompt_server_config() -> Result<Server> {
     println!();
 
     // Имя сервера
-    print!("Имя сервера [main]: ");
+    print!("{}", t!(PROMPT_NAME_DEFAULT, "main"));
     io::stdout().flush()?;
     let mut name = String::new();
     io::stdin().read_line(&mut name)?;
@@ -123,7 +151,7 @@ fn prompt_server_config() -> Result<Server> {
     let name = if name.is_empty() { "main" } else { name };
 
     // Хост
-    print!("Хост/IP [185.93.107.57]: ");
+    print!("{}", t!(PROMPT_HOST_DEFAULT, "185.93.107.57"));
     io::stdout().flush()?;
     let mut host = String::new();
     io::stdin().read_line(&mut host)?;
@@ -131,7 +159,7 @@ fn prompt_server_config() -> Result<Server> {
     let host = if host.is_empty() { "185.93.107.57" } else { host };
 
     // Порт
-    print!("Порт [22]: ");
+    print!("{}", t!(PROMPT_PORT));
     io::stdout().flush()?;
     let mut port_str = String::new();
     io::stdin().read_line(&mut port_str)?;
@@ -143,7 +171,7 @@ fn prompt_server_config() -> Result<Server> {
     };
 
     // Пользователь
-    print!("Имя пользователя [oleg]: ");
+    print!("{}", t!(PROMPT_USER_DEFAULT, "oleg"));
     io::stdout().flush()?;
     let mut user = String::new();
     io::stdin().read_line(&mut user)?;
@@ -151,7 +179,7 @@ fn prompt_server_config() -> Result<Server> {
     let user = if user.is_empty() { "oleg" } else { user };
 
     // Описание
-    print!("Описание (опционально): ");
+    print!("{}", t!(PROMPT_DESCRIPTION));
     io::stdout().flush()?;
     let mut description = String::new();
     io::stdin().read_line(&mut description)?;
@@ -0,0 +1,142 @@
+//! Слоты ключа: несколько независимых паролей к одному хранилищу
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config;
+use crate::crypto::{self, MAX_KEY_SLOTS};
+use crate::error::{Result, SecureSshError};
+
+use super::{confirm, prompt_new_password, unlock_vault, OutputFormat};
+
+/// Слот для `--output json`
+#[derive(Serialize)]
+struct KeySlotJson {
+    number: usize,
+    current: bool,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+/// Показать слоты ключа; номера - для `keyslot remove`
+pub fn list(output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    let vault = unlock_vault()?;
+    let current = vault.current_key_slot();
+
+    if output.is_json() {
+        let slots: Vec<_> = vault
+            .key_slots()
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| KeySlotJson {
+                number: i + 1,
+                current: i == current,
+                memory_kib: slot.kdf.memory_kib,
+                iterations: slot.kdf.iterations,
+                parallelism: slot.kdf.parallelism,
+            })
+            .collect();
+        return super::print_json(&slots);
+    }
+
+    println!();
+    println!("{}", t!(KEYSLOT_LIST_TITLE, MAX_KEY_SLOTS).cyan().bold());
+    println!();
+    for (i, slot) in vault.key_slots().iter().enumerate() {
+        let mut line = format!("  {} {}", format!("[{}]", i + 1).cyan(), slot.kdf);
+        if i == current {
+            line.push_str(&format!("  ({})", t!(KEYSLOT_CURRENT)).green().to_string());
+        }
+        println!("{}", line);
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Добавить слот с новым паролем; хранилище открывается любым из существующих
+pub fn add() -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    println!("{}", t!(KEYSLOT_ADD_TITLE).cyan().bold());
+    println!();
+    println!("{}", t!(CHANGE_PASS_CURRENT));
+    let mut vault = unlock_vault()?;
+
+    if vault.key_slots().len() >= MAX_KEY_SLOTS {
+        return Err(SecureSshError::InvalidConfig(t!(KEYSLOT_FULL, MAX_KEY_SLOTS)));
+    }
+
+    println!();
+    let new_password = prompt_new_password()?;
+    println!();
+
+    print!("{}", t!(CHANGE_PASS_DERIVING).cyan());
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    // Параметры Argon2id берутся из слота, которым открыто хранилище
+    let derived_key = crypto::derive_key(new_password.as_bytes(), None, &vault.kdf_params())?;
+    vault.add_key_slot(&derived_key)?;
+    println!("{}", t!(DONE).green());
+
+    println!();
+    println!(
+        "{} {}",
+        t!(LABEL_SUCCESS).green().bold(),
+        t!(KEYSLOT_ADDED, vault.key_slots().len())
+    );
+
+    Ok(())
+}
+
+/// Удалить слот по номеру из `keyslot list`, отозвав его пароль
+pub fn remove(number: usize, yes: bool, output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    let mut vault = unlock_vault()?;
+
+    if number == 0 || number > vault.key_slots().len() {
+        return Err(SecureSshError::InvalidConfig(t!(KEYSLOT_NO_NUMBER, number)));
+    }
+    // Так нельзя удалить и последний слот: он всегда тот, которым открыли
+    if number - 1 == vault.current_key_slot() {
+        return Err(SecureSshError::InvalidConfig(t!(KEYSLOT_REMOVE_CURRENT, number)));
+    }
+
+    if !yes {
+        if output.is_json() {
+            return Err(SecureSshError::InvalidConfig(t!(OUTPUT_JSON_NEEDS_YES).into()));
+        }
+        println!("{} {}", t!(LABEL_WARNING).yellow().bold(), t!(KEYSLOT_REMOVE_WARNING, number));
+        if !confirm(t!(KEYSLOT_REMOVE_CONFIRM)) {
+            println!("{}", t!(CANCELLED));
+            return Ok(());
+        }
+    }
+
+    vault.remove_key_slot(number - 1)?;
+    let backups_removed = super::save_backup_key_slots(&vault)?;
+
+    if output.is_json() {
+        return super::print_json(&serde_json::json!({
+            "removed": number,
+            "backup_files_removed": backups_removed,
+        }));
+    }
+
+    println!("{} {}", t!(LABEL_SUCCESS).green().bold(), t!(KEYSLOT_REMOVED, number));
+    if backups_removed > 0 {
+        println!("{}", t!(BACKUP_FILES_REMOVED, backups_removed).yellow());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! Обновление файлов хранилища до текущего формата
+
+use std::path::Path;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config::{self, FileFormat, FsStorage, VaultFile};
+use crate::crypto::FORMAT_VERSION;
+use crate::error::{Result, SecureSshError};
+
+use super::{unlock_vault, OutputFormat};
+
+/// Префикс директорий резервных копий перед миграцией
+pub const BACKUP_KIND: &str = "format";
+
+/// Файл для `--output json`
+#[derive(Serialize)]
+struct FormatJson {
+    file: &'static str,
+    version: u32,
+    current: bool,
+}
+
+/// Результат для `--output json`
+#[derive(Serialize)]
+struct MigrateJson {
+    /// Зашифрованные файлы и их форматы до миграции
+    files: Vec<FormatJson>,
+    /// Переписанные или перенесённые файлы; пусто, если менять было нечего
+    migrated_files: Vec<&'static str>,
+    /// Хранилище - один файл-контейнер
+    container: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    backup_dir: Option<String>,
+}
+
+impl MigrateJson {
+    fn new(formats: &[FileFormat], migrated: &[VaultFile], container: bool, backup_dir: Option<&Path>) -> Self {
+        Self {
+            files: formats
+                .iter()
+                .map(|f| FormatJson {
+                    file: f.file.file_name(),
+                    version: f.version,
+                    current: f.is_current(),
+                })
+                .collect(),
+            migrated_files: migrated.iter().map(|f| f.file_name()).collect(),
+            container,
+            backup_dir: backup_dir.map(|dir| dir.display().to_string()),
+        }
+    }
+}
+
+/// Показать версии форматов файлов и, без `check`, обновить устаревшие;
+/// с `container` перенести хранилище в один файл
+pub fn run(check: bool, container: bool, output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    let storage = config::data_storage()?;
+    let formats = config::check_formats(&*storage)?;
+    if container {
+        return run_container(&formats, output);
+    }
+
+    let outdated: Vec<_> = formats.iter().filter(|f| !f.is_current()).map(|f| f.file).collect();
+    let is_container = config::is_container(&*storage)?;
+    let text = !output.is_json();
+    if text {
+        print_formats(&formats);
+    }
+
+    if outdated.is_empty() {
+        if text {
+            println!("{}", t!(MIGRATE_UP_TO_DATE).green());
+            return Ok(());
+        }
+        return super::print_json(&MigrateJson::new(&formats, &[], is_container, None));
+    }
+    if check {
+        // The report lists which files are outdated before the command fails
+        if !text {
+            super::print_json(&MigrateJson::new(&formats, &[], is_container, None))?;
+        }
+        return Err(SecureSshError::MigrationNeeded);
+    }
+
+    let mut vault = unlock_vault()?;
+    let backup_dir = super::new_backup_dir(BACKUP_KIND)?;
+
+    if text {
+        println!();
+        println!("{}", t!(MIGRATE_BACKUP, backup_dir.display()).cyan());
+        print!("{}", t!(MIGRATE_REWRITING, FORMAT_VERSION).cyan());
+        std::io::Write::flush(&mut std::io::stdout())?;
+    }
+
+    if let Err(e) = config::migrate(&mut vault, &FsStorage::new(&backup_dir)) {
+        if text {
+            println!();
+            println!("{}", t!(MIGRATE_RESTORED).yellow());
+        }
+        return Err(e);
+    }
+
+    if !text {
+        return super::print_json(&MigrateJson::new(&formats, &outdated, is_container, Some(&backup_dir)));
+    }
+
+    println!("{}", t!(DONE).green());
+
+    println!();
+    println!("{}", t!(MIGRATE_DONE, FORMAT_VERSION).green().bold());
+    println!("{}", t!(MIGRATE_BACKUP_HINT, backup_dir.display()).dimmed());
+
+    Ok(())
+}
+
+/// Перенести отдельные файлы в контейнер `vault.enc`
+fn run_container(formats: &[FileFormat], output: OutputFormat) -> Result<()> {
+    let storage = config::data_storage()?;
+    let text = !output.is_json();
+
+    if config::is_container(&*storage)? {
+        if text {
+            println!("{}", t!(MIGRATE_ALREADY_CONTAINER).green());
+            return Ok(());
+        }
+        return super::print_json(&MigrateJson::new(formats, &[], true, None));
+    }
+
+    let mut moved = Vec::new();
+    for file in [VaultFile::Key, VaultFile::PublicKey, VaultFile::Servers] {
+        if storage.exists(file)? {
+            moved.push(file);
+        }
+    }
+
+    let mut vault = unlock_vault()?;
+    let backup_dir = super::new_backup_dir(BACKUP_KIND)?;
+
+    if text {
+        println!();
+        println!("{}", t!(MIGRATE_BACKUP, backup_dir.display()).cyan());
+        print!("{}", t!(MIGRATE_CONTAINER, VaultFile::Container.file_name()).cyan());
+        std::io::Write::flush(&mut std::io::stdout())?;
+    }
+
+    if let Err(e) = config::migrate_to_container(&mut vault, &FsStorage::new(&backup_dir)) {
+        if text {
+            println!();
+            println!("{}", t!(MIGRATE_RESTORED).yellow());
+        }
+        return Err(e);
+    }
+
+    if !text {
+        return super::print_json(&MigrateJson::new(formats, &moved, true, Some(&backup_dir)));
+    }
+
+    println!("{}", t!(DONE).green());
+
+    println!();
+    println!("{}", t!(MIGRATE_CONTAINER_DONE).green().bold());
+    println!("{}", t!(MIGRATE_BACKUP_HINT, backup_dir.display()).dimmed());
+
+    Ok(())
+}
+
+/// Вывести версию формата каждого зашифрованного файла
+fn print_formats(formats: &[FileFormat]) {
+    println!("{}", t!(MIGRATE_TITLE, FORMAT_VERSION).cyan().bold());
+    println!();
+
+    for format in formats {
+        let status = if format.is_current() {
+            t!(MIGRATE_FILE_CURRENT).green()
+        } else {
+            t!(MIGRATE_FILE_OLD).yellow()
+        };
+        println!("  {:<12} v{}  {}", format.file.file_name(), format.version, status);
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_json_is_one_object() {
+        let formats = [
+            FileFormat {
+                file: VaultFile::Key,
+                version: 1,
+            },
+            FileFormat {
+                file: VaultFile::Servers,
+                version: FORMAT_VERSION,
+            },
+        ];
+        let report = MigrateJson::new(&formats, &[VaultFile::Key], false, Some(Path::new("/data/backups/format-1")));
+        let value: serde_json::Value = serde_json::from_str(&serde_json::to_string_pretty(&report).unwrap()).unwrap();
+
+        assert_eq!(value["files"][0]["file"], "key.enc");
+        assert_eq!(value["files"][0]["current"], false);
+        assert_eq!(value["files"][1]["current"], true);
+        assert_eq!(value["migrated_files"], serde_json::json!(["key.enc"]));
+        assert_eq!(value["container"], false);
+        assert_eq!(value["backup_dir"], "/data/backups/format-1");
+
+        let up_to_date = serde_json::to_value(MigrateJson::new(&formats[1..], &[], true, None)).unwrap();
+        assert_eq!(up_to_date["migrated_files"], serde_json::json!([]));
+        assert!(up_to_date.get("backup_dir").is_none());
+    }
+}
@@ -1,56 +1,297 @@
 //! Реализация CLI команд
 
+pub mod agent;
 pub mod change_pass;
 pub mod connect;
+pub mod export;
+pub mod import;
 pub mod init;
+pub mod keyslot;
+pub mod migrate;
+pub mod picker;
+pub mod profile;
 pub mod pubkey;
+pub mod rekey;
+pub mod repair;
+pub mod run;
 pub mod server;
+pub mod tunnel;
 
 use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use colored::Colorize;
+use zeroize::Zeroize;
+
+use crate::config::{Forward, UnlockedVault};
+use crate::crypto::KdfParams;
+use crate::error::SecureSshError;
 
 /// Минимальная длина пароля
 pub const MIN_PASSWORD_LEN: usize = 12;
 
+/// Сколько раз спрашивать новый пароль, прежде чем завершиться ошибкой
+const NEW_PASSWORD_ATTEMPTS: usize = 3;
+
+/// Поддиректория директории данных с резервными копиями
+const BACKUPS_DIR: &str = "backups";
+
+/// Формат вывода результатов (`--output`)
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    #[value(help = t!(HELP_OUTPUT_TEXT))]
+    Text,
+    #[value(help = t!(HELP_OUTPUT_JSON))]
+    Json,
+}
+
+impl OutputFormat {
+    pub fn is_json(self) -> bool {
+        self == Self::Json
+    }
+}
+
+/// Вывести значение в stdout как JSON
+pub fn print_json(value: &impl serde::Serialize) -> crate::error::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Вывести ошибку команды: текстом или объектом JSON с кодом ошибки
+pub fn print_error(error: &SecureSshError, output: OutputFormat) {
+    if output.is_json() {
+        let report = serde_json::json!({
+            "error": {
+                "code": error.code(),
+                "message": error.to_string(),
+            }
+        });
+        eprintln!("{}", report);
+    } else {
+        eprintln!("{} {}", t!(LABEL_ERROR).red().bold(), error);
+    }
+}
+
+/// Параметры пробросов в синтаксисе ssh
+#[derive(clap::Args, Debug, Default)]
+pub struct ForwardArgs {
+    #[arg(short = 'L', long = "local-forward", value_name = "SPEC", help = t!(HELP_LOCAL_FORWARD))]
+    pub local: Vec<String>,
+
+    #[arg(short = 'R', long = "remote-forward", value_name = "SPEC", help = t!(HELP_REMOTE_FORWARD))]
+    pub remote: Vec<String>,
+
+    #[arg(short = 'D', long = "dynamic-forward", value_name = "SPEC", help = t!(HELP_DYNAMIC_FORWARD))]
+    pub dynamic: Vec<String>,
+}
+
+impl ForwardArgs {
+    /// Разобрать все указанные пробросы
+    pub fn parse(&self) -> crate::error::Result<Vec<Forward>> {
+        let local = self.local.iter().map(|s| (s, Forward::parse_local(s)));
+        let remote = self.remote.iter().map(|s| (s, Forward::parse_remote(s)));
+        let dynamic = self.dynamic.iter().map(|s| (s, Forward::parse_dynamic(s)));
+
+        local
+            .chain(remote)
+            .chain(dynamic)
+            .map(|(spec, result)| {
+                result.map_err(|e| {
+                    SecureSshError::InvalidConfig(t!(INVALID_FORWARD, spec, e))
+                })
+            })
+            .collect()
+    }
+
+    /// Проверить, не указано ли ни одного проброса
+    pub fn is_empty(&self) -> bool {
+        self.local.is_empty() && self.remote.is_empty() && self.dynamic.is_empty()
+    }
+}
+
+/// Вывести состояние пробросов
+pub fn print_forward_statuses(statuses: &[crate::ssh::ForwardStatus]) {
+    for status in statuses {
+        match &status.error {
+            None => println!("  {} {}", "✓".green(), status.forward),
+            Some(e) => println!("  {} {} - {}", "✗".red(), status.forward, e.red()),
+        }
+    }
+}
+
 /// Запросить новый пароль с подтверждением
 pub fn prompt_new_password() -> crate::error::Result<String> {
-    println!("{}", "Создание мастер-пароля".cyan().bold());
-    println!("Этот пароль шифрует ваш SSH-ключ. Выберите надёжный пароль.");
-    println!("Минимальная длина: {} символов\n", MIN_PASSWORD_LEN);
+    println!("{}", t!(NEW_PASSWORD_TITLE).cyan().bold());
+    println!("{}", t!(NEW_PASSWORD_INTRO));
+    println!("{}\n", t!(NEW_PASSWORD_MIN_LEN, MIN_PASSWORD_LEN));
 
+    let mut attempt = 1;
     loop {
-        let password = rpassword::prompt_password("Введите мастер-пароль: ")?;
-
-        if password.len() < MIN_PASSWORD_LEN {
-            println!(
-                "{} Пароль должен содержать минимум {} символов",
-                "Ошибка:".red(),
-                MIN_PASSWORD_LEN
-            );
-            continue;
-        }
-
-        let confirm = rpassword::prompt_password("Подтвердите мастер-пароль: ")?;
+        let password = rpassword::prompt_password(t!(PROMPT_PASSWORD))?;
+        let error = if password.len() < MIN_PASSWORD_LEN {
+            SecureSshError::PasswordTooShort
+        } else if password != rpassword::prompt_password(t!(PROMPT_PASSWORD_CONFIRM))? {
+            SecureSshError::PasswordMismatch
+        } else {
+            return Ok(password);
+        };
 
-        if password != confirm {
-            println!("{} Пароли не совпадают", "Ошибка:".red());
-            continue;
+        // Из скрипта без терминала одна и та же ошибка повторялась бы бесконечно
+        if attempt == NEW_PASSWORD_ATTEMPTS {
+            return Err(error);
         }
-
-        return Ok(password);
+        println!("{} {}", t!(LABEL_ERROR).red(), error);
+        attempt += 1;
     }
 }
 
 /// Запросить существующий пароль
 pub fn prompt_password() -> crate::error::Result<String> {
-    let password = rpassword::prompt_password("Введите мастер-пароль: ")?;
+    let password = rpassword::prompt_password(t!(PROMPT_PASSWORD))?;
     Ok(password)
 }
 
+/// Запросить пароль и разблокировать хранилище (Argon2 выполняется один раз)
+pub fn unlock_vault() -> crate::error::Result<UnlockedVault> {
+    let mut password = prompt_password()?;
+    let vault = UnlockedVault::unlock(crate::config::data_storage()?, password.as_bytes());
+    password.zeroize();
+    vault
+}
+
+/// Новая директория резервной копии `<kind>-<время в мс>`, по одной на каждый запуск
+///
+/// Директория создаётся сразу, чтобы два запуска в одну миллисекунду не
+/// писали в одну копию: занятое имя получает суффикс `-<n>`.
+pub fn new_backup_dir(kind: &str) -> crate::error::Result<PathBuf> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let parent = crate::config::get_data_dir()?.join(BACKUPS_DIR);
+    std::fs::create_dir_all(&parent)?;
+
+    let mut suffix = 0;
+    loop {
+        let name = match suffix {
+            0 => format!("{}-{}", kind, stamp),
+            n => format!("{}-{}-{}", kind, stamp, n),
+        };
+        let dir = parent.join(name);
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => suffix += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Время и суффикс из имени резервной копии без префикса `<kind>-`
+///
+/// Первая копия в миллисекунду названа без суффикса, это суффикс 0
+/// (см. [`new_backup_dir`]).
+fn parse_backup_stamp(name: &str) -> Option<(u128, u32)> {
+    let (stamp, suffix) = match name.split_once('-') {
+        Some((stamp, suffix)) => (stamp, suffix.parse().ok()?),
+        None => (name, 0),
+    };
+    Some((stamp.parse().ok()?, suffix))
+}
+
+/// Директории резервных копий `<kind>-<время>`, новые первыми
+pub fn list_backup_dirs(kind: &str) -> crate::error::Result<Vec<PathBuf>> {
+    let dir = crate::config::get_data_dir()?.join(BACKUPS_DIR);
+    let prefix = format!("{}-", kind);
+
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut found = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let stamp = name.strip_prefix(&prefix).and_then(parse_backup_stamp);
+        if let (Some(stamp), true) = (stamp, entry.path().is_dir()) {
+            found.push((stamp, entry.path()));
+        }
+    }
+    found.sort_by_key(|(stamp, _)| std::cmp::Reverse(*stamp));
+    Ok(found.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Дать резервным копиям (`format-*`, `damaged-*`) текущие слоты ключа
+///
+/// Вызывается после смены пароля, параметров KDF или удаления слота, иначе
+/// прежний пароль продолжал бы открывать копии. Возвращает число удалённых
+/// файлов до формата 5: их пароль не сменить и не отозвать.
+pub fn save_backup_key_slots(vault: &UnlockedVault) -> crate::error::Result<usize> {
+    let mut removed = 0;
+    for kind in [migrate::BACKUP_KIND, repair::DAMAGED_KIND] {
+        for dir in list_backup_dirs(kind)? {
+            removed += vault.save_backup_key_slots(&crate::config::FsStorage::new(dir))?;
+        }
+    }
+    Ok(removed)
+}
+
+/// Подобрать параметры Argon2id под время разблокировки (`--kdf-target`)
+///
+/// Замер занимает несколько целевых интервалов, поэтому в текстовом режиме
+/// выводится прогресс.
+pub fn calibrate_kdf(target: &str, output: OutputFormat) -> crate::error::Result<KdfParams> {
+    let duration = parse_duration(target)
+        .map_err(|e| SecureSshError::InvalidConfig(t!(INVALID_KDF_TARGET, target, e)))?;
+
+    if output.is_json() {
+        return KdfParams::calibrate(duration);
+    }
+
+    print!("{}", t!(KDF_CALIBRATING, target).cyan());
+    io::stdout().flush()?;
+
+    let params = KdfParams::calibrate(duration)?;
+    println!("{}", t!(DONE).green());
+    println!("  {}", params.to_string().dimmed());
+
+    Ok(params)
+}
+
+/// Разобрать длительность: `1s`, `2.5s`, `500ms`
+fn parse_duration(spec: &str) -> Result<Duration, String> {
+    let spec = spec.trim();
+    let (number, scale) = if let Some(ms) = spec.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(s) = spec.strip_suffix('s') {
+        (s, 1.0)
+    } else {
+        return Err(t!(DURATION_FORMAT).into());
+    };
+
+    let seconds = number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && *n > 0.0)
+        .map(|n| n * scale)
+        .ok_or_else(|| t!(DURATION_INVALID, spec))?;
+
+    if !(0.05..=60.0).contains(&seconds) {
+        return Err(t!(DURATION_RANGE).into());
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
 /// Запросить подтверждение да/нет
+///
+/// Вопрос выводится в stderr, чтобы не попасть в перенаправленный вывод.
+/// Ответ принимается на любом из языков интерфейса.
 pub fn confirm(prompt: &str) -> bool {
-    print!("{} [y/N] ", prompt);
-    io::stdout().flush().ok();
+    eprint!("{} {} ", prompt, t!(CONFIRM_HINT));
+    io::stderr().flush().ok();
 
     let mut input = String::new();
     if io::stdin().read_line(&mut input).is_err() {
@@ -59,3 +300,31 @@ pub fn confirm(prompt: &str) -> bool {
 
     matches!(input.trim().to_lowercase().as_str(), "y" | "yes" | "д" | "да")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backup_stamp() {
+        assert_eq!(parse_backup_stamp("1792338403123"), Some((1792338403123, 0)));
+        assert_eq!(parse_backup_stamp("1792338403123-2"), Some((1792338403123, 2)));
+        assert_eq!(parse_backup_stamp("1792338403123-x"), None);
+        assert_eq!(parse_backup_stamp("latest"), None);
+
+        // Суффикс отличает копии одной миллисекунды, более поздняя - новее
+        assert!(parse_backup_stamp("1000-1") > parse_backup_stamp("1000"));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("1s").unwrap(), Duration::from_secs(1));
+        assert_eq!(parse_duration("2.5s").unwrap(), Duration::from_millis(2500));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+
+        assert!(parse_duration("1").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("10ms").is_err());
+        assert!(parse_duration("2m").is_err());
+    }
+}
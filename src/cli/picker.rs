@@ -0,0 +1,423 @@
+//! Полноэкранный выбор сервера с нечётким поиском
+
+use std::io::{self, Write};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor, ResetColor};
+use crossterm::terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+
+use crate::config::{Server, ServerList};
+use crate::error::{Result, SecureSshError};
+
+/// Ширина панели предпросмотра (если терминал достаточно широкий)
+const PREVIEW_WIDTH: u16 = 40;
+/// Минимальная ширина терминала для показа предпросмотра
+const MIN_WIDTH_FOR_PREVIEW: u16 = 90;
+
+/// Выбрать сервер в полноэкранном режиме
+///
+/// Поиск идёт по имени, хосту, пользователю и описанию.
+/// Esc или Ctrl+C отменяют выбор.
+pub fn pick_server(servers: &ServerList) -> Result<&Server> {
+    let mut picker = Picker::new(servers.iter().collect());
+
+    let _guard = TerminalGuard::enter()?;
+    let mut stdout = io::stdout();
+
+    loop {
+        picker.render(&mut stdout)?;
+
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match picker.handle_key(key) {
+            Action::Continue => {}
+            Action::Select(server) => return Ok(server),
+            Action::Cancel => return Err(SecureSshError::Cancelled),
+        }
+    }
+}
+
+/// Результат обработки клавиши
+enum Action<'a> {
+    Continue,
+    Select(&'a Server),
+    Cancel,
+}
+
+/// Состояние выбора: строка поиска, отфильтрованный список и курсор
+struct Picker<'a> {
+    servers: Vec<&'a Server>,
+    query: String,
+    /// Индексы в `servers`, отсортированные по релевантности
+    matches: Vec<usize>,
+    selected: usize,
+    /// Первая видимая строка списка
+    offset: usize,
+}
+
+impl<'a> Picker<'a> {
+    fn new(servers: Vec<&'a Server>) -> Self {
+        let mut picker = Self {
+            servers,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            offset: 0,
+        };
+        picker.refilter();
+        picker
+    }
+
+    /// Пересчитать совпадения после изменения строки поиска
+    fn refilter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .servers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| server_score(&self.query, s).map(|score| (score, i)))
+            .collect();
+
+        // Сортировка по убыванию релевантности, при равенстве - исходный порядок
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+        self.offset = 0;
+    }
+
+    fn current(&self) -> Option<&'a Server> {
+        self.matches.get(self.selected).map(|&i| self.servers[i])
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action<'a> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => return Action::Cancel,
+            KeyCode::Char('c') if ctrl => return Action::Cancel,
+            KeyCode::Enter => {
+                if let Some(server) = self.current() {
+                    return Action::Select(server);
+                }
+            }
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Char('p') if ctrl => self.move_by(-1),
+            KeyCode::Down => self.move_by(1),
+            KeyCode::Char('n') if ctrl => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-10),
+            KeyCode::PageDown => self.move_by(10),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = self.matches.len().saturating_sub(1),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.refilter();
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.refilter();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+
+        Action::Continue
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let last = self.matches.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).clamp(0, last) as usize;
+    }
+
+    fn render(&mut self, out: &mut impl Write) -> Result<()> {
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        let show_preview = width >= MIN_WIDTH_FOR_PREVIEW;
+        let list_width = if show_preview { width - PREVIEW_WIDTH - 1 } else { width } as usize;
+
+        // Строки: поиск, разделитель, список..., подсказка
+        let list_height = height.saturating_sub(3).max(1) as usize;
+
+        // Прокрутить так, чтобы выбранная строка была видна
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + list_height {
+            self.offset = self.selected + 1 - list_height;
+        }
+
+        queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+
+        // Строка поиска
+        queue!(
+            out,
+            SetForegroundColor(Color::Cyan),
+            Print("> "),
+            ResetColor,
+            Print(&self.query),
+        )?;
+        let counter = format!("{}/{}", self.matches.len(), self.servers.len());
+        queue!(
+            out,
+            cursor::MoveTo(width.saturating_sub(counter.len() as u16 + 1), 0),
+            SetAttribute(Attribute::Dim),
+            Print(&counter),
+            SetAttribute(Attribute::Reset),
+            cursor::MoveTo(0, 1),
+            SetAttribute(Attribute::Dim),
+            Print("─".repeat(width as usize)),
+            SetAttribute(Attribute::Reset),
+        )?;
+
+        // Список совпадений
+        for (row, &index) in self.matches.iter().skip(self.offset).take(list_height).enumerate() {
+            let server = self.servers[index];
+            let line = format!(" {:<20} {}", server.name, server.connection_string());
+            let line = truncate(&line, list_width);
+
+            queue!(out, cursor::MoveTo(0, 2 + row as u16))?;
+            if self.offset + row == self.selected {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(format!("{:<width$}", line, width = list_width)),
+                    SetAttribute(Attribute::Reset),
+                )?;
+            } else {
+                queue!(out, Print(line))?;
+            }
+        }
+
+        if self.matches.is_empty() {
+            queue!(
+                out,
+                cursor::MoveTo(1, 2),
+                SetAttribute(Attribute::Dim),
+                Print(t!(PICKER_NO_MATCHES)),
+                SetAttribute(Attribute::Reset),
+            )?;
+        }
+
+        // Панель предпросмотра
+        if show_preview {
+            let x = list_width as u16 + 1;
+            for row in 2..height.saturating_sub(1) {
+                queue!(
+                    out,
+                    cursor::MoveTo(x - 1, row),
+                    SetAttribute(Attribute::Dim),
+                    Print("│"),
+                    SetAttribute(Attribute::Reset),
+                )?;
+            }
+
+            if let Some(server) = self.current() {
+                for (row, line) in preview_lines(server).into_iter().enumerate() {
+                    if row + 3 >= height as usize {
+                        break;
+                    }
+                    queue!(
+                        out,
+                        cursor::MoveTo(x + 1, 2 + row as u16),
+                        Print(truncate(&line, PREVIEW_WIDTH as usize - 2)),
+                    )?;
+                }
+            }
+        }
+
+        // Подсказка
+        queue!(
+            out,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            SetAttribute(Attribute::Dim),
+            Print(truncate(t!(PICKER_HINT), width as usize)),
+            SetAttribute(Attribute::Reset),
+            cursor::MoveTo(2 + self.query.chars().count() as u16, 0),
+        )?;
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Строки панели предпросмотра
+fn preview_lines(server: &Server) -> Vec<String> {
+    let mut lines = vec![
+        server.name.clone(),
+        String::new(),
+        field(t!(FIELD_HOST), &server.host),
+        field(t!(FIELD_PORT), &server.port),
+        field(t!(FIELD_USER), &server.user),
+    ];
+
+    if !server.group.is_empty() {
+        lines.push(field(t!(FIELD_GROUP), &server.group));
+    }
+    if !server.tags.is_empty() {
+        lines.push(field(t!(FIELD_TAGS), &server.tags.join(", ")));
+    }
+    if !server.proxy_jump.is_empty() {
+        lines.push(field(t!(FIELD_VIA), &server.proxy_jump.join(" → ")));
+    }
+
+    if !server.description.is_empty() {
+        lines.push(String::new());
+        lines.push(server.description.clone());
+    }
+
+    if !server.forwards.is_empty() {
+        lines.push(String::new());
+        lines.push(t!(FORWARDS).to_string());
+        lines.extend(server.forwards.iter().map(|f| format!("  {}", f)));
+    }
+
+    lines
+}
+
+/// Строка «метка: значение» с выровненными значениями
+fn field(label: &str, value: &dyn std::fmt::Display) -> String {
+    format!("{:<14}{}", label, value)
+}
+
+/// Обрезать строку до заданного числа символов
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let mut out: String = s.chars().take(max.saturating_sub(1)).collect();
+        out.push('…');
+        out
+    }
+}
+
+/// Лучшая оценка сервера по всем полям поиска (None - не подходит)
+fn server_score(query: &str, server: &Server) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    // Совпадение в имени важнее, чем в остальных полях
+    [
+        fuzzy_score(query, &server.name).map(|s| s * 2),
+        fuzzy_score(query, &server.host),
+        fuzzy_score(query, &server.user),
+        fuzzy_score(query, &server.description),
+        fuzzy_score(query, &server.group),
+    ]
+    .into_iter()
+    .chain(server.tags.iter().map(|tag| fuzzy_score(query, tag)))
+    .flatten()
+    .max()
+}
+
+/// Нечёткое совпадение: все символы запроса должны встречаться в тексте по порядку
+///
+/// Бонусы за подряд идущие символы и за совпадение в начале слова,
+/// штраф за пропущенные символы. Регистр не учитывается.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let query: Vec<char> = query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    let Some(&first) = query.first() else {
+        return Some(0);
+    };
+
+    // Жадное сопоставление от каждого вхождения первого символа, берём лучшее
+    text.iter()
+        .enumerate()
+        .filter(|&(_, &c)| c == first)
+        .filter_map(|(start, _)| greedy_score(&query, &text, start))
+        .max()
+}
+
+/// Оценка жадного сопоставления, начиная с позиции `start`
+fn greedy_score(query: &[char], text: &[char], start: usize) -> Option<i64> {
+    let mut score = 0i64;
+    let mut pos = start;
+    let mut prev_match: Option<usize> = None;
+
+    for &q in query {
+        let found = text[pos..].iter().position(|&c| c == q)? + pos;
+
+        score += 1;
+        if prev_match.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - pos).min(5) as i64;
+
+        prev_match = Some(found);
+        pos = found + 1;
+    }
+
+    Some(score)
+}
+
+/// Включает полноэкранный режим и восстанавливает терминал при выходе
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode().map_err(|e| SecureSshError::Other(e.to_string()))?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        execute!(io::stdout(), LeaveAlternateScreen, cursor::Show).ok();
+        terminal::disable_raw_mode().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_subsequence() {
+        assert!(fuzzy_score("pdb", "prod-db-01").is_some());
+        assert!(fuzzy_score("PROD", "prod-db-01").is_some());
+        assert!(fuzzy_score("dbp", "prod-db-01").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn test_fuzzy_prefers_contiguous_and_word_starts() {
+        let contiguous = fuzzy_score("db", "prod-db").unwrap();
+        let scattered = fuzzy_score("db", "dev-web").unwrap();
+        assert!(contiguous > scattered);
+
+        let word_start = fuzzy_score("w", "prod-web").unwrap();
+        let inner = fuzzy_score("w", "prodweb").unwrap();
+        assert!(word_start > inner);
+    }
+
+    #[test]
+    fn test_picker_ranks_name_matches_first() {
+        let by_host = Server::new("alpha", "web.example.com", 22, "root");
+        let by_name = Server::new("web", "10.0.0.1", 22, "root");
+        let other = Server::new("db", "10.0.0.2", 22, "root");
+
+        let mut picker = Picker::new(vec![&by_host, &by_name, &other]);
+        picker.query = "web".into();
+        picker.refilter();
+
+        let names: Vec<&str> = picker.matches.iter().map(|&i| picker.servers[i].name.as_str()).collect();
+        assert_eq!(names, ["web", "alpha"]);
+    }
+}
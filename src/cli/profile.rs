@@ -0,0 +1,106 @@
+//! Управление профилями: у каждого свой пароль, ключ и серверы
+
+use std::fs;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config::{self, FsStorage};
+use crate::error::{Result, SecureSshError};
+
+use super::{confirm, OutputFormat};
+
+/// Профиль для `--output json`
+#[derive(Serialize)]
+struct ProfileJson {
+    name: String,
+    path: String,
+    current: bool,
+    initialized: bool,
+}
+
+/// Показать профили в директории данных
+pub fn list(output: OutputFormat) -> Result<()> {
+    let current = config::current_profile();
+
+    let mut profiles = Vec::new();
+    for name in config::list_profiles()? {
+        let dir = config::profile_dir(&name)?;
+        profiles.push(ProfileJson {
+            initialized: config::has_vault(&FsStorage::new(&dir))?,
+            current: name == current,
+            path: dir.display().to_string(),
+            name,
+        });
+    }
+
+    if output.is_json() {
+        return super::print_json(&profiles);
+    }
+
+    println!("{}", t!(PROFILE_LIST_TITLE).cyan().bold());
+    println!();
+
+    let width = profiles.iter().map(|p| p.name.chars().count()).max().unwrap_or(0);
+    for profile in &profiles {
+        let marker = if profile.current { "*".green().bold() } else { " ".normal() };
+        let mut line = format!(
+            "{} {:<width$}  {}",
+            marker,
+            profile.name.bold(),
+            profile.path.dimmed(),
+            width = width
+        );
+        if !profile.initialized {
+            line.push_str(&format!("  ({})", t!(PROFILE_NOT_INITIALIZED)).yellow().to_string());
+        }
+        println!("{}", line);
+    }
+
+    println!();
+    println!("{}", t!(PROFILE_CURRENT_HINT, "--profile <name>".cyan()).dimmed());
+
+    Ok(())
+}
+
+/// Создать профиль: новый мастер-пароль и SSH-ключ
+pub fn create(name: &str) -> Result<()> {
+    let dir = config::profile_dir(name)?;
+    if config::has_vault(&FsStorage::new(&dir))? {
+        return Err(SecureSshError::InvalidConfig(t!(PROFILE_EXISTS, name)));
+    }
+
+    super::init::run_profile(name, None, false)
+}
+
+/// Удалить профиль вместе с ключом и серверами
+pub fn delete(name: &str, yes: bool, output: OutputFormat) -> Result<()> {
+    if name == config::DEFAULT_PROFILE {
+        return Err(SecureSshError::InvalidConfig(t!(PROFILE_DELETE_DEFAULT).into()));
+    }
+
+    let dir = config::profile_dir(name)?;
+    if !dir.is_dir() {
+        return Err(SecureSshError::ProfileNotFound(name.to_string()));
+    }
+
+    if !yes {
+        if output.is_json() {
+            return Err(SecureSshError::InvalidConfig(t!(OUTPUT_JSON_NEEDS_YES).into()));
+        }
+        println!("{} {}", t!(LABEL_WARNING).yellow().bold(), t!(PROFILE_DELETE_WARNING, name));
+        if !confirm(t!(PROFILE_DELETE_CONFIRM)) {
+            println!("{}", t!(CANCELLED));
+            return Ok(());
+        }
+    }
+
+    fs::remove_dir_all(&dir)?;
+
+    if output.is_json() {
+        return super::print_json(&serde_json::json!({ "deleted": name }));
+    }
+
+    println!("{} {}", t!(LABEL_SUCCESS).green().bold(), t!(PROFILE_DELETED, name));
+
+    Ok(())
+}
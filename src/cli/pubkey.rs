@@ -1,21 +1,49 @@
 //! Отображение публичного SSH-ключа
 
 use colored::Colorize;
+use serde::Serialize;
 
-use crate::config::read_public_key;
+use crate::config::{data_storage, is_container, read_public_key};
+use crate::crypto;
 use crate::error::Result;
 
-pub fn run() -> Result<()> {
-    let public_key = read_public_key()?;
+use super::{unlock_vault, OutputFormat};
+
+/// Ключ для `--output json`
+#[derive(Serialize)]
+struct PublicKeyJson<'a> {
+    algorithm: &'a str,
+    public_key: &'a str,
+    fingerprint: &'a str,
+}
+
+pub fn run(output: OutputFormat) -> Result<()> {
+    // В контейнере публичный ключ зашифрован вместе со всем остальным
+    let storage = data_storage()?;
+    let public_key = if is_container(&*storage)? {
+        unlock_vault()?.public_key()?
+    } else {
+        read_public_key(&*storage)?
+    };
+    let fingerprint = crypto::fingerprint(&public_key)?;
+
+    if output.is_json() {
+        return super::print_json(&PublicKeyJson {
+            algorithm: public_key.split_whitespace().next().unwrap_or_default(),
+            public_key: &public_key,
+            fingerprint: &fingerprint,
+        });
+    }
 
     println!();
-    println!("{}", "Ваш публичный SSH-ключ:".cyan().bold());
+    println!("{}", t!(PUBKEY_TITLE).cyan().bold());
     println!();
     println!("{}", "─".repeat(60).dimmed());
     println!("{}", public_key);
     println!("{}", "─".repeat(60).dimmed());
+    println!("{} {}", t!(PUBKEY_FINGERPRINT).dimmed(), fingerprint);
     println!();
-    println!("Добавьте этот ключ в {} на ваших серверах.", "~/.ssh/authorized_keys".cyan());
+    println!("{}", t!(PUBKEY_HINT, "~/.ssh/authorized_keys".cyan()));
     println!();
 
     Ok(())
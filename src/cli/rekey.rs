@@ -0,0 +1,101 @@
+//! Перешифровка мастер-ключа хранилища с новыми параметрами Argon2id
+
+use colored::Colorize;
+use zeroize::Zeroize;
+
+use crate::config::{self, UnlockedVault};
+use crate::crypto::{self, KdfParams};
+use crate::error::{Result, SecureSshError};
+
+use super::{calibrate_kdf, prompt_password, OutputFormat};
+
+/// Усилить (или ослабить) вычисление ключа, не меняя пароль
+///
+/// Ровно один из `kdf` и `kdf_target` задан (это проверяет clap).
+pub fn run(kdf: Option<String>, kdf_target: Option<String>, output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    let text = !output.is_json();
+    if text {
+        println!("{}", t!(REKEY_TITLE).cyan().bold());
+        println!();
+    }
+
+    // Параметры разбираются до запроса пароля, чтобы ошибка не стоила разблокировки
+    let params = match (kdf, kdf_target) {
+        (Some(spec), _) => KdfParams::parse(&spec)
+            .map_err(|e| SecureSshError::InvalidConfig(t!(INVALID_KDF, spec, e)))?,
+        (None, Some(target)) => calibrate_kdf(&target, output)?,
+        (None, None) => unreachable!("clap requires --kdf or --kdf-target"),
+    };
+
+    let mut password = prompt_password()?;
+
+    if text {
+        print!("{}", t!(CHANGE_PASS_CHECKING).cyan());
+        std::io::Write::flush(&mut std::io::stdout())?;
+    }
+
+    let mut vault = match UnlockedVault::unlock(config::data_storage()?, password.as_bytes()) {
+        Ok(vault) => vault,
+        Err(e) => {
+            password.zeroize();
+            return Err(e);
+        }
+    };
+    let previous = vault.kdf_params();
+    if text {
+        println!("{}", t!(DONE).green());
+
+        println!();
+        println!("{}", t!(REKEY_CURRENT, previous));
+        println!("{}", t!(REKEY_NEW, params));
+        println!();
+
+        print!("{}", t!(CHANGE_PASS_DERIVING).cyan());
+        std::io::Write::flush(&mut std::io::stdout())?;
+    }
+
+    // Новая соль и новые параметры, пароль прежний
+    let derived_key = crypto::derive_key(password.as_bytes(), None, &params);
+    password.zeroize();
+    let derived_key = derived_key?;
+
+    if text {
+        println!("{}", t!(DONE).green());
+        print!("{}", t!(CHANGE_PASS_REWRAPPING).cyan());
+        std::io::Write::flush(&mut std::io::stdout())?;
+    }
+
+    vault.rekey(derived_key)?;
+    let backups_removed = super::save_backup_key_slots(&vault)?;
+
+    if !text {
+        return super::print_json(&serde_json::json!({
+            "previous": kdf_json(&previous),
+            "current": kdf_json(&params),
+            "backup_files_removed": backups_removed,
+        }));
+    }
+
+    println!("{}", t!(DONE).green());
+
+    println!();
+    println!("{}", t!(REKEY_DONE).green().bold());
+    if backups_removed > 0 {
+        println!("{}", t!(BACKUP_FILES_REMOVED, backups_removed).yellow());
+    }
+
+    Ok(())
+}
+
+/// Параметры Argon2id для `--output json`, как в `keyslot list`
+fn kdf_json(params: &KdfParams) -> serde_json::Value {
+    serde_json::json!({
+        "memory_kib": params.memory_kib,
+        "iterations": params.iterations,
+        "parallelism": params.parallelism,
+    })
+}
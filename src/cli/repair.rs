@@ -0,0 +1,115 @@
+//! Восстановление повреждённого хранилища из резервных копий
+
+use colored::Colorize;
+use serde::Serialize;
+use zeroize::Zeroize;
+
+use crate::config::{self, FileRepair, FsStorage, RepairOutcome, RepairSource};
+use crate::error::{Result, SecureSshError};
+
+use super::OutputFormat;
+
+/// Префикс директорий, куда откладываются повреждённые файлы
+pub const DAMAGED_KIND: &str = "damaged";
+
+/// Файл для `--output json`
+#[derive(Serialize)]
+struct RepairJson {
+    file: &'static str,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+}
+
+/// Проверить файлы хранилища и заменить повреждённые первой целой копией:
+/// предыдущей версией (`.bak`) или копией из резервных копий `migrate`
+pub fn run(output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    let storage = config::data_storage()?;
+    let backup_dirs = super::list_backup_dirs(super::migrate::BACKUP_KIND)?;
+    let backup_storages: Vec<_> = backup_dirs.iter().map(FsStorage::new).collect();
+    let backups: Vec<_> = backup_dirs
+        .iter()
+        .zip(&backup_storages)
+        .map(|(dir, storage)| RepairSource {
+            name: dir.display().to_string(),
+            storage,
+        })
+        .collect();
+    let damaged_dir = super::new_backup_dir(DAMAGED_KIND)?;
+
+    let mut password = super::prompt_password()?;
+    let report = config::repair(
+        &*storage,
+        &backups,
+        password.as_bytes(),
+        &FsStorage::new(&damaged_dir),
+    );
+    password.zeroize();
+    // Директория создаётся заранее; пустую, если откладывать было нечего, убрать
+    let _ = std::fs::remove_dir(&damaged_dir);
+    let report = report?;
+
+    if output.is_json() {
+        let files: Vec<_> = report.iter().map(repair_json).collect();
+        super::print_json(&files)?;
+    } else {
+        print_report(&report);
+        if report.iter().any(|r| is_restored(&r.outcome)) {
+            println!("{}", t!(REPAIR_DAMAGED_KEPT, damaged_dir.display()).dimmed());
+        }
+    }
+
+    match report.iter().find(|r| r.outcome == RepairOutcome::Lost) {
+        Some(lost) => Err(SecureSshError::VaultCorrupted(lost.file.file_name().to_string())),
+        None => Ok(()),
+    }
+}
+
+fn is_restored(outcome: &RepairOutcome) -> bool {
+    matches!(
+        outcome,
+        RepairOutcome::RestoredPrevious | RepairOutcome::RestoredBackup(_)
+    )
+}
+
+fn repair_json(repair: &FileRepair) -> RepairJson {
+    let (status, source) = match &repair.outcome {
+        RepairOutcome::Intact => ("intact", None),
+        RepairOutcome::RestoredPrevious => ("restored", Some("previous".to_string())),
+        RepairOutcome::RestoredBackup(name) => ("restored", Some(name.clone())),
+        RepairOutcome::Lost => ("lost", None),
+    };
+    RepairJson {
+        file: repair.file.file_name(),
+        status,
+        source,
+    }
+}
+
+/// Вывести, что стало с каждым файлом
+fn print_report(report: &[FileRepair]) {
+    println!();
+    println!("{}", t!(REPAIR_TITLE).cyan().bold());
+    println!();
+
+    for repair in report {
+        let status = match &repair.outcome {
+            RepairOutcome::Intact => t!(REPAIR_INTACT).green(),
+            RepairOutcome::RestoredPrevious => t!(REPAIR_RESTORED_PREVIOUS).yellow(),
+            RepairOutcome::RestoredBackup(name) => t!(REPAIR_RESTORED_BACKUP, name).yellow(),
+            RepairOutcome::Lost => t!(REPAIR_LOST).red(),
+        };
+        println!("  {:<12} {}", repair.file.file_name(), status);
+    }
+    println!();
+
+    if report.iter().all(|r| r.outcome == RepairOutcome::Intact) {
+        println!("{}", t!(REPAIR_ALL_INTACT).green());
+    } else if !report.iter().any(|r| r.outcome == RepairOutcome::Lost) {
+        println!("{}", t!(REPAIR_DONE).green().bold());
+    }
+}
@@ -0,0 +1,469 @@
+//! Выполнение команды на нескольких серверах параллельно
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use colored::Colorize;
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::config;
+use crate::crypto::SecureBytes;
+use crate::error::{exit_code, Result, SecureSshError};
+use crate::ssh::{self, ExitStatus};
+use crate::watchdog;
+
+use super::connect::Target;
+use super::OutputFormat;
+
+pub fn run(
+    command: Vec<String>,
+    selectors: Vec<String>,
+    parallel: usize,
+    connect_timeout: u64,
+    output: OutputFormat,
+) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    if parallel == 0 {
+        return Err(SecureSshError::InvalidConfig(t!(RUN_PARALLEL_ZERO).into()));
+    }
+
+    let command = command.join(" ");
+    let json = output.is_json();
+
+    let (private_key, servers) = super::connect::unlock()?;
+    let selected = super::connect::resolve_servers(&servers, &selectors)?
+        .into_iter()
+        .map(|server| Target::new(&servers, server))
+        .collect::<Result<Vec<_>>>()?;
+    drop(servers);
+
+    if !json {
+        println!();
+        println!("{} {}", t!(RUN_RUNNING, selected.len()).cyan(), command.bold());
+        println!();
+    }
+
+    let watchdog = watchdog::create_watchdog();
+
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| SecureSshError::Other(t!(RUNTIME_FAILED, e)))?;
+
+    // Ключ остаётся в SecureBytes; задачи получают ссылку на него через Arc
+    let private_key = Arc::new(private_key);
+    let options = RunOptions {
+        command,
+        parallel,
+        connect_timeout: Duration::from_secs(connect_timeout),
+        stream: !json,
+    };
+
+    let result = runtime.block_on(run_all(&selected, private_key, &options, watchdog));
+
+    let results = result?;
+
+    if json {
+        print_json(&options.command, &results)?;
+    } else {
+        print_summary(&results);
+    }
+
+    match run_error(results) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Ошибка команды `run`, если хоть один сервер не справился
+///
+/// Если все неудачи одного класса (например, все серверы недоступны по
+/// таймауту), возвращается ошибка этого класса и её код выхода; при
+/// разных причинах, как и при ненулевом коде команды, -
+/// [`SecureSshError::RemoteCommandFailed`].
+fn run_error(results: Vec<RunResult>) -> Option<SecureSshError> {
+    let total = results.len();
+    let mut failures: Vec<_> = results
+        .into_iter()
+        .filter(|r| !r.succeeded())
+        .map(|r| r.status.err())
+        .collect();
+
+    let failed = failures.len();
+    let class = |failure: &Option<SecureSshError>| match failure {
+        Some(e) => e.exit_code(),
+        None => exit_code::REMOTE_COMMAND_FAILED,
+    };
+    let first = class(failures.first()?);
+    if first != exit_code::REMOTE_COMMAND_FAILED && failures.iter().all(|f| class(f) == first) {
+        return failures.swap_remove(0);
+    }
+    Some(SecureSshError::RemoteCommandFailed { failed, total })
+}
+
+/// Параметры выполнения
+struct RunOptions {
+    command: String,
+    /// Максимум одновременных подключений
+    parallel: usize,
+    connect_timeout: Duration,
+    /// Выводить строки по мере поступления (иначе собирать для JSON)
+    stream: bool,
+}
+
+/// Результат выполнения на одном сервере
+struct RunResult {
+    server: String,
+    host: String,
+    /// Статус команды или ошибка подключения
+    status: Result<ExitStatus>,
+    duration: Duration,
+    output: ServerOutput,
+}
+
+impl RunResult {
+    fn succeeded(&self) -> bool {
+        matches!(&self.status, Ok(status) if status.success())
+    }
+
+    /// Результат задачи, которая завершилась паникой или была отменена
+    fn lost(target: &Target, error: tokio::task::JoinError) -> Self {
+        Self {
+            server: target.server.name.clone(),
+            host: target.server.host.clone(),
+            status: Err(SecureSshError::Other(t!(RUN_TASK_LOST, error))),
+            duration: Duration::ZERO,
+            output: ServerOutput::new(None),
+        }
+    }
+}
+
+/// Запустить команду на всех серверах, не более `parallel` одновременно
+async fn run_all(
+    targets: &[Target],
+    private_key: Arc<SecureBytes>,
+    options: &RunOptions,
+    watchdog: Option<Box<dyn watchdog::UsbWatchdog>>,
+) -> Result<Vec<RunResult>> {
+    let semaphore = Arc::new(Semaphore::new(options.parallel));
+    let width = targets.iter().map(|t| t.server.name.chars().count()).max().unwrap_or(0);
+    let mut tasks = JoinSet::new();
+    // Индекс сервера по id задачи: упавшая задача не возвращает свой результат
+    let mut indexes = HashMap::with_capacity(targets.len());
+
+    for (index, target) in targets.iter().cloned().enumerate() {
+        let semaphore = semaphore.clone();
+        let private_key = private_key.clone();
+        let command = options.command.clone();
+        let timeout = options.connect_timeout;
+        let prefix = options
+            .stream
+            .then(|| format!("{:<width$} |", target.server.name, width = width).cyan().to_string());
+
+        let task = tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = run_one(&target, &command, &private_key, timeout, prefix).await;
+            (index, result)
+        });
+        indexes.insert(task.id(), index);
+    }
+
+    let mut results = Vec::with_capacity(targets.len());
+
+    loop {
+        tokio::select! {
+            joined = tasks.join_next() => match joined {
+                Some(Ok(result)) => results.push(result),
+                Some(Err(e)) => {
+                    let index = indexes[&e.id()];
+                    results.push((index, RunResult::lost(&targets[index], e)));
+                }
+                None => break,
+            },
+            _ = tokio::time::sleep(Duration::from_millis(500)) => {
+                if watchdog.as_ref().is_some_and(|wd| !wd.is_present()) {
+                    tasks.abort_all();
+                    return Err(SecureSshError::UsbRemoved);
+                }
+            }
+        }
+    }
+
+    // Сводка в порядке, в котором серверы были указаны
+    results.sort_by_key(|(index, _)| *index);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Подключиться к серверу и выполнить команду
+async fn run_one(
+    target: &Target,
+    command: &str,
+    private_key: &SecureBytes,
+    connect_timeout: Duration,
+    prefix: Option<String>,
+) -> RunResult {
+    let started = Instant::now();
+    let mut output = ServerOutput::new(prefix);
+
+    let status = async {
+        let session = tokio::time::timeout(
+            connect_timeout,
+            ssh::connect(
+                &target.route,
+                private_key,
+                ssh::SshClient::default(),
+            ),
+        )
+        .await
+        .map_err(|_| SecureSshError::ConnectionTimeout(target.server.connection_string()))??;
+
+        let status = ssh::exec(&session, command, |data, stderr| output.push(data, stderr)).await;
+
+        session
+            .disconnect(russh::Disconnect::ByApplication, "", "en")
+            .await
+            .ok();
+
+        status
+    }
+    .await;
+
+    output.finish();
+
+    RunResult {
+        server: target.server.name.clone(),
+        host: target.server.host.clone(),
+        status,
+        duration: started.elapsed(),
+        output,
+    }
+}
+
+/// Вывод одного сервера
+struct ServerOutput {
+    /// Префикс строк при потоковом выводе; None - собирать вывод целиком
+    prefix: Option<String>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl ServerOutput {
+    fn new(prefix: Option<String>) -> Self {
+        Self {
+            prefix,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, data: &[u8], stderr: bool) {
+        let buffer = if stderr { &mut self.stderr } else { &mut self.stdout };
+        buffer.extend_from_slice(data);
+
+        // Печатаем только целые строки, чтобы вывод разных серверов не перемешивался
+        if let Some(prefix) = &self.prefix {
+            for line in take_lines(buffer) {
+                print_line(prefix, &line, stderr);
+            }
+        }
+    }
+
+    /// Вывести остаток без завершающего перевода строки
+    fn finish(&mut self) {
+        if let Some(prefix) = &self.prefix {
+            for (buffer, stderr) in [(&mut self.stdout, false), (&mut self.stderr, true)] {
+                if !buffer.is_empty() {
+                    buffer.push(b'\n');
+                    for line in take_lines(buffer) {
+                        print_line(prefix, &line, stderr);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn print_line(prefix: &str, line: &str, stderr: bool) {
+    if stderr {
+        eprintln!("{} {}", prefix, line);
+    } else {
+        println!("{} {}", prefix, line);
+    }
+}
+
+/// Забрать из буфера завершённые строки (без символов перевода строки)
+fn take_lines(buffer: &mut Vec<u8>) -> Vec<String> {
+    let Some(end) = buffer.iter().rposition(|&b| b == b'\n') else {
+        return Vec::new();
+    };
+
+    let complete: Vec<u8> = buffer.drain(..=end).collect();
+    String::from_utf8_lossy(&complete[..end])
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line).to_string())
+        .collect()
+}
+
+/// Вывести сводную таблицу
+fn print_summary(results: &[RunResult]) {
+    let width = results
+        .iter()
+        .map(|r| r.server.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(t!(RUN_COLUMN_SERVER).chars().count());
+
+    println!();
+    println!(
+        "{:<width$} {:>8}  {}",
+        t!(RUN_COLUMN_SERVER).bold(),
+        t!(RUN_COLUMN_TIME).bold(),
+        t!(RUN_COLUMN_RESULT).bold(),
+        width = width
+    );
+    println!("{}", "─".repeat(width + 30).dimmed());
+
+    for result in results {
+        let outcome = match &result.status {
+            Ok(status) if status.success() => format!("{} {}", "✓".green(), status),
+            Ok(status) => format!("{} {}", "✗".red(), status.to_string().red()),
+            Err(e) => format!("{} {}", "✗".red(), e.to_string().red()),
+        };
+
+        println!(
+            "{:<width$} {:>7.1}{}  {}",
+            result.server,
+            result.duration.as_secs_f64(),
+            t!(RUN_SECONDS),
+            outcome,
+            width = width
+        );
+    }
+
+    let succeeded = results.iter().filter(|r| r.succeeded()).count();
+    println!();
+    println!("{}", t!(RUN_SUCCEEDED, succeeded, results.len()));
+}
+
+/// Отчёт для `--output json`
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    command: &'a str,
+    results: Vec<JsonResult<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonResult<'a> {
+    server: &'a str,
+    host: &'a str,
+    success: bool,
+    exit_code: Option<u32>,
+    signal: Option<&'a str>,
+    error: Option<String>,
+    /// Код ошибки подключения, как в `--output json`
+    error_code: Option<&'static str>,
+    duration_ms: u128,
+    stdout: String,
+    stderr: String,
+}
+
+fn print_json(command: &str, results: &[RunResult]) -> Result<()> {
+    let results = results
+        .iter()
+        .map(|result| {
+            let (exit_code, signal, error) = match &result.status {
+                Ok(ExitStatus::Code(code)) => (Some(*code), None, None),
+                Ok(ExitStatus::Signal(signal)) => (None, Some(signal.as_str()), None),
+                Ok(ExitStatus::Unknown) => (None, None, None),
+                Err(e) => (None, None, Some(e)),
+            };
+
+            JsonResult {
+                server: &result.server,
+                host: &result.host,
+                success: result.succeeded(),
+                exit_code,
+                signal,
+                error: error.map(|e| e.to_string()),
+                error_code: error.map(|e| e.code()),
+                duration_ms: result.duration.as_millis(),
+                stdout: String::from_utf8_lossy(&result.output.stdout).into_owned(),
+                stderr: String::from_utf8_lossy(&result.output.stderr).into_owned(),
+            }
+        })
+        .collect();
+
+    let report = JsonReport { command, results };
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_lines_keeps_partial() {
+        let mut buffer = b"one\r\ntwo\nthr".to_vec();
+        assert_eq!(take_lines(&mut buffer), vec!["one", "two"]);
+        assert_eq!(buffer, b"thr");
+
+        buffer.extend_from_slice(b"ee\n");
+        assert_eq!(take_lines(&mut buffer), vec!["three"]);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_take_lines_without_newline() {
+        let mut buffer = b"partial".to_vec();
+        assert!(take_lines(&mut buffer).is_empty());
+        assert_eq!(buffer, b"partial");
+    }
+
+    fn result(status: Result<ExitStatus>) -> RunResult {
+        RunResult {
+            server: "web".into(),
+            host: "192.0.2.10".into(),
+            status,
+            duration: Duration::ZERO,
+            output: ServerOutput::new(None),
+        }
+    }
+
+    #[test]
+    fn test_run_error_keeps_a_single_class() {
+        let timeout = || result(Err(SecureSshError::ConnectionTimeout("192.0.2.10:22".into())));
+        let refused = || result(Err(SecureSshError::ConnectionRefused("192.0.2.10:22".into())));
+        let ok = || result(Ok(ExitStatus::Code(0)));
+        let exit_code = |results| run_error(results).map(|e: SecureSshError| e.exit_code());
+
+        assert_eq!(exit_code(vec![ok(), ok()]), None);
+        assert_eq!(exit_code(vec![timeout(), ok(), timeout()]), Some(exit_code::CONNECTION_TIMEOUT));
+        assert_eq!(exit_code(vec![refused()]), Some(exit_code::CONNECTION_REFUSED));
+
+        // Разные причины - общий код
+        assert_eq!(exit_code(vec![timeout(), refused()]), Some(exit_code::REMOTE_COMMAND_FAILED));
+        assert_eq!(
+            exit_code(vec![timeout(), result(Ok(ExitStatus::Code(1)))]),
+            Some(exit_code::REMOTE_COMMAND_FAILED)
+        );
+        assert!(matches!(
+            run_error(vec![result(Ok(ExitStatus::Code(2))), ok()]),
+            Some(SecureSshError::RemoteCommandFailed { failed: 1, total: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_collected_output_is_kept_whole() {
+        let mut output = ServerOutput::new(None);
+        output.push(b"a\nb", false);
+        output.push(b"err\n", true);
+        output.finish();
+        assert_eq!(output.stdout, b"a\nb");
+        assert_eq!(output.stderr, b"err\n");
+    }
+}
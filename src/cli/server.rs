@@ -2,33 +2,121 @@
 
 use std::io::{self, Write};
 use colored::Colorize;
+use serde::Serialize;
 
-use crate::config::{self, Server};
-use crate::crypto;
+use crate::config::{self, Forward, Server, ServerList};
 use crate::error::{Result, SecureSshError};
 
-use super::prompt_password;
+use super::{unlock_vault, ForwardArgs, OutputFormat};
+
+/// Поля сервера, задаваемые флагами (`server add`, `server edit`)
+#[derive(clap::Args, Debug, Default)]
+pub struct ServerFields {
+    #[arg(long, help = t!(HELP_FIELD_HOST))]
+    pub host: Option<String>,
+
+    #[arg(long, help = t!(HELP_FIELD_PORT))]
+    pub port: Option<u16>,
+
+    #[arg(long, help = t!(HELP_FIELD_USER))]
+    pub user: Option<String>,
+
+    #[arg(long = "desc", value_name = t!(VALUE_TEXT), help = t!(HELP_FIELD_DESC))]
+    pub description: Option<String>,
+
+    #[arg(long, help = t!(HELP_FIELD_GROUP))]
+    pub group: Option<String>,
+
+    #[arg(long, help = t!(HELP_FIELD_TAGS))]
+    pub tags: Option<String>,
+
+    #[arg(long, value_name = t!(VALUE_HOSTS), help = t!(HELP_FIELD_JUMP))]
+    pub jump: Option<String>,
+}
+
+impl ServerFields {
+    /// Проверить, не указано ли ни одного поля
+    pub fn is_empty(&self) -> bool {
+        self.host.is_none()
+            && self.port.is_none()
+            && self.user.is_none()
+            && self.description.is_none()
+            && self.group.is_none()
+            && self.tags.is_none()
+            && self.jump.is_none()
+    }
+
+    /// Проверить указанные поля и записать их в сервер
+    fn apply(self, server: &mut Server) -> Result<()> {
+        if let Some(host) = self.host {
+            server.host = config::parse_host(&host).map_err(SecureSshError::InvalidConfig)?;
+        }
+        if let Some(port) = self.port {
+            config::validate_port(port).map_err(SecureSshError::InvalidConfig)?;
+            server.port = port;
+        }
+        if let Some(user) = self.user {
+            let user = user.trim().to_string();
+            config::validate_user(&user).map_err(SecureSshError::InvalidConfig)?;
+            server.user = user;
+        }
+        if let Some(description) = self.description {
+            server.description = description.trim().to_string();
+        }
+        if let Some(group) = self.group {
+            server.group = config::parse_group(&group).map_err(SecureSshError::InvalidConfig)?;
+        }
+        if let Some(tags) = self.tags {
+            server.tags = config::parse_tags(&tags).map_err(SecureSshError::InvalidConfig)?;
+        }
+        if let Some(jump) = self.jump {
+            server.proxy_jump = jump
+                .split(',')
+                .map(str::trim)
+                .filter(|j| !j.is_empty())
+                .map(|j| config::validate_name(j).map(|_| j.to_string()))
+                .collect::<std::result::Result<_, _>>()
+                .map_err(SecureSshError::InvalidConfig)?;
+        }
+        Ok(())
+    }
+}
 
 /// Добавить новый сервер
-pub fn add() -> Result<()> {
+///
+/// Без флагов данные запрашиваются интерактивно; с флагами нужны
+/// как минимум `--name`, `--host` и `--user`.
+pub fn add(name: Option<String>, fields: ServerFields, output: OutputFormat) -> Result<()> {
     if !config::is_initialized()? {
         return Err(SecureSshError::NotInitialized);
     }
 
-    println!("{}", "=== Добавление сервера ===".cyan().bold());
-    println!();
+    let interactive = name.is_none() && fields.is_empty();
+    if interactive && output.is_json() {
+        return Err(SecureSshError::InvalidConfig(t!(SERVER_ADD_JSON_NEEDS_FLAGS).into()));
+    }
 
-    // Получить пароль для расшифровки конфига
-    let password = prompt_password()?;
+    // Проверить флаги до запроса пароля
+    let flagged = if interactive {
+        None
+    } else {
+        Some(server_from_flags(name, fields)?)
+    };
 
-    // Загрузить существующий ключ для получения соли
-    let (_, salt) = config::load_encrypted_key(password.as_bytes())?;
+    if !output.is_json() {
+        println!("{}", t!(SERVER_ADD_TITLE).cyan().bold());
+        println!();
+    }
 
-    // Загрузить существующие серверы
-    let mut servers = config::load_servers(password.as_bytes(), &salt)?;
+    // Разблокировать хранилище: ключ вычисляется один раз для чтения и записи
+    let mut vault = unlock_vault()?;
+    let servers = vault.servers_mut();
 
     // Запросить данные нового сервера
-    let server = prompt_server_details()?;
+    let server = match flagged {
+        Some(server) => server,
+        None => prompt_server_details()?,
+    };
 
     // Проверить, не существует ли уже
     if servers.get(&server.name).is_some() {
@@ -38,98 +126,427 @@ pub fn add() -> Result<()> {
     // Добавить и сохранить
     servers.add(server.clone()).map_err(|e| SecureSshError::Other(e.to_string()))?;
 
-    let derived_key = crypto::derive_key(password.as_bytes(), Some(&salt))?;
-    config::save_servers(&servers, &derived_key)?;
+    vault.save()?;
+
+    if output.is_json() {
+        return super::print_json(&ServerJson::from(&server));
+    }
 
     println!();
-    println!(
-        "{} Сервер '{}' добавлен!",
-        "Успех:".green().bold(),
-        server.name
-    );
+    println!("{} {}", t!(LABEL_SUCCESS).green().bold(), t!(SERVER_ADDED, server.name));
 
     Ok(())
 }
 
-/// Показать список всех настроенных серверов
-pub fn list() -> Result<()> {
+/// Собрать сервер из флагов `server add`
+fn server_from_flags(name: Option<String>, fields: ServerFields) -> Result<Server> {
+    let missing = |flag: &str| SecureSshError::InvalidConfig(t!(SERVER_MISSING_FLAG, flag));
+
+    let name = name.ok_or_else(|| missing("--name"))?;
+    config::validate_name(&name).map_err(SecureSshError::InvalidConfig)?;
+    if fields.host.is_none() {
+        return Err(missing("--host"));
+    }
+    if fields.user.is_none() {
+        return Err(missing("--user"));
+    }
+
+    let mut server = Server::new(name, "", 22, "");
+    fields.apply(&mut server)?;
+    Ok(server)
+}
+
+/// Изменить поля сервера
+pub fn edit(name: &str, fields: ServerFields, output: OutputFormat) -> Result<()> {
     if !config::is_initialized()? {
         return Err(SecureSshError::NotInitialized);
     }
 
-    println!("{}", "=== Настроенные серверы ===".cyan().bold());
+    if fields.is_empty() {
+        return Err(SecureSshError::InvalidConfig(t!(SERVER_EDIT_NO_FIELDS).into()));
+    }
+
+    let mut vault = unlock_vault()?;
+    let servers = vault.servers_mut();
+
+    let name = super::connect::select_server(servers, Some(name.to_string()))?
+        .name
+        .clone();
+    let server = servers
+        .get_mut(&name)
+        .ok_or_else(|| SecureSshError::ServerNotFound(name.clone()))?;
+
+    fields.apply(server)?;
+
+    vault.save()?;
+
+    if output.is_json() {
+        return print_server_json(vault.servers(), &name);
+    }
+
+    println!("{} {}", t!(LABEL_SUCCESS).green().bold(), t!(SERVER_UPDATED, name));
+
+    Ok(())
+}
+
+/// Переименовать сервер
+pub fn rename(old: &str, new: &str, output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    config::validate_name(new).map_err(SecureSshError::InvalidConfig)?;
+
+    let mut vault = unlock_vault()?;
+    let servers = vault.servers_mut();
+
+    let old = super::connect::select_server(servers, Some(old.to_string()))?
+        .name
+        .clone();
+
+    if old != new && servers.get(new).is_some() {
+        return Err(SecureSshError::ServerAlreadyExists(new.to_string()));
+    }
+    servers
+        .rename(&old, new)
+        .map_err(|e| SecureSshError::Other(e.to_string()))?;
+
+    vault.save()?;
+
+    if output.is_json() {
+        return print_server_json(vault.servers(), new);
+    }
+
+    println!("{} {}", t!(LABEL_SUCCESS).green().bold(), t!(SERVER_RENAMED, old, new));
+
+    Ok(())
+}
+
+/// Показать все поля сервера
+pub fn show(name: &str, output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    let vault = unlock_vault()?;
+    let servers = vault.servers();
+
+    let server = super::connect::select_server(servers, Some(name.to_string()))?;
+
+    if output.is_json() {
+        return super::print_json(&ServerJson::from(server));
+    }
+
+    println!();
+    println!("{}", t!(SERVER_SHOW_TITLE, server.name).cyan().bold());
+    println!();
+    println!("  {:<14} {}", t!(FIELD_HOST), server.host);
+    println!("  {:<14} {}", t!(FIELD_PORT), server.port);
+    println!("  {:<14} {}", t!(FIELD_USER), server.user);
+    println!("  {:<14} {}", t!(FIELD_CONNECTION), server.connection_string());
+    if !server.description.is_empty() {
+        println!("  {:<14} {}", t!(FIELD_DESCRIPTION), server.description);
+    }
+    if !server.group.is_empty() {
+        println!("  {:<14} {}", t!(FIELD_GROUP), server.group);
+    }
+    if !server.tags.is_empty() {
+        println!("  {:<14} {}", t!(FIELD_TAGS), server.tags.join(", "));
+    }
+    if !server.proxy_jump.is_empty() {
+        println!("  {:<14} {}", t!(FIELD_VIA), server.proxy_jump.join(" → "));
+    }
+    if !server.forwards.is_empty() {
+        println!("  {}", t!(FORWARDS));
+        for (i, forward) in server.forwards.iter().enumerate() {
+            println!("    {} {}", format!("[{}]", i + 1).cyan(), forward);
+        }
+    }
     println!();
 
-    let password = prompt_password()?;
+    Ok(())
+}
 
-    // Загрузить существующий ключ для получения соли
-    let (_, salt) = config::load_encrypted_key(password.as_bytes())?;
+/// Показать список настроенных серверов, сгруппированный по группам
+///
+/// `tag` оставляет только серверы с этим тегом.
+pub fn list(tag: Option<String>, output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
 
-    // Загрузить серверы
-    let servers = config::load_servers(password.as_bytes(), &salt)?;
+    if !output.is_json() {
+        println!("{}", t!(SERVER_LIST_TITLE).cyan().bold());
+        println!();
+    }
+
+    let vault = unlock_vault()?;
+    let servers = vault.servers();
+
+    // Тег можно указать и как селектор (@prod)
+    let tag = tag.map(|t| t.trim_start_matches(config::TAG_PREFIX).to_string());
+    let mut shown: Vec<&Server> = servers
+        .iter()
+        .filter(|s| tag.as_ref().map_or(true, |t| s.has_tag(t)))
+        .collect();
+
+    // Серверы без группы первыми, затем по дереву групп; внутри группы порядок добавления
+    shown.sort_by(|a, b| group_segments(a).cmp(&group_segments(b)));
+
+    if output.is_json() {
+        let servers: Vec<ServerJson> = shown.into_iter().map(ServerJson::from).collect();
+        return super::print_json(&serde_json::json!({ "servers": servers }));
+    }
 
     if servers.is_empty() {
-        println!("Серверы не настроены.");
+        println!("{}", t!(SERVER_LIST_EMPTY));
         println!();
-        println!(
-            "Выполните {} для добавления сервера.",
-            "secure-ssh server add".cyan()
-        );
+        println!("{}", t!(SERVER_LIST_ADD_HINT, "secure-ssh server add".cyan()));
+        return Ok(());
+    }
+
+    if shown.is_empty() {
+        println!("{}", t!(SERVER_LIST_NO_TAG, tag.unwrap_or_default()));
         return Ok(());
     }
 
     println!(
-        "{:<15} {:<30} {:<20}",
-        "ИМЯ".bold(),
-        "ПОДКЛЮЧЕНИЕ".bold(),
-        "ОПИСАНИЕ".bold()
+        "{:<15} {:<30} {:<20} {}",
+        t!(COLUMN_NAME).bold(),
+        t!(COLUMN_CONNECTION).bold(),
+        t!(COLUMN_DESCRIPTION).bold(),
+        t!(COLUMN_TAGS).bold()
     );
-    println!("{}", "─".repeat(65).dimmed());
+    println!("{}", "─".repeat(75).dimmed());
+
+    let mut current: Vec<&str> = Vec::new();
+
+    for server in shown {
+        let path = group_segments(server);
+
+        // Заголовки групп, которых не было у предыдущего сервера
+        let common = current.iter().zip(&path).take_while(|(a, b)| a == b).count();
+        for (depth, segment) in path.iter().enumerate().skip(common) {
+            println!(
+                "{}{}",
+                "  ".repeat(depth),
+                format!("{}/", segment).yellow().bold()
+            );
+        }
+
+        // Отступ по глубине группы, не сдвигая остальные колонки
+        let indent = "  ".repeat(path.len());
+        let name_width = 15usize.saturating_sub(indent.len());
+        let tags = server
+            .tags
+            .iter()
+            .map(|t| format!("{}{}", config::TAG_PREFIX, t))
+            .collect::<Vec<_>>()
+            .join(" ");
 
-    for server in servers.iter() {
         println!(
-            "{:<15} {:<30} {:<20}",
+            "{}{:<name_width$} {:<30} {:<20} {}",
+            indent,
             server.name,
             server.connection_string(),
-            server.description
+            server.description,
+            tags.dimmed(),
+            name_width = name_width
         );
+
+        current = path;
     }
 
     println!();
     Ok(())
 }
 
+/// Сервер для `--output json`: все поля присутствуют, даже пустые
+#[derive(Serialize)]
+struct ServerJson<'a> {
+    name: &'a str,
+    host: &'a str,
+    port: u16,
+    user: &'a str,
+    description: &'a str,
+    group: &'a str,
+    tags: &'a [String],
+    proxy_jump: &'a [String],
+    forwards: &'a [Forward],
+}
+
+impl<'a> From<&'a Server> for ServerJson<'a> {
+    fn from(server: &'a Server) -> Self {
+        Self {
+            name: &server.name,
+            host: &server.host,
+            port: server.port,
+            user: &server.user,
+            description: &server.description,
+            group: &server.group,
+            tags: &server.tags,
+            proxy_jump: &server.proxy_jump,
+            forwards: &server.forwards,
+        }
+    }
+}
+
+/// Сегменты пути группы сервера
+fn group_segments(server: &Server) -> Vec<&str> {
+    server.group.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+/// Вывести сервер после изменения, как `server show --output json`
+fn print_server_json(servers: &ServerList, name: &str) -> Result<()> {
+    let server = servers
+        .get(name)
+        .ok_or_else(|| SecureSshError::ServerNotFound(name.to_string()))?;
+    super::print_json(&ServerJson::from(server))
+}
+
 /// Удалить сервер
-pub fn remove(name: &str) -> Result<()> {
+pub fn remove(name: &str, output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    if !output.is_json() {
+        println!("{}", t!(SERVER_REMOVE_TITLE).cyan().bold());
+        println!();
+    }
+
+    let mut vault = unlock_vault()?;
+    let servers = vault.servers_mut();
+
+    // Удалить сервер
+    let name = super::connect::select_server(servers, Some(name.to_string()))?
+        .name
+        .clone();
+    servers.remove(&name);
+
+    // Сохранить обновлённый список
+    vault.save()?;
+
+    if output.is_json() {
+        return super::print_json(&serde_json::json!({ "removed": name }));
+    }
+
+    println!("{} {}", t!(LABEL_SUCCESS).green().bold(), t!(SERVER_REMOVED, name));
+
+    Ok(())
+}
+
+/// Добавить постоянные пробросы серверу
+pub fn forward_add(name: &str, forward_args: ForwardArgs, output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    if forward_args.is_empty() {
+        return Err(SecureSshError::InvalidConfig(t!(FORWARD_ADD_NO_FORWARDS).into()));
+    }
+    let forwards = forward_args.parse()?;
+
+    let mut vault = unlock_vault()?;
+    let servers = vault.servers_mut();
+
+    let name = super::connect::select_server(servers, Some(name.to_string()))?
+        .name
+        .clone();
+    let server = servers
+        .get_mut(&name)
+        .ok_or_else(|| SecureSshError::ServerNotFound(name.clone()))?;
+
+    for forward in forwards {
+        if server.forwards.contains(&forward) {
+            continue;
+        }
+        if !output.is_json() {
+            println!("  {} {}", "+".green(), forward);
+        }
+        server.forwards.push(forward);
+    }
+
+    vault.save()?;
+
+    if output.is_json() {
+        return print_server_json(vault.servers(), &name);
+    }
+
+    println!("{} {}", t!(LABEL_SUCCESS).green().bold(), t!(FORWARDS_UPDATED, name));
+
+    Ok(())
+}
+
+/// Показать постоянные пробросы сервера
+pub fn forward_list(name: &str, output: OutputFormat) -> Result<()> {
     if !config::is_initialized()? {
         return Err(SecureSshError::NotInitialized);
     }
 
-    println!("{}", "=== Удаление сервера ===".cyan().bold());
+    let vault = unlock_vault()?;
+    let servers = vault.servers();
+
+    let name = super::connect::select_server(servers, Some(name.to_string()))?
+        .name
+        .clone();
+    let server = servers
+        .get(&name)
+        .ok_or_else(|| SecureSshError::ServerNotFound(name.clone()))?;
+
+    if output.is_json() {
+        return super::print_json(&serde_json::json!({
+            "server": name,
+            "forwards": server.forwards,
+        }));
+    }
+
+    if server.forwards.is_empty() {
+        println!("{}", t!(FORWARDS_NONE, name));
+        return Ok(());
+    }
+
+    println!("{}", t!(FORWARDS_TITLE, name).cyan().bold());
     println!();
+    for (i, forward) in server.forwards.iter().enumerate() {
+        println!("  {} {}", format!("[{}]", i + 1).cyan(), forward);
+    }
+    println!();
+
+    Ok(())
+}
 
-    let password = prompt_password()?;
+/// Удалить постоянный проброс по номеру из `server forward list`
+pub fn forward_remove(name: &str, number: usize, output: OutputFormat) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
 
-    // Загрузить существующий ключ для получения соли
-    let (_, salt) = config::load_encrypted_key(password.as_bytes())?;
+    let mut vault = unlock_vault()?;
+    let servers = vault.servers_mut();
 
-    // Загрузить серверы
-    let mut servers = config::load_servers(password.as_bytes(), &salt)?;
+    let name = super::connect::select_server(servers, Some(name.to_string()))?
+        .name
+        .clone();
+    let server = servers
+        .get_mut(&name)
+        .ok_or_else(|| SecureSshError::ServerNotFound(name.clone()))?;
 
-    // Удалить сервер
-    if servers.remove(name).is_none() {
-        return Err(SecureSshError::ServerNotFound(name.to_string()));
+    if number == 0 || number > server.forwards.len() {
+        return Err(SecureSshError::InvalidConfig(t!(FORWARD_NO_NUMBER, name, number)));
     }
+    let removed = server.forwards.remove(number - 1);
 
-    // Сохранить обновлённый список
-    let derived_key = crypto::derive_key(password.as_bytes(), Some(&salt))?;
-    config::save_servers(&servers, &derived_key)?;
+    vault.save()?;
 
-    println!(
-        "{} Сервер '{}' удалён.",
-        "Успех:".green().bold(),
-        name
-    );
+    if output.is_json() {
+        return super::print_json(&serde_json::json!({
+            "server": name,
+            "removed": removed,
+        }));
+    }
+
+    println!("{} {}", t!(LABEL_SUCCESS).green().bold(), t!(FORWARD_REMOVED, removed));
 
     Ok(())
 }
@@ -137,29 +554,23 @@ pub fn remove(name: &str) -> Result<()> {
 /// Запросить данные сервера
 fn prompt_server_details() -> Result<Server> {
     // Имя сервера
-    print!("Имя сервера: ");
+    print!("{}", t!(PROMPT_NAME));
     io::stdout().flush()?;
     let mut name = String::new();
     io::stdin().read_line(&mut name)?;
     let name = name.trim().to_string();
 
-    if name.is_empty() {
-        return Err(SecureSshError::InvalidConfig("Имя сервера не может быть пустым".into()));
-    }
+    config::validate_name(&name).map_err(SecureSshError::InvalidConfig)?;
 
     // Хост
-    print!("Хост/IP: ");
+    print!("{}", t!(PROMPT_HOST));
     io::stdout().flush()?;
     let mut host = String::new();
     io::stdin().read_line(&mut host)?;
-    let host = host.trim().to_string();
-
-    if host.is_empty() {
-        return Err(SecureSshError::InvalidConfig("Хост не может быть пустым".into()));
-    }
+    let host = config::parse_host(&host).map_err(SecureSshError::InvalidConfig)?;
 
     // Порт
-    print!("Порт [22]: ");
+    print!("{}", t!(PROMPT_PORT));
     io::stdout().flush()?;
     let mut port_str = String::new();
     io::stdin().read_line(&mut port_str)?;
@@ -169,31 +580,45 @@ fn prompt_server_details() -> Result<Server> {
     } else {
         port_str
             .parse()
-            .map_err(|_| SecureSshError::InvalidConfig("Неверный номер порта".into()))?
+            .map_err(|_| SecureSshError::InvalidConfig(t!(INVALID_PORT).into()))?
     };
+    config::validate_port(port).map_err(SecureSshError::InvalidConfig)?;
 
     // Пользователь
-    print!("Имя пользователя: ");
+    print!("{}", t!(PROMPT_USER));
     io::stdout().flush()?;
     let mut user = String::new();
     io::stdin().read_line(&mut user)?;
     let user = user.trim().to_string();
-
-    if user.is_empty() {
-        return Err(SecureSshError::InvalidConfig("Имя пользователя не может быть пустым".into()));
-    }
+    config::validate_user(&user).map_err(SecureSshError::InvalidConfig)?;
 
     // Описание
-    print!("Описание (опционально): ");
+    print!("{}", t!(PROMPT_DESCRIPTION));
     io::stdout().flush()?;
     let mut description = String::new();
     io::stdin().read_line(&mut description)?;
     let description = description.trim().to_string();
 
+    // Группа
+    print!("{}", t!(PROMPT_GROUP));
+    io::stdout().flush()?;
+    let mut group = String::new();
+    io::stdin().read_line(&mut group)?;
+    let group = config::parse_group(&group).map_err(SecureSshError::InvalidConfig)?;
+
+    // Теги
+    print!("{}", t!(PROMPT_TAGS));
+    io::stdout().flush()?;
+    let mut tags = String::new();
+    io::stdin().read_line(&mut tags)?;
+    let tags = config::parse_tags(&tags).map_err(SecureSshError::InvalidConfig)?;
+
     let mut server = Server::new(name, host, port, user);
     if !description.is_empty() {
         server = server.with_description(description);
     }
+    server.group = group;
+    server.tags = tags;
 
     Ok(server)
 }
@@ -0,0 +1,87 @@
+//! Режим туннеля: только пробросы портов, без shell (аналог `ssh -N`)
+
+use colored::Colorize;
+
+use crate::config::{self, Forward};
+use crate::error::{Result, SecureSshError};
+use crate::ssh;
+use crate::watchdog;
+
+use super::connect::Target;
+use super::ForwardArgs;
+
+pub fn run(server_name: Option<String>, forward_args: ForwardArgs) -> Result<()> {
+    if !config::is_initialized()? {
+        return Err(SecureSshError::NotInitialized);
+    }
+
+    let extra_forwards = forward_args.parse()?;
+
+    let (private_key, servers) = super::connect::unlock()?;
+    let target = Target::new(&servers, super::connect::select_server(&servers, server_name)?)?;
+    let server = &target.server;
+
+    let mut forwards = server.forwards.clone();
+    forwards.extend(extra_forwards);
+
+    if forwards.is_empty() {
+        return Err(SecureSshError::InvalidConfig(t!(TUNNEL_NO_FORWARDS, server.name)));
+    }
+
+    println!();
+    target.print_connecting();
+
+    let watchdog = watchdog::create_watchdog();
+    if watchdog.is_some() {
+        println!("{}", t!(TUNNEL_WATCHDOG).dimmed());
+    }
+
+    println!();
+
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| SecureSshError::Other(t!(RUNTIME_FAILED, e)))?;
+
+    let result = runtime.block_on(async {
+        connect_and_tunnel(&target, &private_key, &forwards, watchdog).await
+    });
+
+    drop(private_key);
+
+    match result {
+        Ok(()) => {
+            println!();
+            println!("{}", t!(TUNNEL_CLOSED).green());
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Подключиться к серверу, запустить пробросы и держать соединение
+async fn connect_and_tunnel(
+    target: &Target,
+    private_key: &[u8],
+    forwards: &[Forward],
+    watchdog: Option<Box<dyn watchdog::UsbWatchdog>>,
+) -> Result<()> {
+    let session = ssh::connect(
+        &target.route,
+        private_key,
+        ssh::SshClient::with_forwards(forwards),
+    )
+    .await?;
+
+    let (session, forward_set) = ssh::ForwardSet::start(session, forwards).await;
+
+    println!("{}", t!(FORWARDS).cyan());
+    super::print_forward_statuses(forward_set.statuses());
+    println!();
+
+    if !forward_set.any_active() {
+        return Err(SecureSshError::ForwardFailed(t!(TUNNEL_NONE_STARTED).into()));
+    }
+
+    println!("{}", t!(TUNNEL_ACTIVE).green());
+
+    ssh::run_tunnel(session, watchdog, forward_set).await
+}
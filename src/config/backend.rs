@@ -0,0 +1,348 @@
+//! Storage backends for the vault files
+//!
+//! The encrypted format lives in `storage`; a backend only moves whole
+//! files in and out. [`FsStorage`] keeps them in a data directory,
+//! [`MemoryStorage`] in memory for tests and embedding. Other places
+//! (a mirror drive, a network share) implement [`Storage`] too.
+//!
+//! [`FsStorage`] replaces a file atomically: the new contents go to a
+//! temporary file that is synced and renamed over the old one, so pulling
+//! the drive mid-write leaves either the old or the new file. The replaced
+//! generation is kept as `<name>.bak` for [`Storage::read_previous`].
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::Result;
+
+/// A file of the vault
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VaultFile {
+    /// Encrypted private key; its header holds the salt
+    Key,
+    /// Public key in OpenSSH format (plaintext)
+    PublicKey,
+    /// Encrypted server list
+    Servers,
+    /// Single-file vault holding all of the above (see `container`)
+    Container,
+}
+
+impl VaultFile {
+    /// Every file of a vault
+    pub const ALL: [Self; 4] = [Self::Key, Self::PublicKey, Self::Servers, Self::Container];
+
+    /// File name in a data directory
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Key => "key.enc",
+            Self::PublicKey => "key.pub",
+            Self::Servers => "servers.enc",
+            Self::Container => "vault.enc",
+        }
+    }
+
+    /// Whether the file is encrypted and should not be readable by others
+    pub fn is_secret(self) -> bool {
+        !matches!(self, Self::PublicKey)
+    }
+}
+
+/// Where the vault files are kept
+pub trait Storage: Send + Sync {
+    /// Read a whole file; `None` if it does not exist
+    fn read(&self, file: VaultFile) -> Result<Option<Vec<u8>>>;
+
+    /// Replace a whole file
+    fn write(&self, file: VaultFile, data: &[u8]) -> Result<()>;
+
+    /// Delete a file and its previous generation; deleting a missing file is not an error
+    fn remove(&self, file: VaultFile) -> Result<()>;
+
+    /// Contents the last [`write`](Self::write) replaced, if the backend keeps them
+    fn read_previous(&self, _file: VaultFile) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Replace the previous generation in place, leaving the current file alone
+    fn write_previous(&self, _file: VaultFile, _data: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Delete the previous generation only; deleting a missing one is not an error
+    fn remove_previous(&self, _file: VaultFile) -> Result<()> {
+        Ok(())
+    }
+
+    fn exists(&self, file: VaultFile) -> Result<bool> {
+        Ok(self.read(file)?.is_some())
+    }
+}
+
+/// Vault files in a directory (`key.enc`, `key.pub`, `servers.enc`)
+#[derive(Debug, Clone)]
+pub struct FsStorage {
+    dir: PathBuf,
+}
+
+impl FsStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, file: VaultFile) -> PathBuf {
+        self.dir.join(file.file_name())
+    }
+
+    fn previous_path(&self, file: VaultFile) -> PathBuf {
+        self.dir.join(format!("{}.bak", file.file_name()))
+    }
+
+    /// Write `data` to a temporary file next to `path`, sync it and rename it over `path`
+    fn replace(&self, path: &Path, data: &[u8], secret: bool) -> Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        // Create a secret file with restrictive permissions on Unix
+        #[cfg(unix)]
+        if secret {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut out = options.open(&tmp)?;
+
+        // mode() only applies on creation: a stale temporary file left by a
+        // crash is narrowed too, before anything is written
+        #[cfg(unix)]
+        if secret {
+            use std::os::unix::fs::PermissionsExt;
+            out.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        #[cfg(not(unix))]
+        let _ = secret;
+
+        out.write_all(data)?;
+        out.sync_all()?;
+        drop(out);
+
+        fs::rename(&tmp, path)?;
+        sync_dir(&self.dir)
+    }
+}
+
+/// Make renames in `dir` durable
+///
+/// Windows cannot open a directory for syncing; there the rename is already
+/// written through by the time it returns.
+fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Remove a file that may not exist
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+impl Storage for FsStorage {
+    fn read(&self, file: VaultFile) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.path(file)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write(&self, file: VaultFile, data: &[u8]) -> Result<()> {
+        if !self.dir.exists() {
+            fs::create_dir_all(&self.dir)?;
+        }
+
+        // Keep the generation being replaced; the current file is untouched
+        // until the new one is complete
+        if let Some(current) = self.read(file)? {
+            self.replace(&self.previous_path(file), &current, file.is_secret())?;
+        }
+        self.replace(&self.path(file), data, file.is_secret())
+    }
+
+    fn remove(&self, file: VaultFile) -> Result<()> {
+        remove_if_exists(&self.path(file))?;
+        self.remove_previous(file)
+    }
+
+    fn read_previous(&self, file: VaultFile) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.previous_path(file)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_previous(&self, file: VaultFile, data: &[u8]) -> Result<()> {
+        self.replace(&self.previous_path(file), data, file.is_secret())
+    }
+
+    fn remove_previous(&self, file: VaultFile) -> Result<()> {
+        remove_if_exists(&self.previous_path(file))
+    }
+
+    fn exists(&self, file: VaultFile) -> Result<bool> {
+        Ok(self.path(file).exists())
+    }
+}
+
+/// Vault files kept in memory; nothing touches the disk
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    files: Mutex<HashMap<VaultFile, Vec<u8>>>,
+    previous: Mutex<HashMap<VaultFile, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, file: VaultFile) -> Result<Option<Vec<u8>>> {
+        Ok(self.files.lock().unwrap().get(&file).cloned())
+    }
+
+    fn write(&self, file: VaultFile, data: &[u8]) -> Result<()> {
+        if let Some(current) = self.files.lock().unwrap().insert(file, data.to_vec()) {
+            self.previous.lock().unwrap().insert(file, current);
+        }
+        Ok(())
+    }
+
+    fn remove(&self, file: VaultFile) -> Result<()> {
+        self.files.lock().unwrap().remove(&file);
+        self.previous.lock().unwrap().remove(&file);
+        Ok(())
+    }
+
+    fn read_previous(&self, file: VaultFile) -> Result<Option<Vec<u8>>> {
+        Ok(self.previous.lock().unwrap().get(&file).cloned())
+    }
+
+    fn write_previous(&self, file: VaultFile, data: &[u8]) -> Result<()> {
+        self.previous.lock().unwrap().insert(file, data.to_vec());
+        Ok(())
+    }
+
+    fn remove_previous(&self, file: VaultFile) -> Result<()> {
+        self.previous.lock().unwrap().remove(&file);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_storage_roundtrip() {
+        let storage = MemoryStorage::new();
+        assert!(!storage.exists(VaultFile::Key).unwrap());
+
+        storage.write(VaultFile::Key, b"one").unwrap();
+        assert_eq!(storage.read_previous(VaultFile::Key).unwrap(), None);
+        storage.write(VaultFile::Key, b"two").unwrap();
+        assert_eq!(storage.read(VaultFile::Key).unwrap().as_deref(), Some(&b"two"[..]));
+        assert_eq!(storage.read_previous(VaultFile::Key).unwrap().as_deref(), Some(&b"one"[..]));
+        assert_eq!(storage.read(VaultFile::Servers).unwrap(), None);
+
+        storage.remove(VaultFile::Key).unwrap();
+        storage.remove(VaultFile::Key).unwrap();
+        assert!(!storage.exists(VaultFile::Key).unwrap());
+        assert_eq!(storage.read_previous(VaultFile::Key).unwrap(), None);
+    }
+
+    #[test]
+    fn test_fs_storage_creates_dir() {
+        let dir = std::env::temp_dir().join(format!("secure-ssh-fs-{}", std::process::id()));
+        let storage = FsStorage::new(dir.join("data"));
+
+        storage.write(VaultFile::PublicKey, b"ssh-ed25519 AAAA\n").unwrap();
+        let read = storage.read(VaultFile::PublicKey);
+        let missing = storage.read(VaultFile::Key);
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(read.unwrap().as_deref(), Some(&b"ssh-ed25519 AAAA\n"[..]));
+        assert_eq!(missing.unwrap(), None);
+    }
+
+    #[test]
+    fn test_fs_storage_keeps_previous_generation() {
+        let dir = std::env::temp_dir().join(format!("secure-ssh-gen-{}", std::process::id()));
+        let storage = FsStorage::new(&dir);
+
+        storage.write(VaultFile::Key, b"one").unwrap();
+        storage.write(VaultFile::Key, b"two").unwrap();
+        let current = storage.read(VaultFile::Key).unwrap();
+        let previous = storage.read_previous(VaultFile::Key).unwrap();
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+
+        storage.write_previous(VaultFile::Key, b"one, rewritten").unwrap();
+        let rewritten = storage.read_previous(VaultFile::Key).unwrap();
+        storage.remove_previous(VaultFile::Key).unwrap();
+        let dropped = storage.read_previous(VaultFile::Key).unwrap();
+        let kept = storage.read(VaultFile::Key).unwrap();
+
+        storage.remove(VaultFile::Key).unwrap();
+        let left = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(current.as_deref(), Some(&b"two"[..]));
+        assert_eq!(previous.as_deref(), Some(&b"one"[..]));
+        // No temporary files are left behind
+        assert_eq!(names, ["key.enc", "key.enc.bak"]);
+        assert_eq!(rewritten.as_deref(), Some(&b"one, rewritten"[..]));
+        assert_eq!(dropped, None);
+        assert_eq!(kept.as_deref(), Some(&b"two"[..]));
+        assert_eq!(left, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fs_storage_secret_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("secure-ssh-mode-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let storage = FsStorage::new(&dir);
+
+        // A temporary file left behind by a crash, readable by others
+        let stale = dir.join("key.enc.tmp");
+        fs::write(&stale, b"stale").unwrap();
+        fs::set_permissions(&stale, fs::Permissions::from_mode(0o644)).unwrap();
+
+        storage.write(VaultFile::Key, b"secret").unwrap();
+        let mode = fs::metadata(dir.join("key.enc")).unwrap().permissions().mode();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
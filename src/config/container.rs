@@ -0,0 +1,169 @@
+//! Single-file vault container
+//!
+//! Instead of `key.enc`, `key.pub` and `servers.enc`, a container vault keeps
+//! everything in one encrypted file, `vault.enc`. Its plaintext is a list of
+//! sections padded to a power-of-two size of at least 16 KiB, so neither the
+//! number of servers nor the identity in the public key shows from outside,
+//! and the parts cannot get out of sync.
+//!
+//! Plaintext layout:
+//! ([1 byte: section id][4 bytes: length][data])* [1 byte: 0] [zero padding]
+//!
+//! Sections this build does not know are kept as they are when the
+//! container is rewritten. Ids 4 (pinned host keys) and 5 (settings) are
+//! reserved; nothing stores either yet.
+
+use crate::crypto::SecureBytes;
+
+/// Smallest padded plaintext
+const MIN_PADDED_LEN: usize = 16 * 1024;
+
+/// Id that ends the section list
+const END: u8 = 0;
+
+/// Known sections of a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// SSH private key
+    PrivateKey = 1,
+    /// Public key in OpenSSH format
+    PublicKey = 2,
+    /// Server list as JSON
+    Servers = 3,
+}
+
+/// Decrypted sections of a container
+#[derive(Debug, Default)]
+pub struct Container {
+    sections: Vec<(u8, SecureBytes)>,
+}
+
+impl Clone for Container {
+    fn clone(&self) -> Self {
+        Self {
+            sections: self
+                .sections
+                .iter()
+                .map(|(id, data)| (*id, SecureBytes::from(&data[..])))
+                .collect(),
+        }
+    }
+}
+
+impl Container {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, section: Section) -> Option<&[u8]> {
+        self.sections
+            .iter()
+            .find(|(id, _)| *id == section as u8)
+            .map(|(_, data)| &data[..])
+    }
+
+    /// Replace a section, or add it
+    pub fn set(&mut self, section: Section, data: &[u8]) {
+        let data = SecureBytes::from(data);
+        match self.sections.iter_mut().find(|(id, _)| *id == section as u8) {
+            Some(entry) => entry.1 = data,
+            None => self.sections.push((section as u8, data)),
+        }
+    }
+
+    /// Serialize and pad
+    pub fn encode(&self) -> SecureBytes {
+        let len = self.sections.iter().map(|(_, data)| 5 + data.len()).sum::<usize>() + 1;
+
+        let mut body = Vec::with_capacity(padded_len(len));
+        for (id, data) in &self.sections {
+            body.push(*id);
+            body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            body.extend_from_slice(data);
+        }
+        body.push(END);
+        body.resize(padded_len(len), 0);
+
+        SecureBytes::new(body)
+    }
+
+    /// Parse a decrypted plaintext, ignoring the padding
+    pub fn decode(body: &[u8]) -> Result<Self, String> {
+        let mut sections: Vec<(u8, SecureBytes)> = Vec::new();
+        let mut rest = body;
+
+        loop {
+            let (&id, tail) = rest.split_first().ok_or(t!(CONTAINER_TRUNCATED))?;
+            if id == END {
+                return Ok(Self { sections });
+            }
+            if tail.len() < 4 {
+                return Err(t!(CONTAINER_TRUNCATED).into());
+            }
+            let len = u32::from_be_bytes([tail[0], tail[1], tail[2], tail[3]]) as usize;
+            let data = tail[4..].get(..len).ok_or(t!(CONTAINER_TRUNCATED))?;
+            if sections.iter().any(|(existing, _)| *existing == id) {
+                return Err(t!(CONTAINER_SECTION_TWICE, id));
+            }
+            sections.push((id, SecureBytes::from(data)));
+            rest = &tail[4 + len..];
+        }
+    }
+}
+
+/// Padded plaintext length for `len` bytes of sections
+fn padded_len(len: usize) -> usize {
+    len.max(MIN_PADDED_LEN).next_power_of_two()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_and_padding() {
+        let mut container = Container::new();
+        container.set(Section::PrivateKey, b"private");
+        container.set(Section::Servers, b"[]");
+        container.set(Section::Servers, br#"[{"name":"web"}]"#);
+
+        let body = container.encode();
+        assert_eq!(body.len(), MIN_PADDED_LEN);
+
+        let decoded = Container::decode(&body).unwrap();
+        assert_eq!(decoded.get(Section::PrivateKey), Some(&b"private"[..]));
+        assert_eq!(decoded.get(Section::Servers), Some(&br#"[{"name":"web"}]"#[..]));
+        assert_eq!(decoded.get(Section::PublicKey), None);
+
+        // Size only grows in power-of-two steps
+        let mut big = Container::new();
+        big.set(Section::Servers, &vec![b'x'; MIN_PADDED_LEN]);
+        assert_eq!(big.encode().len(), 2 * MIN_PADDED_LEN);
+    }
+
+    #[test]
+    fn test_keeps_unknown_sections() {
+        let mut body = vec![42u8];
+        body.extend_from_slice(&3u32.to_be_bytes());
+        body.extend_from_slice(b"new");
+        body.push(END);
+
+        let mut container = Container::decode(&body).unwrap();
+        container.set(Section::PublicKey, b"ssh-ed25519 AAAA");
+
+        let decoded = Container::decode(&container.encode()).unwrap();
+        assert_eq!(decoded.sections[0].0, 42);
+        assert_eq!(&decoded.sections[0].1[..], b"new");
+        assert_eq!(decoded.get(Section::PublicKey), Some(&b"ssh-ed25519 AAAA"[..]));
+    }
+
+    #[test]
+    fn test_rejects_truncated() {
+        let mut container = Container::new();
+        container.set(Section::PrivateKey, b"private");
+        let body = container.encode();
+
+        assert!(Container::decode(&body[..8]).is_err());
+        assert!(Container::decode(&[]).is_err());
+    }
+}
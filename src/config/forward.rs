@@ -0,0 +1,266 @@
+//! Port and Unix socket forwarding specifications
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Default bind address for locally listening forwards
+pub const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1";
+
+/// One end of a forward: a TCP address or a Unix socket path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Endpoint {
+    /// TCP host and port
+    Tcp { host: String, port: u16 },
+    /// Unix domain socket path
+    Unix { path: String },
+}
+
+impl Endpoint {
+    /// Create a TCP endpoint
+    pub fn tcp(host: impl Into<String>, port: u16) -> Self {
+        Self::Tcp {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// Create a Unix socket endpoint
+    pub fn unix(path: impl Into<String>) -> Self {
+        Self::Unix { path: path.into() }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp { host, port } if host.contains(':') => write!(f, "[{}]:{}", host, port),
+            Self::Tcp { host, port } => write!(f, "{}:{}", host, port),
+            Self::Unix { path } => f.write_str(path),
+        }
+    }
+}
+
+/// A forward established over the SSH connection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Forward {
+    /// Listen locally and connect to `target` from the server side (`ssh -L`).
+    /// A Unix socket target uses `direct-streamlocal@openssh.com`.
+    Local { listen: Endpoint, target: Endpoint },
+    /// Listen on the server and connect to a local `target` (`ssh -R`).
+    /// A Unix socket listener uses `streamlocal-forward@openssh.com`.
+    Remote { listen: Endpoint, target: Endpoint },
+    /// Local SOCKS5 proxy tunnelling connections through the server (`ssh -D`)
+    Dynamic { listen: Endpoint },
+}
+
+impl Forward {
+    /// Parse a local forward in `ssh -L` syntax:
+    ///
+    /// - `[bind:]port:host:hostport`
+    /// - `[bind:]port:/remote/socket`
+    /// - `/local/socket:host:hostport`
+    /// - `/local/socket:/remote/socket`
+    pub fn parse_local(spec: &str) -> Result<Self, String> {
+        let parts = split_spec(spec)?;
+
+        let (listen, rest) = parse_listen(&parts)?;
+        let target = parse_target(rest)?;
+
+        Ok(Self::Local { listen, target })
+    }
+
+    /// Parse a remote forward in `ssh -R` syntax:
+    ///
+    /// - `[bind:]port:host:hostport`
+    /// - `[bind:]port:/local/socket`
+    /// - `/remote/socket:host:hostport`
+    /// - `/remote/socket:/local/socket`
+    pub fn parse_remote(spec: &str) -> Result<Self, String> {
+        let parts = split_spec(spec)?;
+
+        let (listen, rest) = parse_listen(&parts)?;
+        let target = parse_target(rest)?;
+
+        match listen {
+            Endpoint::Tcp { port: 0, .. } => Err(t!(FORWARD_REMOTE_PORT).into()),
+            listen => Ok(Self::Remote { listen, target }),
+        }
+    }
+
+    /// Parse a dynamic (SOCKS) forward in `ssh -D` syntax: `[bind:]port`
+    pub fn parse_dynamic(spec: &str) -> Result<Self, String> {
+        let parts = split_spec(spec)?;
+
+        let listen = match parts.as_slice() {
+            [port] => Endpoint::tcp(DEFAULT_BIND_ADDRESS, parse_port(port)?),
+            [host, port] => Endpoint::tcp(host.as_str(), parse_port(port)?),
+            _ => return Err(t!(FORWARD_DYNAMIC_INVALID, spec)),
+        };
+
+        Ok(Self::Dynamic { listen })
+    }
+}
+
+impl fmt::Display for Forward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local { listen, target } => write!(f, "-L {} -> {}", listen, target),
+            Self::Remote { listen, target } => write!(f, "-R {} -> {}", listen, target),
+            Self::Dynamic { listen } => write!(f, "-D {} (SOCKS5)", listen),
+        }
+    }
+}
+
+/// Split a forward spec on ':' while keeping bracketed IPv6 addresses intact
+fn split_spec(spec: &str) -> Result<Vec<String>, String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_brackets = false;
+
+    for c in spec.chars() {
+        match c {
+            '[' if !in_brackets && current.is_empty() => in_brackets = true,
+            ']' if in_brackets => in_brackets = false,
+            ':' if !in_brackets => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+
+    if in_brackets {
+        return Err(t!(UNTERMINATED_BRACKET, spec));
+    }
+    parts.push(current);
+
+    if parts.iter().any(|p| p.is_empty()) {
+        return Err(t!(FORWARD_EMPTY_FIELD, spec));
+    }
+
+    Ok(parts)
+}
+
+/// Parse the listening side, returning the remaining (target) fields
+fn parse_listen(parts: &[String]) -> Result<(Endpoint, &[String]), String> {
+    let first = parts.first().ok_or(t!(FORWARD_SPEC_EMPTY))?;
+
+    if first.starts_with('/') {
+        return Ok((Endpoint::unix(first.as_str()), &parts[1..]));
+    }
+
+    // Either "port:..." or "bind:port:..." - the bind form has one field more
+    let target_len = if parts.last().is_some_and(|p| p.starts_with('/')) { 1 } else { 2 };
+
+    match parts.len().checked_sub(target_len) {
+        Some(1) => Ok((Endpoint::tcp(DEFAULT_BIND_ADDRESS, parse_port(first)?), &parts[1..])),
+        Some(2) => Ok((Endpoint::tcp(first.as_str(), parse_port(&parts[1])?), &parts[2..])),
+        _ => Err(t!(FORWARD_FIELD_COUNT, parts.join(":"))),
+    }
+}
+
+/// Parse the target side: "/remote/socket" or "host:port"
+fn parse_target(parts: &[String]) -> Result<Endpoint, String> {
+    match parts {
+        [path] if path.starts_with('/') => Ok(Endpoint::unix(path.as_str())),
+        [host, port] => Ok(Endpoint::tcp(host.as_str(), parse_port(port)?)),
+        _ => Err(t!(FORWARD_TARGET_INVALID, parts.join(":"))),
+    }
+}
+
+fn parse_port(s: &str) -> Result<u16, String> {
+    s.parse().map_err(|_| t!(PORT_INVALID, s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tcp_to_socket() {
+        let fwd = Forward::parse_local("2375:/var/run/docker.sock").unwrap();
+        assert_eq!(
+            fwd,
+            Forward::Local {
+                listen: Endpoint::tcp(DEFAULT_BIND_ADDRESS, 2375),
+                target: Endpoint::unix("/var/run/docker.sock"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_socket_to_socket() {
+        let fwd = Forward::parse_local("/tmp/pg.sock:/var/run/postgresql/.s.PGSQL.5432").unwrap();
+        assert_eq!(
+            fwd,
+            Forward::Local {
+                listen: Endpoint::unix("/tmp/pg.sock"),
+                target: Endpoint::unix("/var/run/postgresql/.s.PGSQL.5432"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_tcp_with_bind_address() {
+        let fwd = Forward::parse_local("[::1]:8080:localhost:80").unwrap();
+        assert_eq!(
+            fwd,
+            Forward::Local {
+                listen: Endpoint::tcp("::1", 8080),
+                target: Endpoint::tcp("localhost", 80),
+            }
+        );
+        assert_eq!(fwd.to_string(), "-L [::1]:8080 -> localhost:80");
+    }
+
+    #[test]
+    fn test_parse_remote() {
+        let fwd = Forward::parse_remote("9000:/run/app.sock").unwrap();
+        assert_eq!(
+            fwd,
+            Forward::Remote {
+                listen: Endpoint::tcp(DEFAULT_BIND_ADDRESS, 9000),
+                target: Endpoint::unix("/run/app.sock"),
+            }
+        );
+
+        assert_eq!(
+            Forward::parse_remote("/tmp/a.sock:localhost:80").unwrap(),
+            Forward::Remote {
+                listen: Endpoint::unix("/tmp/a.sock"),
+                target: Endpoint::tcp("localhost", 80),
+            }
+        );
+        assert!(Forward::parse_remote("0:localhost:80").is_err());
+    }
+
+    #[test]
+    fn test_parse_dynamic() {
+        assert_eq!(
+            Forward::parse_dynamic("1080").unwrap(),
+            Forward::Dynamic { listen: Endpoint::tcp(DEFAULT_BIND_ADDRESS, 1080) }
+        );
+        assert_eq!(
+            Forward::parse_dynamic("0.0.0.0:1080").unwrap(),
+            Forward::Dynamic { listen: Endpoint::tcp("0.0.0.0", 1080) }
+        );
+        assert!(Forward::parse_dynamic("a:b:c").is_err());
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let fwd = Forward::parse_local("5432:/var/run/postgresql/.s.PGSQL.5432").unwrap();
+        let json = serde_json::to_string(&fwd).unwrap();
+        let parsed: Forward = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, fwd);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Forward::parse_local("").is_err());
+        assert!(Forward::parse_local("abc:/sock").is_err());
+        assert!(Forward::parse_local("80").is_err());
+        assert!(Forward::parse_local("1:2:3:4:5").is_err());
+        assert!(Forward::parse_local("[::1:80:/sock").is_err());
+    }
+}
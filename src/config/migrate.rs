@@ -0,0 +1,214 @@
+//! Upgrading vault files to the current format
+//!
+//! Readers for every format version live in [`crypto::Header`], so old files
+//! keep working without migrating; any save writes the current format. A
+//! migration rewrites all encrypted files at once, after copying the originals
+//! to a backup, and restores them if the rewritten vault does not read back
+//! identically.
+
+use crate::crypto::FORMAT_VERSION;
+use crate::error::{Result, SecureSshError};
+
+use super::backend::{Storage, VaultFile};
+use super::{storage, UnlockedVault};
+
+/// Format version of one encrypted vault file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileFormat {
+    pub file: VaultFile,
+    pub version: u32,
+}
+
+impl FileFormat {
+    pub fn is_current(&self) -> bool {
+        self.version == FORMAT_VERSION
+    }
+}
+
+/// Format versions of the encrypted files present, read from their headers
+///
+/// Needs no password.
+pub fn check_formats(storage: &dyn Storage) -> Result<Vec<FileFormat>> {
+    let mut formats = Vec::new();
+    for file in VaultFile::ALL.into_iter().filter(|f| f.is_secret()) {
+        if let Some(version) = storage::file_version(storage, file)? {
+            formats.push(FileFormat { file, version });
+        }
+    }
+    Ok(formats)
+}
+
+/// Copy every vault file present in `from` to `to`
+fn copy_files(from: &dyn Storage, to: &dyn Storage) -> Result<()> {
+    for file in VaultFile::ALL {
+        if let Some(data) = from.read(file)? {
+            to.write(file, &data)?;
+        }
+    }
+    Ok(())
+}
+
+/// Make `to` hold exactly the vault files in `from`
+fn restore_files(from: &dyn Storage, to: &dyn Storage) -> Result<()> {
+    for file in VaultFile::ALL {
+        match from.read(file)? {
+            Some(data) => to.write(file, &data)?,
+            None => to.remove(file)?,
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite every encrypted file of an unlocked vault in the current format
+///
+/// The originals are copied to `backup` first. If writing fails or the
+/// result does not decrypt to the same key and servers, they are copied back
+/// and the error is returned.
+pub fn migrate(vault: &mut UnlockedVault, backup: &dyn Storage) -> Result<()> {
+    with_backup(vault, backup, |vault| vault.rewrite())
+}
+
+/// Move a vault kept in separate files into a single container, like [`migrate`]
+pub fn migrate_to_container(vault: &mut UnlockedVault, backup: &dyn Storage) -> Result<()> {
+    with_backup(vault, backup, UnlockedVault::convert_to_container)
+}
+
+/// Back up, change the files, verify, and restore the backup on failure
+fn with_backup(
+    vault: &mut UnlockedVault,
+    backup: &dyn Storage,
+    change: impl FnOnce(&mut UnlockedVault) -> Result<()>,
+) -> Result<()> {
+    copy_files(vault.storage(), backup)?;
+
+    let result = change(vault).and_then(|()| verify(vault));
+    if let Err(e) = result {
+        restore_files(backup, vault.storage())?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Check that the files on storage hold what the unlocked vault holds
+fn verify(vault: &UnlockedVault) -> Result<()> {
+    let (private_key, servers) = vault.read_back()?;
+
+    let same_key = private_key[..] == vault.private_key()[..];
+    let same_servers = serde_json::to_vec(&servers)? == serde_json::to_vec(vault.servers())?;
+    let all_current = check_formats(vault.storage())?.iter().all(FileFormat::is_current);
+
+    if same_key && same_servers && all_current {
+        Ok(())
+    } else {
+        Err(SecureSshError::Other(t!(VAULT_READ_BACK, FORMAT_VERSION)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{MemoryStorage, Server};
+    use crate::crypto::{self, KeyPair};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_migrate_version_1_vault() {
+        let storage = Arc::new(MemoryStorage::new());
+        let key = crypto::test_v1_password_key();
+        let keypair = KeyPair::generate().unwrap();
+        let private_key = keypair.into_private_key();
+
+        let mut servers = crate::config::ServerList::new();
+        servers.add(Server::new("web", "192.0.2.10", 22, "deploy")).unwrap();
+
+        // Lay out the vault as version 1 wrote it
+        for (file, plaintext) in [
+            (VaultFile::Key, private_key[..].to_vec()),
+            (VaultFile::Servers, serde_json::to_vec(&servers).unwrap()),
+        ] {
+            let (nonce, ciphertext) = crypto::encrypt(&key.key, &plaintext).unwrap();
+            let mut data = 1u32.to_be_bytes().to_vec();
+            data.extend_from_slice(&key.salt);
+            data.extend_from_slice(&nonce);
+            data.extend_from_slice(&ciphertext);
+            storage.write(file, &data).unwrap();
+        }
+        storage.write(VaultFile::PublicKey, b"ssh-ed25519 AAAA\n").unwrap();
+
+        let formats = check_formats(&*storage).unwrap();
+        assert_eq!(formats.len(), 2);
+        assert!(formats.iter().all(|f| f.version == 1 && !f.is_current()));
+
+        let mut vault = UnlockedVault::unlock(storage.clone(), b"password").unwrap();
+        let backup = MemoryStorage::new();
+        migrate(&mut vault, &backup).unwrap();
+
+        assert!(check_formats(&*storage).unwrap().iter().all(FileFormat::is_current));
+        assert!(check_formats(&backup).unwrap().iter().all(|f| f.version == 1));
+
+        let reopened = UnlockedVault::unlock(storage, b"password").unwrap();
+        assert_eq!(&reopened.private_key()[..], &private_key[..]);
+        assert!(reopened.servers().get("web").is_some());
+    }
+
+    #[test]
+    fn test_save_version_1_vault_without_servers() {
+        let storage = Arc::new(MemoryStorage::new());
+        let key = crypto::test_v1_password_key();
+
+        // A version 1 vault that never had a server added: no servers.enc
+        let (nonce, ciphertext) = crypto::encrypt(&key.key, b"private").unwrap();
+        let mut data = 1u32.to_be_bytes().to_vec();
+        data.extend_from_slice(&key.salt);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        storage.write(VaultFile::Key, &data).unwrap();
+        storage.write(VaultFile::PublicKey, b"ssh-ed25519 AAAA\n").unwrap();
+
+        let mut vault = UnlockedVault::unlock(storage.clone(), b"password").unwrap();
+        vault.servers_mut().add(Server::new("web", "192.0.2.10", 22, "deploy")).unwrap();
+        vault.save().unwrap();
+
+        let reopened = UnlockedVault::unlock(storage, b"password").unwrap();
+        assert_eq!(reopened.servers().len(), 1);
+        assert!(reopened.servers().get("web").is_some());
+    }
+
+    #[test]
+    fn test_migrate_to_container() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let key = crypto::test_password_key(b"password");
+        let keypair = KeyPair::generate().unwrap();
+        let public_key = keypair.public_key_openssh("test");
+        let mut vault =
+            UnlockedVault::create(storage.clone(), key, keypair.into_private_key(), &public_key)
+                .unwrap();
+        vault.servers_mut().add(Server::new("web", "192.0.2.10", 22, "deploy")).unwrap();
+        vault.save().unwrap();
+
+        let backup = MemoryStorage::new();
+        migrate_to_container(&mut vault, &backup).unwrap();
+
+        for file in [VaultFile::Key, VaultFile::PublicKey, VaultFile::Servers] {
+            assert!(!storage.exists(file).unwrap());
+            assert!(backup.exists(file).unwrap());
+        }
+        assert!(matches!(
+            crate::config::read_public_key(&*storage),
+            Err(SecureSshError::VaultLocked)
+        ));
+
+        let mut reopened = UnlockedVault::unlock(storage.clone(), b"password").unwrap();
+        assert!(reopened.is_container());
+        assert_eq!(reopened.public_key().unwrap(), public_key);
+        assert!(reopened.servers().get("web").is_some());
+
+        // Later saves keep everything in the container
+        reopened.servers_mut().add(Server::new("db", "192.0.2.11", 22, "deploy")).unwrap();
+        reopened.save().unwrap();
+        assert!(!storage.exists(VaultFile::Servers).unwrap());
+        let again = UnlockedVault::unlock(storage, b"password").unwrap();
+        assert_eq!(again.servers().len(), 2);
+        assert_eq!(again.public_key().unwrap(), public_key);
+    }
+}
@@ -3,14 +3,37 @@
 //! Handles encrypted storage of:
 //! - SSH private key
 //! - Server configurations
+//!
+//! and reading of OpenSSH client configs for import.
 
+mod backend;
+mod container;
+mod forward;
+mod migrate;
+mod profile;
+mod repair;
 mod server;
+mod ssh_config;
 mod storage;
+mod vault;
 
-pub use server::{Server, ServerList};
+#[allow(unused_imports)]
+pub use backend::{FsStorage, MemoryStorage, Storage, VaultFile};
+pub use container::{Container, Section};
+pub use forward::{Endpoint, Forward};
+pub use migrate::{check_formats, migrate, migrate_to_container, FileFormat};
+pub use profile::{current_profile, init_location, list_profiles, profile_dir, DEFAULT_PROFILE};
+pub use repair::{repair, FileRepair, RepairOutcome, RepairSource};
+pub use server::{
+    parse_group, parse_host, Hop, parse_tags, validate_name, validate_port, validate_user, Server,
+    ServerList, TAG_PREFIX,
+};
+pub use ssh_config::{SshConfig, SshHost};
 #[allow(unused_imports)]
 pub use storage::{
-    load_encrypted_key, load_servers, save_encrypted_key, save_servers,
-    get_data_dir, get_public_key_path, is_initialized, read_public_key,
+    load_encrypted_key, open_vault_key, load_servers, save_encrypted_key, save_servers,
+    load_container, load_public_key, save_container, save_key_slots, has_vault, is_container,
+    data_storage, get_data_dir, is_initialized, read_public_key,
     get_exe_dir, get_marker_path, create_marker_file, marker_exists,
 };
+pub use vault::UnlockedVault;
@@ -0,0 +1,138 @@
+//! Data directory location and named profiles
+//!
+//! The data directory is `<exe dir>/data` unless `--data-dir` or
+//! `SECURE_SSH_DATA_DIR` points elsewhere. The default profile lives
+//! directly in it; each named profile is a subdirectory of `profiles/`
+//! with its own password, key and servers.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::error::{Result, SecureSshError};
+
+use super::storage::get_exe_dir;
+
+/// Environment variable overriding the data directory
+const DATA_DIR_ENV: &str = "SECURE_SSH_DATA_DIR";
+
+/// Name of the profile stored directly in the data directory
+pub const DEFAULT_PROFILE: &str = "default";
+
+const DATA_DIR: &str = "data";
+const PROFILES_DIR: &str = "profiles";
+
+/// Data directory and profile chosen for this process
+struct Location {
+    root: Option<PathBuf>,
+    profile: Option<String>,
+}
+
+static LOCATION: OnceLock<Location> = OnceLock::new();
+
+/// Set the data directory and profile for this process
+///
+/// `root` falls back to `SECURE_SSH_DATA_DIR`, then to `<exe dir>/data`.
+/// Only the first call has an effect.
+pub fn init_location(root: Option<PathBuf>, profile: Option<String>) -> Result<()> {
+    let profile = match profile {
+        Some(name) if name != DEFAULT_PROFILE => {
+            validate_profile_name(&name).map_err(SecureSshError::InvalidConfig)?;
+            Some(name)
+        }
+        _ => None,
+    };
+    let root = root.or_else(|| {
+        std::env::var_os(DATA_DIR_ENV)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    });
+
+    LOCATION.get_or_init(|| Location { root, profile });
+    Ok(())
+}
+
+/// Data directory holding the default profile and `profiles/`
+pub fn data_root() -> Result<PathBuf> {
+    match LOCATION.get().and_then(|l| l.root.clone()) {
+        Some(root) => Ok(root),
+        None => Ok(get_exe_dir()?.join(DATA_DIR)),
+    }
+}
+
+/// Name of the selected profile
+pub fn current_profile() -> &'static str {
+    LOCATION
+        .get()
+        .and_then(|l| l.profile.as_deref())
+        .unwrap_or(DEFAULT_PROFILE)
+}
+
+/// Directory of a profile (the data directory itself for the default one)
+pub fn profile_dir(name: &str) -> Result<PathBuf> {
+    let root = data_root()?;
+    if name == DEFAULT_PROFILE {
+        return Ok(root);
+    }
+    validate_profile_name(name).map_err(SecureSshError::InvalidConfig)?;
+    Ok(root.join(PROFILES_DIR).join(name))
+}
+
+/// Names of the profiles that have a directory, default first
+pub fn list_profiles() -> Result<Vec<String>> {
+    let mut names = Vec::new();
+
+    let dir = data_root()?.join(PROFILES_DIR);
+    if dir.is_dir() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type()?.is_dir() && validate_profile_name(&name).is_ok() {
+                names.push(name);
+            }
+        }
+    }
+    names.sort();
+
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    Ok(names)
+}
+
+/// Validate a profile name
+///
+/// The name becomes a directory name, so only letters, digits, `-`, `_`
+/// and `.` are allowed, and it must not start with `.`.
+pub fn validate_profile_name(name: &str) -> std::result::Result<(), String> {
+    if name.is_empty() {
+        return Err(t!(PROFILE_NAME_EMPTY).into());
+    }
+    if name.len() > 64 {
+        return Err(t!(PROFILE_NAME_TOO_LONG, name));
+    }
+    if name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(t!(PROFILE_NAME_INVALID, name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_profile_name() {
+        assert!(validate_profile_name("work").is_ok());
+        assert!(validate_profile_name("client-2.prod_eu").is_ok());
+
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name("..").is_err());
+        assert!(validate_profile_name(".hidden").is_err());
+        assert!(validate_profile_name("a/b").is_err());
+        assert!(validate_profile_name("a b").is_err());
+        assert!(validate_profile_name(&"x".repeat(65)).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! Recovering a damaged vault from earlier copies
+//!
+//! Every encrypted file has copies: the previous generation the backend
+//! keeps on each write, and the backups `migrate` leaves behind (each with
+//! its own previous generations). A file of the vault that no longer
+//! decrypts is replaced by the first copy that does, current backend first,
+//! then the backups in the order given. The damaged file is moved aside
+//! rather than deleted.
+//!
+//! Only copies encrypted under the same master key qualify (for files from
+//! before format 5, under the same salt and KDF parameters), so a repaired
+//! vault never mixes files of two vaults. A restored key file (or container)
+//! gets the current key slots, so the password that opened the vault keeps
+//! opening it.
+
+use crate::crypto::{FileKey, VaultKey};
+use crate::error::{Result, SecureSshError};
+
+use super::backend::{Storage, VaultFile};
+use super::storage;
+
+/// Encrypted files a vault may consist of
+const ENCRYPTED_FILES: [VaultFile; 3] = [VaultFile::Key, VaultFile::Servers, VaultFile::Container];
+
+/// A place holding copies of the vault files
+pub struct RepairSource<'a> {
+    /// Shown in the report, e.g. a backup directory
+    pub name: String,
+    pub storage: &'a dyn Storage,
+}
+
+/// What happened to one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairOutcome {
+    /// It decrypts; left alone
+    Intact,
+    /// Replaced with the previous generation the backend kept
+    RestoredPrevious,
+    /// Replaced with the copy from the named backup
+    RestoredBackup(String),
+    /// No copy decrypts
+    Lost,
+}
+
+/// Result of repairing one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRepair {
+    pub file: VaultFile,
+    pub outcome: RepairOutcome,
+}
+
+/// Where a copy of a file was found
+enum Origin<'a> {
+    Current,
+    Previous,
+    Backup(&'a str),
+}
+
+/// Check every encrypted file of the vault in `storage` and replace damaged ones
+///
+/// The key is opened from the first readable header of the key file (or
+/// container); a password that fits none of its slots fails with
+/// [`SecureSshError::InvalidPassword`] before anything is changed. Damaged
+/// files are written to `damaged` before being replaced.
+pub fn repair(
+    storage: &dyn Storage,
+    backups: &[RepairSource],
+    password: &[u8],
+    damaged: &dyn Storage,
+) -> Result<Vec<FileRepair>> {
+    let master = master_file(storage)?;
+    let key = open_key(storage, backups, master, password)?;
+
+    let mut report = Vec::new();
+    for file in ENCRYPTED_FILES {
+        let Some(current) = storage.read(file)? else {
+            continue;
+        };
+        if storage::decrypt_copy(file, &current, &key).is_ok() {
+            report.push(FileRepair {
+                file,
+                outcome: RepairOutcome::Intact,
+            });
+            continue;
+        }
+
+        // The current file was just tried; the rest are in search order
+        let copy = copies(storage, backups, file)?
+            .into_iter()
+            .filter(|(origin, _)| !matches!(origin, Origin::Current))
+            .find(|(_, data)| storage::decrypt_copy(file, data, &key).is_ok());
+
+        let outcome = match copy {
+            Some((origin, data)) => {
+                damaged.write(file, &current)?;
+                storage.write(file, &with_current_slots(file, master, data, &key)?)?;
+                match origin {
+                    Origin::Backup(name) => RepairOutcome::RestoredBackup(name.to_string()),
+                    _ => RepairOutcome::RestoredPrevious,
+                }
+            }
+            None => RepairOutcome::Lost,
+        };
+        report.push(FileRepair { file, outcome });
+    }
+
+    if report.is_empty() {
+        return Err(SecureSshError::NotInitialized);
+    }
+    Ok(report)
+}
+
+/// File whose header holds the key slots
+fn master_file(storage: &dyn Storage) -> Result<VaultFile> {
+    Ok(if storage::is_container(storage)? {
+        VaultFile::Container
+    } else {
+        VaultFile::Key
+    })
+}
+
+/// Open the key from the master file headers that parse, newest first
+///
+/// A damaged slot moves on to the next copy; a wrong password stops at once.
+fn open_key(
+    storage: &dyn Storage,
+    backups: &[RepairSource],
+    master: VaultFile,
+    password: &[u8],
+) -> Result<VaultKey> {
+    let mut result = Err(SecureSshError::NotInitialized);
+    for (_, data) in copies(storage, backups, master)? {
+        let Ok(header) = storage::copy_header(master, &data) else {
+            continue;
+        };
+        result = storage::key_from_header(master, &header, password);
+        if !matches!(result, Err(SecureSshError::VaultCorrupted(_))) {
+            break;
+        }
+    }
+    result
+}
+
+/// A copy to restore, with the current key slots if it is the master file
+///
+/// A copy from before a password change holds slots for the old password.
+fn with_current_slots(file: VaultFile, master: VaultFile, data: Vec<u8>, key: &VaultKey) -> Result<Vec<u8>> {
+    let has_slots = storage::copy_header(file, &data)
+        .is_ok_and(|header| matches!(header.key, FileKey::Master { .. }));
+    if file == master && has_slots {
+        storage::replace_slots(file, &data, key)
+    } else {
+        Ok(data)
+    }
+}
+
+/// Every copy of `file`: the current file, its previous generation, then each backup's
+fn copies<'a>(
+    storage: &dyn Storage,
+    backups: &'a [RepairSource],
+    file: VaultFile,
+) -> Result<Vec<(Origin<'a>, Vec<u8>)>> {
+    let mut copies = Vec::new();
+    if let Some(data) = storage.read(file)? {
+        copies.push((Origin::Current, data));
+    }
+    if let Some(data) = storage.read_previous(file)? {
+        copies.push((Origin::Previous, data));
+    }
+    for backup in backups {
+        // An unreadable backup is just one copy fewer
+        if let Ok(Some(data)) = backup.storage.read(file) {
+            copies.push((Origin::Backup(&backup.name), data));
+        }
+        if let Ok(Some(data)) = backup.storage.read_previous(file) {
+            copies.push((Origin::Backup(&backup.name), data));
+        }
+    }
+    Ok(copies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{MemoryStorage, Server, ServerList};
+    use crate::crypto::test_password_key as password_key;
+
+    const PASSWORD: &[u8] = b"password";
+
+    fn test_key() -> VaultKey {
+        VaultKey::create(password_key(PASSWORD)).unwrap()
+    }
+
+    fn save_vault(storage: &dyn Storage, key: &VaultKey, servers: &[&str]) {
+        let mut list = ServerList::new();
+        for &name in servers {
+            list.add(Server::new(name, "192.0.2.10", 22, "deploy")).unwrap();
+        }
+        storage::save_encrypted_key(storage, b"private", "ssh-ed25519 AAAA", key).unwrap();
+        storage::save_servers(storage, &list, key).unwrap();
+    }
+
+    /// Cut the tag short; damaging a damaged file again keeps it damaged
+    fn damage(storage: &dyn Storage, file: VaultFile) {
+        let mut data = storage.read(file).unwrap().unwrap();
+        data.pop();
+        storage.write(file, &data).unwrap();
+    }
+
+    #[test]
+    fn test_restores_previous_generation() {
+        let storage = MemoryStorage::new();
+        let damaged = MemoryStorage::new();
+        let key = test_key();
+        save_vault(&storage, &key, &["web"]);
+        damage(&storage, VaultFile::Key);
+
+        let report = repair(&storage, &[], PASSWORD, &damaged).unwrap();
+        assert_eq!(
+            report,
+            [
+                FileRepair {
+                    file: VaultFile::Key,
+                    outcome: RepairOutcome::RestoredPrevious,
+                },
+                FileRepair {
+                    file: VaultFile::Servers,
+                    outcome: RepairOutcome::Intact,
+                },
+            ]
+        );
+        assert!(damaged.exists(VaultFile::Key).unwrap());
+
+        // Readable straight from the file, without the fallback
+        let data = storage.read(VaultFile::Key).unwrap().unwrap();
+        assert_eq!(&*storage::decrypt_copy(VaultFile::Key, &data, &key).unwrap(), b"private");
+    }
+
+    #[test]
+    fn test_restores_from_backup() {
+        let storage = MemoryStorage::new();
+        let backup = MemoryStorage::new();
+        let key = test_key();
+        save_vault(&backup, &key, &["web", "db"]);
+        save_vault(&storage, &key, &["web", "db"]);
+
+        // Both generations of the servers file are damaged
+        damage(&storage, VaultFile::Servers);
+        damage(&storage, VaultFile::Servers);
+
+        let backups = [RepairSource {
+            name: "format-1".into(),
+            storage: &backup,
+        }];
+        let report = repair(&storage, &backups, PASSWORD, &MemoryStorage::new()).unwrap();
+        assert_eq!(report[1].outcome, RepairOutcome::RestoredBackup("format-1".into()));
+        assert_eq!(storage::load_servers(&storage, &key).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_wrong_password_changes_nothing() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        save_vault(&storage, &key, &["web"]);
+        damage(&storage, VaultFile::Servers);
+        let before = storage.read(VaultFile::Servers).unwrap();
+
+        assert!(matches!(
+            repair(&storage, &[], b"wrong", &MemoryStorage::new()),
+            Err(SecureSshError::InvalidPassword)
+        ));
+        assert_eq!(storage.read(VaultFile::Servers).unwrap(), before);
+    }
+
+    #[test]
+    fn test_reports_lost_files() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        storage::save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+        storage::save_servers(&storage, &ServerList::new(), &key).unwrap();
+        damage(&storage, VaultFile::Servers);
+        damage(&storage, VaultFile::Servers);
+
+        let report = repair(&storage, &[], PASSWORD, &MemoryStorage::new()).unwrap();
+        assert_eq!(report[0].outcome, RepairOutcome::Intact);
+        assert_eq!(report[1].outcome, RepairOutcome::Lost);
+    }
+
+    #[test]
+    fn test_restored_key_file_keeps_new_password() {
+        let storage = MemoryStorage::new();
+        let backup = MemoryStorage::new();
+        let mut key = test_key();
+        save_vault(&backup, &key, &["web"]);
+        save_vault(&storage, &key, &["web"]);
+
+        // The backup predates the password change and holds the old slots
+        key.rewrap(password_key(b"new password")).unwrap();
+        storage::save_key_slots(&storage, &key).unwrap();
+        damage(&storage, VaultFile::Key);
+        damage(&storage, VaultFile::Key);
+
+        let backups = [RepairSource {
+            name: "format-1".into(),
+            storage: &backup,
+        }];
+        let report = repair(&storage, &backups, b"new password", &MemoryStorage::new()).unwrap();
+        assert_eq!(report[0].outcome, RepairOutcome::RestoredBackup("format-1".into()));
+
+        let opened = storage::open_vault_key(&storage, b"new password").unwrap();
+        assert_eq!(&*storage::load_encrypted_key(&storage, &opened).unwrap(), b"private");
+        assert!(storage::open_vault_key(&storage, PASSWORD).is_err());
+    }
+}
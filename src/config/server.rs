@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::Forward;
+
 /// A single server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Server {
@@ -16,6 +18,76 @@ pub struct Server {
     /// Optional description
     #[serde(default)]
     pub description: String,
+    /// Port forwards established automatically on connect
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forwards: Vec<Forward>,
+    /// Free-form labels used for selection (`@tag`) and filtering
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Group path with `/`-separated segments (e.g. "prod/eu"), empty for none
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub group: String,
+    /// Jump hosts, in order: server names or `[user@]host[:port]` specs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proxy_jump: Vec<String>,
+}
+
+/// One SSH connection on the way to a server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hop {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+}
+
+impl Hop {
+    /// Parse a `[user@]host[:port]` jump spec; `user` is used when none is given
+    pub fn parse(spec: &str, user: &str) -> Result<Self, String> {
+        let (spec_user, rest) = match spec.rsplit_once('@') {
+            Some((u, rest)) => (u, rest),
+            None => (user, spec),
+        };
+
+        // [v6]:port, host:port or a bare host / IPv6 literal
+        let (host, port) = if let Some(inner) = rest.strip_prefix('[') {
+            let (host, tail) = inner
+                .split_once(']')
+                .ok_or_else(|| t!(UNTERMINATED_BRACKET, spec))?;
+            match tail.strip_prefix(':') {
+                Some(port) => (host, Some(port)),
+                None if tail.is_empty() => (host, None),
+                None => return Err(t!(JUMP_INVALID, spec)),
+            }
+        } else {
+            match rest.split_once(':') {
+                Some((host, port)) if !port.contains(':') => (host, Some(port)),
+                _ => (rest, None),
+            }
+        };
+
+        let port = match port {
+            Some(p) => p.parse().map_err(|_| t!(PORT_INVALID, p))?,
+            None => 22,
+        };
+        validate_port(port)?;
+        validate_user(spec_user)?;
+
+        Ok(Self {
+            host: parse_host(host)?,
+            port,
+            user: spec_user.to_string(),
+        })
+    }
+}
+
+impl From<&Server> for Hop {
+    fn from(server: &Server) -> Self {
+        Self {
+            host: server.host.clone(),
+            port: server.port,
+            user: server.user.clone(),
+        }
+    }
 }
 
 impl Server {
@@ -27,6 +99,10 @@ impl Server {
             port,
             user: user.into(),
             description: String::new(),
+            forwards: Vec::new(),
+            tags: Vec::new(),
+            group: String::new(),
+            proxy_jump: Vec::new(),
         }
     }
 
@@ -36,10 +112,34 @@ impl Server {
         self
     }
 
+    /// Check whether the server carries a tag (case-insensitive)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Check whether the server is in `group` or one of its subgroups
+    pub fn in_group(&self, group: &str) -> bool {
+        let group = group.trim_matches('/');
+        !group.is_empty()
+            && (self.group == group
+                || self
+                    .group
+                    .strip_prefix(group)
+                    .is_some_and(|rest| rest.starts_with('/')))
+    }
+
+    /// Check whether the server matches a `@tag` selector (tag or group path)
+    pub fn matches_selector(&self, selector: &str) -> bool {
+        self.has_tag(selector) || self.in_group(selector)
+    }
+
     /// Get the SSH connection string (user@host:port)
     pub fn connection_string(&self) -> String {
         if self.port == 22 {
             format!("{}@{}", self.user, self.host)
+        } else if self.host.contains(':') {
+            // IPv6 literal needs brackets to separate the port
+            format!("{}@[{}]:{}", self.user, self.host, self.port)
         } else {
             format!("{}@{}:{}", self.user, self.host, self.port)
         }
@@ -54,6 +154,10 @@ impl Default for Server {
             port: 22,
             user: "root".to_string(),
             description: String::new(),
+            forwards: Vec::new(),
+            tags: Vec::new(),
+            group: String::new(),
+            proxy_jump: Vec::new(),
         }
     }
 }
@@ -73,12 +177,31 @@ impl ServerList {
     /// Add a server to the list
     pub fn add(&mut self, server: Server) -> Result<(), &'static str> {
         if self.servers.iter().any(|s| s.name == server.name) {
-            return Err("Server with this name already exists");
+            return Err(t!(SERVER_NAME_TAKEN));
         }
         self.servers.push(server);
         Ok(())
     }
 
+    /// Rename a server, keeping its position in the list and jump references
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), &'static str> {
+        if old != new && self.get(new).is_some() {
+            return Err(t!(SERVER_NAME_TAKEN));
+        }
+        let server = self.get_mut(old).ok_or(t!(SERVER_NAME_UNKNOWN))?;
+        server.name = new.to_string();
+
+        // Keep jump host references pointing at the renamed server
+        for server in &mut self.servers {
+            for jump in &mut server.proxy_jump {
+                if jump == old {
+                    *jump = new.to_string();
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Remove a server by name
     pub fn remove(&mut self, name: &str) -> Option<Server> {
         if let Some(pos) = self.servers.iter().position(|s| s.name == name) {
@@ -93,6 +216,11 @@ impl ServerList {
         self.servers.iter().find(|s| s.name == name)
     }
 
+    /// Get a mutable server by name
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Server> {
+        self.servers.iter_mut().find(|s| s.name == name)
+    }
+
     /// Check if the list is empty
     pub fn is_empty(&self) -> bool {
         self.servers.is_empty()
@@ -112,4 +240,324 @@ impl ServerList {
     pub fn iter(&self) -> impl Iterator<Item = &Server> {
         self.servers.iter()
     }
+
+    /// Build the chain of connections needed to reach `server`
+    ///
+    /// Jump hosts that name another server are expanded recursively
+    /// (with that server's own jumps); anything else is parsed as a
+    /// `[user@]host[:port]` spec. The last hop is the server itself.
+    pub fn route(&self, server: &Server) -> Result<Vec<Hop>, String> {
+        let mut route = Vec::new();
+        self.push_route(server, &mut route, 0)?;
+        Ok(route)
+    }
+
+    fn push_route(&self, server: &Server, route: &mut Vec<Hop>, depth: usize) -> Result<(), String> {
+        if depth > MAX_JUMP_DEPTH {
+            return Err(t!(JUMP_CHAIN_TOO_LONG, server.name));
+        }
+
+        for jump in &server.proxy_jump {
+            match self.get(jump) {
+                Some(jump_server) => self.push_route(jump_server, route, depth + 1)?,
+                None => route.push(Hop::parse(jump, &server.user)?),
+            }
+        }
+
+        route.push(Hop::from(server));
+        Ok(())
+    }
+
+    /// Resolve a selector: a server name, or `@tag` / `@group/path`
+    ///
+    /// A plain name yields at most one server; an `@` selector yields
+    /// every matching server in list order.
+    pub fn select(&self, selector: &str) -> Vec<&Server> {
+        match selector.strip_prefix(TAG_PREFIX) {
+            Some(tag) => self.iter().filter(|s| s.matches_selector(tag)).collect(),
+            None => self.get(selector).into_iter().collect(),
+        }
+    }
+}
+
+/// Maximum nesting of jump hosts that refer to other servers
+const MAX_JUMP_DEPTH: usize = 8;
+
+/// Prefix that marks a tag or group selector (`@prod`)
+pub const TAG_PREFIX: char = '@';
+
+/// Check that a server name can be used on the command line
+///
+/// Names must be non-empty, contain no whitespace or commas (used as a
+/// list separator) and must not start with the tag prefix.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err(t!(SERVER_NAME_EMPTY).into());
+    }
+    if name.starts_with(TAG_PREFIX) {
+        return Err(t!(SERVER_NAME_TAG_PREFIX, TAG_PREFIX));
+    }
+    if name.chars().any(|c| c.is_whitespace() || c.is_control() || c == ',') {
+        return Err(t!(SERVER_NAME_INVALID, name));
+    }
+    Ok(())
+}
+
+/// Validate a host and return it in canonical form
+///
+/// Accepts IPv4 and IPv6 literals (the latter optionally in brackets,
+/// which are stripped) and DNS names: dot-separated labels of up to 63
+/// letters, digits, `-` or `_`, not starting or ending with `-`.
+pub fn parse_host(input: &str) -> Result<String, String> {
+    let host = input.trim();
+    let invalid = || t!(HOST_INVALID, host);
+
+    if host.is_empty() {
+        return Err(t!(HOST_EMPTY).into());
+    }
+
+    // IPv6 literal, with or without brackets
+    let unbracketed = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    if unbracketed.contains(':') {
+        return unbracketed
+            .parse::<std::net::Ipv6Addr>()
+            .map(|_| unbracketed.to_string())
+            .map_err(|_| invalid());
+    }
+
+    // Everything numeric must be a valid IPv4 address (no "999.1.1.1")
+    if host.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return host
+            .parse::<std::net::Ipv4Addr>()
+            .map(|_| host.to_string())
+            .map_err(|_| invalid());
+    }
+
+    let name = host.strip_suffix('.').unwrap_or(host);
+    if name.len() > 253 {
+        return Err(invalid());
+    }
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    if !name.split('.').all(valid_label) {
+        return Err(invalid());
+    }
+
+    Ok(host.to_string())
+}
+
+/// Check an SSH port (0 is not a usable port)
+pub fn validate_port(port: u16) -> Result<(), String> {
+    if port == 0 {
+        return Err(t!(PORT_OUT_OF_RANGE).into());
+    }
+    Ok(())
+}
+
+/// Check a login name
+pub fn validate_user(user: &str) -> Result<(), String> {
+    if user.is_empty() {
+        return Err(t!(USER_EMPTY).into());
+    }
+    if user.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(t!(USER_INVALID, user));
+    }
+    Ok(())
+}
+
+/// Parse a comma- or space-separated tag list
+///
+/// Tags are trimmed, de-duplicated (case-insensitively) and must not
+/// contain `/`, `,` or start with `@`.
+pub fn parse_tags(input: &str) -> Result<Vec<String>, String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for tag in input.split(|c: char| c == ',' || c.is_whitespace()) {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.starts_with(TAG_PREFIX) || tag.contains('/') {
+            return Err(t!(TAG_INVALID, tag));
+        }
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+
+    Ok(tags)
+}
+
+/// Normalize a group path: trim segments and drop empty ones
+///
+/// `" prod / eu/ "` becomes `"prod/eu"`; an empty input means no group.
+pub fn parse_group(input: &str) -> Result<String, String> {
+    let segments: Vec<&str> = input
+        .split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if let Some(bad) = segments.iter().find(|s| s.starts_with(TAG_PREFIX) || s.contains(',')) {
+        return Err(t!(GROUP_INVALID, bad));
+    }
+
+    Ok(segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged(name: &str, tags: &[&str], group: &str) -> Server {
+        let mut server = Server::new(name, "10.0.0.1", 22, "root");
+        server.tags = tags.iter().map(|t| t.to_string()).collect();
+        server.group = group.to_string();
+        server
+    }
+
+    #[test]
+    fn test_old_config_without_tags() {
+        let json = r#"{"name":"main","host":"h","port":22,"user":"u","description":""}"#;
+        let server: Server = serde_json::from_str(json).unwrap();
+        assert!(server.tags.is_empty());
+        assert!(server.group.is_empty());
+
+        // Empty fields are not written back
+        let out = serde_json::to_string(&server).unwrap();
+        assert!(!out.contains("tags"));
+        assert!(!out.contains("group"));
+    }
+
+    #[test]
+    fn test_select() {
+        let mut list = ServerList::new();
+        list.add(tagged("web1", &["prod", "web"], "prod/eu")).unwrap();
+        list.add(tagged("web2", &["staging"], "staging")).unwrap();
+        list.add(tagged("db", &["Prod"], "prod/us")).unwrap();
+
+        let names = |sel: &str| list.select(sel).iter().map(|s| s.name.clone()).collect::<Vec<_>>();
+
+        assert_eq!(names("web2"), vec!["web2"]);
+        assert!(names("prod").is_empty());
+        assert_eq!(names("@prod"), vec!["web1", "db"]);
+        assert_eq!(names("@prod/eu"), vec!["web1"]);
+        assert_eq!(names("@staging"), vec!["web2"]);
+        assert!(names("@pro").is_empty());
+    }
+
+    #[test]
+    fn test_connection_string() {
+        assert_eq!(Server::new("a", "h", 22, "u").connection_string(), "u@h");
+        assert_eq!(Server::new("a", "h", 2222, "u").connection_string(), "u@h:2222");
+        assert_eq!(Server::new("a", "::1", 2222, "u").connection_string(), "u@[::1]:2222");
+    }
+
+    #[test]
+    fn test_hop_parse() {
+        let hop = Hop::parse("bastion", "deploy").unwrap();
+        assert_eq!((hop.host.as_str(), hop.port, hop.user.as_str()), ("bastion", 22, "deploy"));
+
+        let hop = Hop::parse("admin@10.0.0.1:2222", "deploy").unwrap();
+        assert_eq!((hop.host.as_str(), hop.port, hop.user.as_str()), ("10.0.0.1", 2222, "admin"));
+
+        let hop = Hop::parse("[2001:db8::1]:2200", "u").unwrap();
+        assert_eq!((hop.host.as_str(), hop.port), ("2001:db8::1", 2200));
+        assert_eq!(Hop::parse("2001:db8::1", "u").unwrap().port, 22);
+
+        assert!(Hop::parse("host:0", "u").is_err());
+        assert!(Hop::parse("host:abc", "u").is_err());
+    }
+
+    #[test]
+    fn test_route() {
+        let mut list = ServerList::new();
+        let mut bastion = Server::new("bastion", "gw.example.com", 2222, "jump");
+        bastion.proxy_jump = vec!["edge.example.com".into()];
+        list.add(bastion).unwrap();
+        let mut db = Server::new("db", "10.0.0.5", 22, "root");
+        db.proxy_jump = vec!["bastion".into()];
+        list.add(db).unwrap();
+
+        let route = list.route(list.get("db").unwrap()).unwrap();
+        let hosts: Vec<_> = route.iter().map(|h| (h.host.as_str(), h.user.as_str())).collect();
+        assert_eq!(
+            hosts,
+            vec![("edge.example.com", "jump"), ("gw.example.com", "jump"), ("10.0.0.5", "root")]
+        );
+
+        // A loop between two servers
+        list.get_mut("bastion").unwrap().proxy_jump = vec!["db".into()];
+        assert!(list.route(list.get("db").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut list = ServerList::new();
+        list.add(Server::new("a", "h", 22, "u")).unwrap();
+        list.add(Server::new("b", "h", 22, "u")).unwrap();
+
+        assert!(list.rename("a", "b").is_err());
+        assert!(list.rename("x", "y").is_err());
+        list.rename("a", "c").unwrap();
+        assert_eq!(list.first().unwrap().name, "c");
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("web-1.prod").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("@prod").is_err());
+        assert!(validate_name("a b").is_err());
+        assert!(validate_name("a,b").is_err());
+    }
+
+    #[test]
+    fn test_parse_host() {
+        assert_eq!(parse_host("example.com").unwrap(), "example.com");
+        assert_eq!(parse_host(" 10.0.0.1 ").unwrap(), "10.0.0.1");
+        assert_eq!(parse_host("[2001:db8::1]").unwrap(), "2001:db8::1");
+        assert_eq!(parse_host("fe80::1").unwrap(), "fe80::1");
+        assert_eq!(parse_host("my_host.local.").unwrap(), "my_host.local.");
+
+        assert!(parse_host("").is_err());
+        assert!(parse_host("999.1.1.1").is_err());
+        assert!(parse_host("2001:db8::zz").is_err());
+        assert!(parse_host("-bad.example").is_err());
+        assert!(parse_host("bad..example").is_err());
+        assert!(parse_host("user@host").is_err());
+        assert!(parse_host(&"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn test_validate_port_and_user() {
+        assert!(validate_port(0).is_err());
+        assert!(validate_port(65535).is_ok());
+        assert!(validate_user("deploy").is_ok());
+        assert!(validate_user("").is_err());
+        assert!(validate_user("a b").is_err());
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(parse_tags("prod, web  db,,prod").unwrap(), vec!["prod", "web", "db"]);
+        assert!(parse_tags("").unwrap().is_empty());
+        assert!(parse_tags("@prod").is_err());
+        assert!(parse_tags("a/b").is_err());
+    }
+
+    #[test]
+    fn test_parse_group() {
+        assert_eq!(parse_group(" prod / eu/ ").unwrap(), "prod/eu");
+        assert_eq!(parse_group("").unwrap(), "");
+        assert!(parse_group("@prod").is_err());
+    }
 }
@@ -0,0 +1,503 @@
+//! Reader for OpenSSH client configuration (`~/.ssh/config`)
+//!
+//! Only the options that map onto a [`Server`](super::Server) are
+//! interpreted: `Host`, `HostName`, `Port`, `User`, `ProxyJump`,
+//! `LocalForward`, `RemoteForward`, `DynamicForward` and `Include`.
+//! Everything else is accepted and ignored.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::Forward;
+
+/// Maximum nesting of `Include` directives (same limit as OpenSSH)
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Options of one host, resolved the way `ssh -G <alias>` would
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshHost {
+    /// Name from the `Host` line
+    pub alias: String,
+    /// `HostName` with `%h` expanded, or the alias itself
+    pub host_name: String,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    /// `ProxyJump` entries as written (`[user@]host[:port]` or another alias)
+    pub proxy_jump: Vec<String>,
+    pub forwards: Vec<Forward>,
+    /// Options that were found but could not be imported
+    pub warnings: Vec<String>,
+}
+
+/// A parsed client configuration
+#[derive(Debug, Default)]
+pub struct SshConfig {
+    blocks: Vec<Block>,
+    /// Problems not tied to a particular host (e.g. `Match` blocks)
+    pub warnings: Vec<String>,
+}
+
+/// Options that apply to the hosts matching `patterns`
+#[derive(Debug, Clone)]
+struct Block {
+    /// `None` for a `Match` block, which is never applied
+    patterns: Option<Vec<String>>,
+    options: Vec<(String, Vec<String>)>,
+}
+
+impl Block {
+    fn global() -> Self {
+        Self {
+            patterns: Some(vec!["*".into()]),
+            options: Vec::new(),
+        }
+    }
+
+    /// A host matches if any pattern matches and no `!pattern` does
+    fn matches(&self, alias: &str) -> bool {
+        let Some(patterns) = &self.patterns else {
+            return false;
+        };
+
+        let mut matched = false;
+        for pattern in patterns {
+            match pattern.strip_prefix('!') {
+                Some(negated) if wildcard_match(negated, alias) => return false,
+                Some(_) => {}
+                None => matched |= wildcard_match(pattern, alias),
+            }
+        }
+        matched
+    }
+}
+
+impl SshConfig {
+    /// Read a config file and everything it includes
+    ///
+    /// Relative `Include` paths are resolved against the directory of
+    /// `path`, as OpenSSH does for `~/.ssh/config`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| t!(SSH_CONFIG_READ, path.display(), e))?;
+        let base = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::parse(&text, &base)
+    }
+
+    /// Parse config text; `base` is the directory for relative includes
+    pub fn parse(text: &str, base: &Path) -> Result<Self, String> {
+        let mut config = Self {
+            blocks: vec![Block::global()],
+            warnings: Vec::new(),
+        };
+        config.parse_text(text, base, "config", 0)?;
+        Ok(config)
+    }
+
+    fn parse_text(&mut self, text: &str, base: &Path, source: &str, depth: usize) -> Result<(), String> {
+        for (number, line) in text.lines().enumerate() {
+            let location = || format!("{}:{}", source, number + 1);
+
+            let Some((keyword, args)) = split_line(line).map_err(|e| format!("{}: {}", location(), e))?
+            else {
+                continue;
+            };
+
+            match keyword.as_str() {
+                "host" => {
+                    if args.is_empty() {
+                        return Err(t!(SSH_CONFIG_HOST_PATTERN, location()));
+                    }
+                    self.blocks.push(Block {
+                        patterns: Some(args),
+                        options: Vec::new(),
+                    });
+                }
+                "match" => {
+                    self.warnings.push(t!(SSH_CONFIG_MATCH, location()));
+                    self.blocks.push(Block {
+                        patterns: None,
+                        options: Vec::new(),
+                    });
+                }
+                "include" => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        return Err(t!(SSH_CONFIG_INCLUDE_DEPTH, location()));
+                    }
+                    // Included files continue the current block; if they open
+                    // blocks of their own, the rest of this file returns to it
+                    let current = self.blocks.len() - 1;
+                    for arg in &args {
+                        for file in expand_include(arg, base)? {
+                            let text = fs::read_to_string(&file)
+                                .map_err(|e| t!(SSH_CONFIG_READ, file.display(), e))?;
+                            self.parse_text(&text, base, &file.display().to_string(), depth + 1)?;
+                        }
+                    }
+                    if self.blocks.len() - 1 != current {
+                        let mut resumed = self.blocks[current].clone();
+                        resumed.options.clear();
+                        self.blocks.push(resumed);
+                    }
+                }
+                _ => {
+                    let block = self.blocks.last_mut().expect("global block always exists");
+                    block.options.push((keyword, args));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Concrete host names: `Host` patterns without wildcards or negation
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for block in &self.blocks {
+            for pattern in block.patterns.iter().flatten() {
+                let concrete = !pattern.starts_with('!') && !pattern.contains(['*', '?']);
+                if concrete && !aliases.contains(pattern) {
+                    aliases.push(pattern.clone());
+                }
+            }
+        }
+        aliases
+    }
+
+    /// Collect the options for `alias`; for single-valued options the
+    /// first value found wins, forwards accumulate across blocks
+    pub fn resolve(&self, alias: &str) -> Result<SshHost, String> {
+        let mut host = SshHost {
+            alias: alias.to_string(),
+            host_name: alias.to_string(),
+            port: None,
+            user: None,
+            proxy_jump: Vec::new(),
+            forwards: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let mut host_name = None;
+        let mut proxy_jump = None;
+
+        for block in self.blocks.iter().filter(|b| b.matches(alias)) {
+            for (keyword, args) in &block.options {
+                let first = args.first().map(String::as_str).unwrap_or("");
+                match keyword.as_str() {
+                    "hostname" if host_name.is_none() => {
+                        host_name = Some(expand_tokens(first, alias));
+                    }
+                    "port" if host.port.is_none() => {
+                        let port = first
+                            .parse()
+                            .ok()
+                            .filter(|&p| p != 0)
+                            .ok_or_else(|| t!(SSH_CONFIG_PORT, first))?;
+                        host.port = Some(port);
+                    }
+                    "user" if host.user.is_none() => host.user = Some(first.to_string()),
+                    "proxyjump" if proxy_jump.is_none() => {
+                        let jumps: Vec<String> = if first.eq_ignore_ascii_case("none") {
+                            Vec::new()
+                        } else {
+                            first.split(',').map(|j| j.trim().to_string()).collect()
+                        };
+                        proxy_jump = Some(jumps);
+                    }
+                    "proxycommand" => {
+                        let warning = t!(SSH_CONFIG_PROXY_COMMAND).to_string();
+                        if !host.warnings.contains(&warning) {
+                            host.warnings.push(warning);
+                        }
+                    }
+                    "localforward" | "remoteforward" | "dynamicforward" => {
+                        match parse_forward(keyword, args) {
+                            Ok(forward) => host.forwards.push(forward),
+                            Err(e) => host.warnings.push(e),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(name) = host_name {
+            host.host_name = name;
+        }
+        host.proxy_jump = proxy_jump.unwrap_or_default();
+        if let Some(uri) = host.proxy_jump.iter().find(|j| j.starts_with("ssh://")) {
+            return Err(t!(SSH_CONFIG_JUMP_URI, uri));
+        }
+
+        Ok(host)
+    }
+}
+
+/// Split a line into a lower-cased keyword and its arguments
+///
+/// Returns `None` for blank lines and comments. The keyword may be
+/// separated from the arguments by whitespace or `=`; arguments may be
+/// double-quoted; an unquoted `#` starts a trailing comment.
+fn split_line(line: &str) -> Result<Option<(String, Vec<String>)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let keyword = line[..end].to_ascii_lowercase();
+
+    let mut rest = line[end..].trim_start();
+    if let Some(stripped) = rest.strip_prefix('=') {
+        rest = stripped.trim_start();
+    }
+
+    let mut args = Vec::new();
+    let mut chars = rest.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '#' {
+            break;
+        }
+
+        let mut arg = String::new();
+        if c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => arg.push(c),
+                    None => return Err(t!(SSH_CONFIG_QUOTE).into()),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                arg.push(c);
+                chars.next();
+            }
+        }
+        args.push(arg);
+    }
+
+    Ok(Some((keyword, args)))
+}
+
+/// Expand `%h` (the alias) and `%%` in a `HostName` value
+fn expand_tokens(value: &str, alias: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('%', Some('h')) => {
+                result.push_str(alias);
+                chars.next();
+            }
+            ('%', Some('%')) => {
+                result.push('%');
+                chars.next();
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Convert `LocalForward 8080 db:5432` into the `ssh -L` form and parse it
+fn parse_forward(keyword: &str, args: &[String]) -> Result<Forward, String> {
+    let spec = args.join(":");
+    let forward = match keyword {
+        "localforward" if args.len() == 2 => Forward::parse_local(&spec),
+        "remoteforward" if args.len() == 2 => Forward::parse_remote(&spec),
+        "dynamicforward" if args.len() == 1 => Forward::parse_dynamic(&spec),
+        "remoteforward" if args.len() == 1 => Err(t!(SSH_CONFIG_REMOTE_DYNAMIC).into()),
+        _ => Err(t!(SSH_CONFIG_ARG_COUNT).into()),
+    };
+    forward.map_err(|e| format!("{} {}: {}", keyword, args.join(" "), e))
+}
+
+/// List the files named by one `Include` argument, sorted
+///
+/// `~` is expanded, relative paths are resolved against `base`, and
+/// wildcards are allowed in the file name. Missing files are skipped.
+fn expand_include(arg: &str, base: &Path) -> Result<Vec<PathBuf>, String> {
+    let path = match arg.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .ok_or(t!(HOME_DIR_UNKNOWN))?
+            .join(rest),
+        None => PathBuf::from(arg),
+    };
+    let path = if path.is_relative() { base.join(path) } else { path };
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !file_name.contains(['*', '?']) {
+        return Ok(if path.is_file() { vec![path] } else { Vec::new() });
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| wildcard_match(&file_name, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Match `text` against a pattern with `*` and `?`, ignoring ASCII case
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_ascii_lowercase().chars().collect();
+    let text: Vec<char> = text.to_ascii_lowercase().chars().collect();
+
+    // Iterative matching with backtracking to the last '*'
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Endpoint;
+
+    fn parse(text: &str) -> SshConfig {
+        SshConfig::parse(text, Path::new("/nonexistent")).unwrap()
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("web-?", "WEB-1"));
+        assert!(wildcard_match("*.prod.example.com", "db.prod.example.com"));
+        assert!(!wildcard_match("web-?", "web-10"));
+        assert!(!wildcard_match("*.prod", "prod"));
+    }
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(split_line("  # comment").unwrap(), None);
+        assert_eq!(
+            split_line("HostName=example.com").unwrap(),
+            Some(("hostname".into(), vec!["example.com".into()]))
+        );
+        assert_eq!(
+            split_line(r#"Host "my host" other # trailing"#).unwrap(),
+            Some(("host".into(), vec!["my host".into(), "other".into()]))
+        );
+        assert!(split_line(r#"User "oops"#).is_err());
+    }
+
+    #[test]
+    fn test_resolve_first_value_wins() {
+        let config = parse(
+            "User fallback\n\
+             Host web web-*\n\
+             \tHostName %h.example.com\n\
+             \tPort 2222\n\
+             Host *\n\
+             \tPort 22\n\
+             \tUser default\n",
+        );
+
+        let web = config.resolve("web").unwrap();
+        assert_eq!(web.host_name, "web.example.com");
+        assert_eq!(web.port, Some(2222));
+        assert_eq!(web.user.as_deref(), Some("fallback"));
+
+        assert_eq!(config.aliases(), vec!["web"]);
+        assert_eq!(config.resolve("other").unwrap().host_name, "other");
+    }
+
+    #[test]
+    fn test_negated_pattern() {
+        let config = parse("Host * !bastion\n  ProxyJump bastion\nHost bastion\n  HostName 10.0.0.1\n");
+
+        assert_eq!(config.resolve("db").unwrap().proxy_jump, vec!["bastion"]);
+        assert!(config.resolve("bastion").unwrap().proxy_jump.is_empty());
+        assert_eq!(config.aliases(), vec!["bastion"]);
+    }
+
+    #[test]
+    fn test_forwards_and_warnings() {
+        let config = parse(
+            "Host app\n\
+             LocalForward 8080 localhost:80\n\
+             DynamicForward 1080\n\
+             RemoteForward 9000\n\
+             ProxyCommand nc %h %p\n\
+             ProxyJump a,user@b:2222\n\
+             Match host app\n\
+             LocalForward 1 x:1\n",
+        );
+
+        let app = config.resolve("app").unwrap();
+        assert_eq!(app.forwards.len(), 2);
+        assert_eq!(
+            app.forwards[0],
+            Forward::Local {
+                listen: Endpoint::tcp("127.0.0.1", 8080),
+                target: Endpoint::tcp("localhost", 80),
+            }
+        );
+        assert_eq!(app.proxy_jump, vec!["a", "user@b:2222"]);
+        assert_eq!(app.warnings.len(), 2);
+        assert_eq!(config.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_port() {
+        let config = parse("Host a\n  Port ssh\n");
+        assert!(config.resolve("a").is_err());
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = std::env::temp_dir().join(format!("secure-ssh-include-{}", std::process::id()));
+        fs::create_dir_all(dir.join("conf.d")).unwrap();
+        fs::write(dir.join("conf.d/b.conf"), "Host b\n  HostName beta\n").unwrap();
+        fs::write(dir.join("conf.d/a.conf"), "Host a\n  HostName alpha\n").unwrap();
+        fs::write(dir.join("conf.d/skip.txt"), "Host skipped\n").unwrap();
+        fs::write(
+            dir.join("config"),
+            "Host top\n  Include conf.d/*.conf\n  User me\nInclude missing\n",
+        )
+        .unwrap();
+
+        let config = SshConfig::load(&dir.join("config"));
+        fs::remove_dir_all(&dir).ok();
+        let config = config.unwrap();
+
+        assert_eq!(config.aliases(), vec!["top", "a", "b"]);
+        assert_eq!(config.resolve("a").unwrap().host_name, "alpha");
+        // Options after the Include still belong to "Host top"
+        assert_eq!(config.resolve("top").unwrap().user.as_deref(), Some("me"));
+        assert_eq!(config.resolve("a").unwrap().user, None);
+    }
+}
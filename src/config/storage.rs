@@ -1,24 +1,43 @@
 //! Зашифрованное хранилище ключей и конфигурации
 //!
-//! Формат зашифрованных файлов:
+//! Формат зашифрованных файлов (версия 5):
 //! [4 байта: версия (u32 BE)]
-//! [32 байта: соль]
+//! [1 байт: тип файла (ключ, серверы или контейнер)]
+//! [1 байт: число слотов N][N слотов ключа]
+//! [16 байт: контрольное значение подключа]
 //! [12 байт: nonce]
 //! [N байт: шифротекст + тег аутентификации]
+//!
+//! Файлы шифруются не ключом из пароля, а подключами случайного мастер-ключа
+//! хранилища (HKDF-SHA256, свой подключ для каждого файла). Мастер-ключ,
+//! обёрнутый ключом из пароля, лежит в слотах заголовка `key.enc` (или
+//! `vault.enc`); смена пароля переписывает только слоты.
+//!
+//! Заголовок без слотов аутентифицируется как associated data, поэтому подмена
+//! любого его байта или файла другим файлом хранилища обнаруживается.
+//! По контрольным значениям неверный пароль отличается от повреждённого
+//! файла. Файлы версий 1-4, зашифрованные ключом из пароля, по-прежнему
+//! читаются, подробности в [`crypto::Header`].
+//!
+//! Хранилище-контейнер вместо отдельных файлов держит всё в `vault.enc`
+//! (см. [`super::container`]); функции чтения ниже находят его сами.
+//!
+//! Файлы целиком читаются и пишутся через бэкенд [`Storage`]. Если файл
+//! повреждён, читается его предыдущее поколение, которое бэкенд хранит
+//! при каждой записи (у [`FsStorage`] это `<имя>.bak`).
 
-use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::fs::File;
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use crate::crypto::{self, DerivedKey, SecureBytes, FORMAT_VERSION, HEADER_LEN, NONCE_LEN, SALT_LEN};
+use crate::crypto::{self, FileKey, Header, SecureBytes, VaultKey};
 use crate::error::{Result, SecureSshError};
 
-use super::ServerList;
+use super::backend::{FsStorage, Storage, VaultFile};
+use super::container::{Container, Section};
+use super::{profile, ServerList};
 
-const KEY_FILE: &str = "key.enc";
-const KEY_PUB_FILE: &str = "key.pub";
-const SERVERS_FILE: &str = "servers.enc";
-const DATA_DIR: &str = "data";
 const MARKER_FILE: &str = ".secure-ssh-marker";
 
 /// Получить директорию исполняемого файла
@@ -29,220 +48,467 @@ pub fn get_exe_dir() -> Result<PathBuf> {
     exe_path
         .parent()
         .map(|p| p.to_path_buf())
-        .ok_or_else(|| SecureSshError::Other("Не удалось определить директорию исполняемого файла".into()))
+        .ok_or_else(|| SecureSshError::Other(t!(EXE_DIR_UNKNOWN).into()))
 }
 
-/// Получить путь к директории данных (относительно исполняемого файла)
+/// Получить директорию данных выбранного профиля
 pub fn get_data_dir() -> Result<PathBuf> {
-    Ok(get_exe_dir()?.join(DATA_DIR))
+    profile::profile_dir(profile::current_profile())
 }
 
-/// Get the public key file path
-pub fn get_public_key_path() -> Result<PathBuf> {
-    Ok(get_data_dir()?.join(KEY_PUB_FILE))
+/// Хранилище файлов в директории данных рядом с исполняемым файлом
+pub fn data_storage() -> Result<Arc<dyn Storage>> {
+    Ok(Arc::new(FsStorage::new(get_data_dir()?)))
 }
 
-/// Get the encrypted key file path
-fn get_key_path() -> Result<PathBuf> {
-    Ok(get_data_dir()?.join(KEY_FILE))
+/// Check if secure-ssh is initialized (key.enc or vault.enc exists)
+///
+/// A named profile without a directory is an error rather than "not initialized".
+pub fn is_initialized() -> Result<bool> {
+    let profile = profile::current_profile();
+    let dir = profile::profile_dir(profile)?;
+    if profile != profile::DEFAULT_PROFILE && !dir.is_dir() {
+        return Err(SecureSshError::ProfileNotFound(profile.to_string()));
+    }
+    has_vault(&FsStorage::new(dir))
 }
 
-/// Get the servers config file path
-fn get_servers_path() -> Result<PathBuf> {
-    Ok(get_data_dir()?.join(SERVERS_FILE))
+/// Whether the backend holds a vault, in separate files or a container
+pub fn has_vault(storage: &dyn Storage) -> Result<bool> {
+    Ok(storage.exists(VaultFile::Key)? || is_container(storage)?)
 }
 
-/// Check if secure-ssh is initialized (key.enc exists)
-pub fn is_initialized() -> Result<bool> {
-    let key_path = get_key_path()?;
-    Ok(key_path.exists())
+/// Whether the vault is a single container file
+pub fn is_container(storage: &dyn Storage) -> Result<bool> {
+    storage.exists(VaultFile::Container)
 }
 
-/// Ensure data directory exists
-fn ensure_data_dir() -> Result<PathBuf> {
-    let data_dir = get_data_dir()?;
-    if !data_dir.exists() {
-        fs::create_dir_all(&data_dir)?;
-    }
-    Ok(data_dir)
+/// File whose header holds the key slots of the vault
+fn master_file(storage: &dyn Storage) -> Result<VaultFile> {
+    Ok(if is_container(storage)? {
+        VaultFile::Container
+    } else {
+        VaultFile::Key
+    })
 }
 
 /// Save encrypted SSH private key
 ///
-/// File format:
-/// [4 bytes: version][32 bytes: salt][12 bytes: nonce][ciphertext][16 bytes: tag]
+/// The key file header carries the key slots of `key`.
 pub fn save_encrypted_key(
+    storage: &dyn Storage,
     private_key: &[u8],
     public_key_openssh: &str,
-    derived_key: &DerivedKey,
+    key: &VaultKey,
 ) -> Result<()> {
-    ensure_data_dir()?;
-
-    // Encrypt the private key
-    let (nonce, ciphertext) = crypto::encrypt(&derived_key.key, private_key)?;
-
-    // Build the encrypted file
-    let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
-    data.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
-    data.extend_from_slice(&derived_key.salt);
-    data.extend_from_slice(&nonce);
-    data.extend_from_slice(&ciphertext);
-
     // Write encrypted key
-    let key_path = get_key_path()?;
-    let mut file = File::create(&key_path)?;
-    file.write_all(&data)?;
-    file.sync_all()?;
-
-    // Set restrictive permissions on Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))?;
-    }
+    write_encrypted(storage, VaultFile::Key, private_key, key)?;
 
     // Write public key (plaintext)
-    let pub_path = get_public_key_path()?;
-    let mut pub_file = File::create(&pub_path)?;
-    pub_file.write_all(public_key_openssh.as_bytes())?;
-    pub_file.write_all(b"\n")?;
-
-    Ok(())
+    storage.write(VaultFile::PublicKey, format!("{}\n", public_key_openssh).as_bytes())
 }
 
-/// Load and decrypt SSH private key
+/// Open the vault key with a password, from the key file (or container) header
 ///
-/// Returns (private_key_bytes, salt) - salt is needed for decrypting servers
-pub fn load_encrypted_key(password: &[u8]) -> Result<(SecureBytes, [u8; SALT_LEN])> {
-    let key_path = get_key_path()?;
-
-    if !key_path.exists() {
-        return Err(SecureSshError::NotInitialized);
+/// A vault in format 5 has its master key unwrapped from the first slot the
+/// password fits. An older vault is encrypted with the password key itself;
+/// it gets a new master key, which takes over once the vault is rewritten.
+/// A wrong password fails with [`SecureSshError::InvalidPassword`] where the
+/// header records a key check value.
+pub fn open_vault_key(storage: &dyn Storage, password: &[u8]) -> Result<VaultKey> {
+    let file = master_file(storage)?;
+    let current = read_encrypted(storage, file).and_then(|found| {
+        let (header, _) = found.ok_or(SecureSshError::NotInitialized)?;
+        key_from_header(file, &header, password)
+    });
+
+    match current {
+        Err(SecureSshError::NotInitialized) | Ok(_) => current,
+        // A damaged header or slot: the previous generation has the same master key.
+        // Damage to a slot's salt, KDF parameters or key check value reads as a
+        // wrong password, so that is retried too; a password that is really
+        // wrong fails on both and is reported as such.
+        Err(e) => match read_previous_encrypted(storage, file) {
+            Ok(Some((header, _))) => key_from_header(file, &header, password).map_err(|_| e),
+            _ => Err(e),
+        },
     }
+}
 
-    // Read the encrypted file
-    let mut file = File::open(&key_path)?;
-    let mut data = Vec::new();
-    file.read_to_end(&mut data)?;
+/// Open the vault key with a password from a header of the master file
+pub(super) fn key_from_header(file: VaultFile, header: &Header, password: &[u8]) -> Result<VaultKey> {
+    match &header.key {
+        FileKey::Password { kdf, salt } => {
+            let derived_key = crypto::derive_key(password, Some(salt), kdf)?;
+            if header.key_check.is_some_and(|check| check != derived_key.check_value()) {
+                return Err(SecureSshError::InvalidPassword);
+            }
+            VaultKey::create(derived_key)
+        }
+        FileKey::Master { slots } if slots.is_empty() => {
+            Err(SecureSshError::VaultCorrupted(file.file_name().to_string()))
+        }
+        FileKey::Master { slots } => VaultKey::open(slots.clone(), password).map_err(|e| match e {
+            SecureSshError::DecryptionFailed => SecureSshError::VaultCorrupted(file.file_name().to_string()),
+            e => e,
+        }),
+    }
+}
 
-    if data.len() < HEADER_LEN + 16 {
-        // 16 = minimum ciphertext (auth tag)
-        return Err(SecureSshError::InvalidConfig("Key file is corrupted".into()));
+/// Write the key slots of `key` into the key file (or container) header
+///
+/// The encrypted data stays byte for byte the same; the slots are not part
+/// of its associated data. The vault must already be in format 5.
+///
+/// The previous generation gets the same slots: left as it was, it would
+/// still open with a changed or removed password. One that has no slots to
+/// replace (an older format) is dropped instead.
+///
+/// A current file that no longer decrypts fails with
+/// [`SecureSshError::VaultCorrupted`] before anything is written: the write
+/// would move it over the previous generation, which may be the only good copy.
+pub fn save_key_slots(storage: &dyn Storage, key: &VaultKey) -> Result<()> {
+    let file = master_file(storage)?;
+    let data = storage.read(file)?.ok_or(SecureSshError::NotInitialized)?;
+    if decrypt_copy(file, &data, key).is_err() {
+        return Err(SecureSshError::VaultCorrupted(file.file_name().to_string()));
     }
+    storage.write(file, &replace_slots(file, &data, key)?)?;
+
+    match storage.read_previous(file)? {
+        Some(previous) => match replace_slots(file, &previous, key) {
+            Ok(previous) => storage.write_previous(file, &previous),
+            Err(_) => storage.remove_previous(file),
+        },
+        None => Ok(()),
+    }
+}
 
-    // Parse header
-    let version = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
-    if version != FORMAT_VERSION {
-        return Err(SecureSshError::InvalidConfig(format!(
-            "Unsupported key file version: {}",
-            version
+/// A copy of the master file with its key slots replaced by those of `key`
+pub(super) fn replace_slots(file: VaultFile, data: &[u8], key: &VaultKey) -> Result<Vec<u8>> {
+    let (mut header, ciphertext) = split_encrypted(file, data)?;
+    if !matches!(header.key, FileKey::Master { .. }) {
+        return Err(SecureSshError::InvalidConfig(t!(
+            VAULT_NO_KEY_SLOTS,
+            file.file_name(),
+            header.version
         )));
     }
 
-    let mut salt = [0u8; SALT_LEN];
-    salt.copy_from_slice(&data[4..4 + SALT_LEN]);
+    header.key = FileKey::Master {
+        slots: key.slots.clone(),
+    };
+    let mut data = header.encode();
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Give the copies of the vault in a backup the key slots of `key`
+///
+/// Without this a password removed or changed in the vault would still open
+/// its backups. A copy of the key file (or container) of this vault, told by
+/// the key check value in its header, gets the slots of `key`; copies of
+/// another vault are left alone. Files from before format 5 are encrypted
+/// with a password key itself, which no slot change can revoke, so they are
+/// removed. Returns how many copies were removed.
+pub fn save_backup_key_slots(backup: &dyn Storage, key: &VaultKey) -> Result<usize> {
+    let mut removed = 0;
+    for file in [VaultFile::Key, VaultFile::Servers, VaultFile::Container] {
+        // Both are read first: writing the current copy replaces the previous one
+        let current = backup.read(file)?.map(|data| backup_copy(file, data, key)).transpose()?;
+        let previous = backup.read_previous(file)?.map(|data| backup_copy(file, data, key)).transpose()?;
+
+        match current {
+            Some(BackupCopy::Remove) => {
+                // Takes the previous generation along
+                backup.remove(file)?;
+                removed += 1 + usize::from(previous.is_some());
+                continue;
+            }
+            Some(BackupCopy::Replace(data)) => backup.write(file, &data)?,
+            Some(BackupCopy::Keep(_)) | None => {}
+        }
+        match previous {
+            Some(BackupCopy::Remove) => {
+                backup.remove_previous(file)?;
+                removed += 1;
+            }
+            Some(BackupCopy::Replace(data) | BackupCopy::Keep(data)) => backup.write_previous(file, &data)?,
+            None => backup.remove_previous(file)?,
+        }
+    }
+    Ok(removed)
+}
 
-    let mut nonce = [0u8; NONCE_LEN];
-    nonce.copy_from_slice(&data[4 + SALT_LEN..4 + SALT_LEN + NONCE_LEN]);
+/// What becomes of one copy in a backup, see [`save_backup_key_slots`]
+enum BackupCopy {
+    Keep(Vec<u8>),
+    Replace(Vec<u8>),
+    Remove,
+}
 
-    let ciphertext = &data[HEADER_LEN..];
+fn backup_copy(file: VaultFile, data: Vec<u8>, key: &VaultKey) -> Result<BackupCopy> {
+    // A header that does not parse opens with no password
+    let Ok(header) = copy_header(file, &data) else {
+        return Ok(BackupCopy::Keep(data));
+    };
+    match &header.key {
+        FileKey::Password { .. } => Ok(BackupCopy::Remove),
+        FileKey::Master { slots }
+            if !slots.is_empty() && header.key_check == Some(crypto::key_check(&key.master.subkey(purpose(file)))) =>
+        {
+            Ok(BackupCopy::Replace(replace_slots(file, &data, key)?))
+        }
+        FileKey::Master { .. } => Ok(BackupCopy::Keep(data)),
+    }
+}
 
-    // Derive key from password using stored salt
-    let derived_key = crypto::derive_key(password, Some(&salt))?;
+/// Format version of an encrypted file from its header; `None` if the file does not exist
+pub fn file_version(storage: &dyn Storage, file: VaultFile) -> Result<Option<u32>> {
+    Ok(read_encrypted(storage, file)?.map(|(header, _)| header.version))
+}
 
-    // Decrypt
-    let private_key = crypto::decrypt(&derived_key.key, &nonce, ciphertext)?;
+/// Load and decrypt SSH private key with an already derived key
+pub fn load_encrypted_key(storage: &dyn Storage, key: &VaultKey) -> Result<SecureBytes> {
+    if let Some(container) = load_container(storage, key)? {
+        return section(&container, Section::PrivateKey).map(SecureBytes::from);
+    }
 
-    Ok((private_key, salt))
+    load_encrypted(storage, VaultFile::Key, key)?.ok_or(SecureSshError::NotInitialized)
 }
 
 /// Save server configurations (encrypted)
-pub fn save_servers(servers: &ServerList, derived_key: &DerivedKey) -> Result<()> {
-    ensure_data_dir()?;
-
+pub fn save_servers(storage: &dyn Storage, servers: &ServerList, key: &VaultKey) -> Result<()> {
     // Serialize to JSON
     let json = serde_json::to_vec(servers)?;
 
-    // Encrypt
-    let (nonce, ciphertext) = crypto::encrypt(&derived_key.key, &json)?;
+    write_encrypted(storage, VaultFile::Servers, &json, key)
+}
 
-    // Build file
-    let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
-    data.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
-    data.extend_from_slice(&derived_key.salt);
-    data.extend_from_slice(&nonce);
-    data.extend_from_slice(&ciphertext);
+/// Load server configurations (decrypted) with an already derived key
+pub fn load_servers(storage: &dyn Storage, key: &VaultKey) -> Result<ServerList> {
+    if let Some(container) = load_container(storage, key)? {
+        return match container.get(Section::Servers) {
+            Some(json) => Ok(serde_json::from_slice(json)?),
+            None => Ok(ServerList::new()),
+        };
+    }
 
-    // Write
-    let path = get_servers_path()?;
-    let mut file = File::create(&path)?;
-    file.write_all(&data)?;
-    file.sync_all()?;
+    // The servers file is encrypted under the key file's master key;
+    // decrypt_file checks that its header agrees
+    let Some(plaintext) = load_encrypted(storage, VaultFile::Servers, key)? else {
+        // No servers configured yet - return empty list
+        return Ok(ServerList::new());
+    };
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
-    }
+    // Parse JSON
+    let servers: ServerList = serde_json::from_slice(&plaintext)?;
 
-    Ok(())
+    Ok(servers)
 }
 
-/// Load server configurations (decrypted)
-pub fn load_servers(password: &[u8], salt: &[u8; SALT_LEN]) -> Result<ServerList> {
-    let path = get_servers_path()?;
+/// Decrypt the container; `None` if the vault is kept in separate files
+pub fn load_container(storage: &dyn Storage, key: &VaultKey) -> Result<Option<Container>> {
+    let Some(body) = load_encrypted(storage, VaultFile::Container, key)? else {
+        return Ok(None);
+    };
+    let container = Container::decode(&body).map_err(|e| {
+        SecureSshError::InvalidConfig(format!("{}: {}", VaultFile::Container.file_name(), e))
+    })?;
+    Ok(Some(container))
+}
 
-    if !path.exists() {
-        // No servers configured yet - return empty list
-        return Ok(ServerList::new());
-    }
+/// Encrypt and write the container; its header carries the key slots of `key`
+pub fn save_container(storage: &dyn Storage, container: &Container, key: &VaultKey) -> Result<()> {
+    write_encrypted(storage, VaultFile::Container, &container.encode(), key)
+}
+
+/// Section every container must have
+fn section(container: &Container, section: Section) -> Result<&[u8]> {
+    container.get(section).ok_or_else(|| {
+        SecureSshError::InvalidConfig(t!(
+            CONTAINER_NO_SECTION,
+            VaultFile::Container.file_name(),
+            format!("{:?}", section)
+        ))
+    })
+}
 
-    // Read file
-    let mut file = File::open(&path)?;
-    let mut data = Vec::new();
-    file.read_to_end(&mut data)?;
+/// Public key of the vault, also from a container
+pub fn load_public_key(storage: &dyn Storage, key: &VaultKey) -> Result<String> {
+    match load_container(storage, key)? {
+        Some(container) => {
+            let data = section(&container, Section::PublicKey)?;
+            Ok(String::from_utf8_lossy(data).trim().to_string())
+        }
+        None => read_public_key(storage),
+    }
+}
 
-    if data.len() < HEADER_LEN + 16 {
-        return Err(SecureSshError::InvalidConfig("Servers file is corrupted".into()));
+/// File type recorded in the header, so one vault file cannot stand in for another
+fn file_type(file: VaultFile) -> u8 {
+    match file {
+        VaultFile::Key => 1,
+        VaultFile::Servers => 2,
+        VaultFile::Container => 3,
+        VaultFile::PublicKey => unreachable!("the public key is not encrypted"),
     }
+}
 
-    // Parse header
-    let version = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
-    if version != FORMAT_VERSION {
-        return Err(SecureSshError::InvalidConfig(format!(
-            "Unsupported servers file version: {}",
-            version
-        )));
+/// HKDF purpose of the subkey a file is encrypted with
+fn purpose(file: VaultFile) -> &'static str {
+    match file {
+        VaultFile::Key => "key",
+        VaultFile::Servers => "servers",
+        VaultFile::Container => "vault",
+        VaultFile::PublicKey => unreachable!("the public key is not encrypted"),
     }
+}
+
+/// Encrypt `plaintext` with the file's subkey and write it behind a header
+/// in the current format
+///
+/// The header, without key slots, is authenticated as associated data. Only
+/// the key file and the container carry the slots.
+fn write_encrypted(
+    storage: &dyn Storage,
+    file: VaultFile,
+    plaintext: &[u8],
+    key: &VaultKey,
+) -> Result<()> {
+    let subkey = key.master.subkey(purpose(file));
+    let slots = match file {
+        VaultFile::Key | VaultFile::Container => key.slots.clone(),
+        _ => Vec::new(),
+    };
+    let header = Header::new(file_type(file), slots, &subkey, crypto::generate_nonce());
+    let mut data = header.encode();
 
-    // We use the same salt as the key file for consistency
-    let mut nonce = [0u8; NONCE_LEN];
-    nonce.copy_from_slice(&data[4 + SALT_LEN..4 + SALT_LEN + NONCE_LEN]);
+    // Encrypt
+    let ciphertext = crypto::encrypt_with_aad(&subkey, &header.nonce, plaintext, &header.aad())?;
+    data.extend_from_slice(&ciphertext);
 
-    let ciphertext = &data[HEADER_LEN..];
+    storage.write(file, &data)
+}
 
-    // Derive key
-    let derived_key = crypto::derive_key(password, Some(salt))?;
+/// Read an encrypted file and split it into header and ciphertext; `None` if it does not exist
+fn read_encrypted(storage: &dyn Storage, file: VaultFile) -> Result<Option<(Header, Vec<u8>)>> {
+    storage.read(file)?.map(|data| split_encrypted(file, &data)).transpose()
+}
 
-    // Decrypt
-    let plaintext = crypto::decrypt(&derived_key.key, &nonce, ciphertext)?;
+/// The previous generation of an encrypted file, split like [`read_encrypted`]
+fn read_previous_encrypted(storage: &dyn Storage, file: VaultFile) -> Result<Option<(Header, Vec<u8>)>> {
+    storage.read_previous(file)?.map(|data| split_encrypted(file, &data)).transpose()
+}
 
-    // Parse JSON
-    let servers: ServerList = serde_json::from_slice(&plaintext)?;
+/// Decrypt an encrypted file; `None` if it does not exist
+///
+/// If the file is damaged (its header does not parse or the ciphertext fails
+/// to authenticate), the previous generation is tried. When that does not
+/// decrypt either, the error is the current file's, so a wrong password is
+/// still reported as one.
+fn load_encrypted(storage: &dyn Storage, file: VaultFile, key: &VaultKey) -> Result<Option<SecureBytes>> {
+    let current = read_encrypted(storage, file).and_then(|found| {
+        found
+            .map(|(header, ciphertext)| decrypt_file(file, &header, &ciphertext, key))
+            .transpose()
+    });
+    let Err(e) = current else {
+        return current;
+    };
+
+    match read_previous_encrypted(storage, file) {
+        Ok(Some((header, ciphertext))) => decrypt_file(file, &header, &ciphertext, key)
+            .map(Some)
+            .map_err(|_| e),
+        _ => Err(e),
+    }
+}
 
-    Ok(servers)
+/// Header of a copy of `file` taken from anywhere (a backup, a previous generation)
+pub(super) fn copy_header(file: VaultFile, data: &[u8]) -> Result<Header> {
+    split_encrypted(file, data).map(|(header, _)| header)
+}
+
+/// Decrypt a copy of `file` taken from anywhere, as [`load_encrypted`] decrypts the file itself
+pub(super) fn decrypt_copy(file: VaultFile, data: &[u8], key: &VaultKey) -> Result<SecureBytes> {
+    let (header, ciphertext) = split_encrypted(file, data)?;
+    decrypt_file(file, &header, &ciphertext, key)
+}
+
+fn split_encrypted(file: VaultFile, data: &[u8]) -> Result<(Header, Vec<u8>)> {
+    let (header, ciphertext) = Header::parse(data)
+        .map_err(|e| SecureSshError::InvalidConfig(format!("{}: {}", file.file_name(), e)))?;
+
+    if header.file_type.is_some_and(|t| t != file_type(file)) {
+        return Err(SecureSshError::InvalidConfig(t!(VAULT_WRONG_FILE, file.file_name())));
+    }
+
+    Ok((header, ciphertext.to_vec()))
 }
 
-/// Прочитать публичный ключ без пароля
-pub fn read_public_key() -> Result<String> {
-    let path = get_public_key_path()?;
+/// Decrypt a file read with [`read_encrypted`]
+///
+/// A wrong password fails with [`SecureSshError::InvalidPassword`], a damaged
+/// file with [`SecureSshError::VaultCorrupted`]; files older than version 4
+/// fail with [`SecureSshError::DecryptionFailed`] either way.
+///
+/// From version 5 a file whose key check value does not match its subkey
+/// belongs to another vault. Older files are encrypted with the password
+/// key; one with another salt or KDF parameters belongs to another vault
+/// (or was left behind by an interrupted password change).
+fn decrypt_file(
+    file: VaultFile,
+    header: &Header,
+    ciphertext: &[u8],
+    key: &VaultKey,
+) -> Result<SecureBytes> {
+    let corrupted = |_| SecureSshError::VaultCorrupted(file.file_name().to_string());
+
+    let (kdf, salt) = match &header.key {
+        FileKey::Master { .. } => {
+            let subkey = key.master.subkey(purpose(file));
+            if header.key_check != Some(crypto::key_check(&subkey)) {
+                return Err(SecureSshError::InvalidConfig(t!(VAULT_OTHER_MASTER, file.file_name())));
+            }
+            return crypto::decrypt_with_aad(&subkey, &header.nonce, ciphertext, &header.aad())
+                .map_err(corrupted);
+        }
+        FileKey::Password { kdf, salt } => (kdf, salt),
+    };
+
+    let password = &key.password;
+    if *salt != password.salt || *kdf != password.params {
+        return Err(SecureSshError::InvalidConfig(t!(
+            VAULT_OTHER_KDF,
+            file.file_name(),
+            VaultFile::Key.file_name()
+        )));
+    }
 
-    if !path.exists() {
-        return Err(SecureSshError::NotInitialized);
+    // Files from version 4 on record the key check value, which separates
+    // a wrong password from a damaged file; older ones cannot tell
+    let Some(key_check) = header.key_check else {
+        return crypto::decrypt_with_aad(&password.key, &header.nonce, ciphertext, &header.aad());
+    };
+    if key_check != password.check_value() {
+        return Err(SecureSshError::InvalidPassword);
     }
+    crypto::decrypt_with_aad(&password.key, &header.nonce, ciphertext, &header.aad()).map_err(corrupted)
+}
 
-    let content = fs::read_to_string(&path)?;
-    Ok(content.trim().to_string())
+/// Прочитать публичный ключ хранилища без пароля
+///
+/// В контейнере публичный ключ зашифрован, поэтому для него это
+/// [`SecureSshError::VaultLocked`]; см. [`load_public_key`].
+pub fn read_public_key(storage: &dyn Storage) -> Result<String> {
+    if is_container(storage)? {
+        return Err(SecureSshError::VaultLocked);
+    }
+    let data = storage
+        .read(VaultFile::PublicKey)?
+        .ok_or(SecureSshError::NotInitialized)?;
+    Ok(String::from_utf8_lossy(&data).trim().to_string())
 }
 
 /// Получить путь к файлу-маркеру
@@ -264,3 +530,307 @@ pub fn create_marker_file() -> Result<()> {
 pub fn marker_exists() -> bool {
     get_marker_path().map(|p| p.exists()).unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{MemoryStorage, Server, UnlockedVault};
+    use crate::crypto::{test_password_key as password_key, KdfParams};
+
+    fn test_key() -> VaultKey {
+        VaultKey::create(password_key(b"password")).unwrap()
+    }
+
+    fn test_servers() -> ServerList {
+        let mut servers = ServerList::new();
+        servers.add(Server::new("web", "192.0.2.10", 22, "deploy")).unwrap();
+        servers
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+        save_servers(&storage, &test_servers(), &key).unwrap();
+
+        let opened = open_vault_key(&storage, b"password").unwrap();
+        assert_eq!(&*load_encrypted_key(&storage, &opened).unwrap(), b"private");
+        assert!(load_servers(&storage, &opened).unwrap().get("web").is_some());
+    }
+
+    #[test]
+    fn test_files_use_separate_subkeys() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+        save_servers(&storage, &test_servers(), &key).unwrap();
+
+        let check = |file| copy_header(file, &storage.read(file).unwrap().unwrap()).unwrap().key_check;
+        assert_eq!(check(VaultFile::Key), Some(crypto::key_check(&key.master.subkey("key"))));
+        assert_eq!(check(VaultFile::Servers), Some(crypto::key_check(&key.master.subkey("servers"))));
+
+        // Only the key file carries the slots
+        let header = copy_header(VaultFile::Servers, &storage.read(VaultFile::Servers).unwrap().unwrap()).unwrap();
+        assert_eq!(header.key, FileKey::Master { slots: Vec::new() });
+    }
+
+    #[test]
+    fn test_header_is_authenticated() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        save_servers(&storage, &test_servers(), &key).unwrap();
+
+        // Relabel the servers file as the key: the key check no longer
+        // matches the key file's subkey, so it is not even decrypted
+        let mut data = storage.read(VaultFile::Servers).unwrap().unwrap();
+        data[4] = file_type(VaultFile::Key);
+        storage.write(VaultFile::Key, &data).unwrap();
+        assert!(matches!(
+            load_encrypted_key(&storage, &key),
+            Err(SecureSshError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_swapped_files_are_rejected() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        save_servers(&storage, &test_servers(), &key).unwrap();
+
+        let servers = storage.read(VaultFile::Servers).unwrap().unwrap();
+        storage.write(VaultFile::Key, &servers).unwrap();
+        assert!(matches!(
+            load_encrypted_key(&storage, &key),
+            Err(SecureSshError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_file_of_another_vault_is_detected() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+        save_servers(&storage, &test_servers(), &test_key()).unwrap();
+
+        assert!(matches!(
+            load_servers(&storage, &key),
+            Err(SecureSshError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_wrong_password_is_told_from_damage() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+        assert!(matches!(
+            open_vault_key(&storage, b"other"),
+            Err(SecureSshError::InvalidPassword)
+        ));
+
+        // Damage both generations so there is nothing to fall back to
+        let mut data = storage.read(VaultFile::Key).unwrap().unwrap();
+        *data.last_mut().unwrap() ^= 1;
+        storage.write(VaultFile::Key, &data).unwrap();
+        storage.write(VaultFile::Key, &data).unwrap();
+        let opened = open_vault_key(&storage, b"password").unwrap();
+        assert!(matches!(
+            load_encrypted_key(&storage, &opened),
+            Err(SecureSshError::VaultCorrupted(_))
+        ));
+        assert!(matches!(
+            open_vault_key(&storage, b"other"),
+            Err(SecureSshError::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn test_damaged_file_falls_back_to_previous_generation() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+
+        // A flipped ciphertext bit fails authentication
+        let mut data = storage.read(VaultFile::Key).unwrap().unwrap();
+        *data.last_mut().unwrap() ^= 1;
+        storage.write(VaultFile::Key, &data).unwrap();
+        assert_eq!(&*load_encrypted_key(&storage, &key).unwrap(), b"private");
+
+        // A torn header is not even parsed; the slots come from the previous one
+        storage.write(VaultFile::Key, &data[..10]).unwrap();
+        let opened = open_vault_key(&storage, b"password").unwrap();
+        assert_eq!(&*opened.master.subkey("key"), &*key.master.subkey("key"));
+    }
+
+    #[test]
+    fn test_damaged_file_is_not_moved_over_previous_generation() {
+        let storage = Arc::new(MemoryStorage::new());
+        let key = test_key();
+        save_encrypted_key(&*storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+        save_encrypted_key(&*storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+
+        // Unlocking falls back to the previous generation
+        let mut data = storage.read(VaultFile::Key).unwrap().unwrap();
+        *data.last_mut().unwrap() ^= 1;
+        storage.write(VaultFile::Key, &data).unwrap();
+        let mut vault = UnlockedVault::unlock(storage.clone(), b"password").unwrap();
+
+        assert!(matches!(
+            vault.rekey(password_key(b"new password")),
+            Err(SecureSshError::VaultCorrupted(_))
+        ));
+        assert_eq!(storage.read(VaultFile::Key).unwrap().unwrap(), data);
+        let previous = storage.read_previous(VaultFile::Key).unwrap().unwrap();
+        assert_eq!(&*decrypt_copy(VaultFile::Key, &previous, &key).unwrap(), b"private");
+    }
+
+    #[test]
+    fn test_damaged_slot_falls_back_to_previous_generation() {
+        let storage = MemoryStorage::new();
+        let key = test_key();
+        save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+
+        // Version, file type and slot count, then the slot's KDF parameters and salt
+        let mut data = storage.read(VaultFile::Key).unwrap().unwrap();
+        data[4 + 1 + 1 + 13] ^= 1;
+        storage.write(VaultFile::Key, &data).unwrap();
+
+        let opened = open_vault_key(&storage, b"password").unwrap();
+        assert_eq!(&*opened.master.subkey("key"), &*key.master.subkey("key"));
+        assert!(matches!(
+            open_vault_key(&storage, b"other"),
+            Err(SecureSshError::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn test_new_password_rewrites_only_slots() {
+        let storage = MemoryStorage::new();
+        let mut key = test_key();
+        save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+        save_servers(&storage, &test_servers(), &key).unwrap();
+        let servers = storage.read(VaultFile::Servers).unwrap();
+
+        key.rewrap(password_key(b"new password")).unwrap();
+        save_key_slots(&storage, &key).unwrap();
+
+        assert_eq!(storage.read(VaultFile::Servers).unwrap(), servers);
+        assert!(matches!(
+            open_vault_key(&storage, b"password"),
+            Err(SecureSshError::InvalidPassword)
+        ));
+        let opened = open_vault_key(&storage, b"new password").unwrap();
+        assert_eq!(&*load_encrypted_key(&storage, &opened).unwrap(), b"private");
+        assert!(load_servers(&storage, &opened).unwrap().get("web").is_some());
+
+        // The previous generation does not keep the old password working
+        let previous = read_previous_encrypted(&storage, VaultFile::Key).unwrap().unwrap().0;
+        assert!(key_from_header(VaultFile::Key, &previous, b"password").is_err());
+        assert!(key_from_header(VaultFile::Key, &previous, b"new password").is_ok());
+    }
+
+    #[test]
+    fn test_removed_slot_does_not_open_previous_generation() {
+        let storage = MemoryStorage::new();
+        let mut key = test_key();
+        key.add_slot(&password_key(b"second")).unwrap();
+        save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+
+        key.remove_slot(1);
+        save_key_slots(&storage, &key).unwrap();
+
+        let previous = storage.read_previous(VaultFile::Key).unwrap().unwrap();
+        let header = copy_header(VaultFile::Key, &previous).unwrap();
+        assert!(matches!(
+            key_from_header(VaultFile::Key, &header, b"second"),
+            Err(SecureSshError::InvalidPassword)
+        ));
+        assert!(key_from_header(VaultFile::Key, &header, b"password").is_ok());
+    }
+
+    #[test]
+    fn test_master_file_without_slots_is_corrupted() {
+        let storage = MemoryStorage::new();
+        save_encrypted_key(&storage, b"private", "ssh-ed25519 AAAA", &test_key()).unwrap();
+
+        let mut header = copy_header(VaultFile::Key, &storage.read(VaultFile::Key).unwrap().unwrap()).unwrap();
+        header.key = FileKey::Master { slots: Vec::new() };
+        assert!(matches!(
+            key_from_header(VaultFile::Key, &header, b"password"),
+            Err(SecureSshError::VaultCorrupted(_))
+        ));
+    }
+
+    #[test]
+    fn test_backup_gets_current_slots() {
+        let mut key = test_key();
+        key.add_slot(&password_key(b"second")).unwrap();
+        let backup = MemoryStorage::new();
+        save_encrypted_key(&backup, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+        save_encrypted_key(&backup, b"private", "ssh-ed25519 AAAA", &key).unwrap();
+        save_servers(&backup, &test_servers(), &key).unwrap();
+        let servers = backup.read(VaultFile::Servers).unwrap();
+
+        // A backup of another vault is not touched
+        let other = MemoryStorage::new();
+        save_encrypted_key(&other, b"other", "ssh-ed25519 BBBB", &test_key()).unwrap();
+        let other_key = other.read(VaultFile::Key).unwrap();
+
+        key.remove_slot(1);
+        assert_eq!(save_backup_key_slots(&backup, &key).unwrap(), 0);
+        assert_eq!(save_backup_key_slots(&other, &key).unwrap(), 0);
+
+        for data in [backup.read(VaultFile::Key), backup.read_previous(VaultFile::Key)] {
+            let header = copy_header(VaultFile::Key, &data.unwrap().unwrap()).unwrap();
+            assert!(key_from_header(VaultFile::Key, &header, b"second").is_err());
+            assert!(key_from_header(VaultFile::Key, &header, b"password").is_ok());
+        }
+        assert_eq!(backup.read(VaultFile::Servers).unwrap(), servers);
+        assert_eq!(other.read(VaultFile::Key).unwrap(), other_key);
+    }
+
+    #[test]
+    fn test_backup_from_before_format_5_is_removed() {
+        let backup = MemoryStorage::new();
+        let key = password_key(b"password");
+        for file in [VaultFile::Key, VaultFile::Servers] {
+            let (nonce, ciphertext) = crypto::encrypt(&key.key, b"data").unwrap();
+            let mut data = 1u32.to_be_bytes().to_vec();
+            data.extend_from_slice(&key.salt);
+            data.extend_from_slice(&nonce);
+            data.extend_from_slice(&ciphertext);
+            backup.write(file, &data).unwrap();
+        }
+
+        assert_eq!(save_backup_key_slots(&backup, &test_key()).unwrap(), 2);
+        assert!(!backup.exists(VaultFile::Key).unwrap());
+        assert!(!backup.exists(VaultFile::Servers).unwrap());
+    }
+
+    #[test]
+    fn test_reads_version_1_files() {
+        let storage = MemoryStorage::new();
+        let key = crypto::test_v1_password_key();
+
+        for (file, plaintext) in [
+            (VaultFile::Key, b"private".to_vec()),
+            (VaultFile::Servers, serde_json::to_vec(&test_servers()).unwrap()),
+        ] {
+            let (nonce, ciphertext) = crypto::encrypt(&key.key, &plaintext).unwrap();
+            let mut data = 1u32.to_be_bytes().to_vec();
+            data.extend_from_slice(&key.salt);
+            data.extend_from_slice(&nonce);
+            data.extend_from_slice(&ciphertext);
+            storage.write(file, &data).unwrap();
+        }
+
+        let opened = open_vault_key(&storage, b"password").unwrap();
+        assert_eq!(opened.password.params, KdfParams::default());
+        assert_eq!(&*load_encrypted_key(&storage, &opened).unwrap(), b"private");
+        assert!(load_servers(&storage, &opened).unwrap().get("web").is_some());
+
+        // There are no slots to replace yet
+        assert!(save_key_slots(&storage, &opened).is_err());
+    }
+}
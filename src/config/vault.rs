@@ -0,0 +1,242 @@
+//! Unlocked vault: the decrypted key and server list for one command
+//!
+//! Argon2 is deliberately expensive (64 MB or more, hundreds of ms), so the
+//! master key is unwrapped once when the vault is unlocked and its subkeys
+//! are used for every read and write until the vault is dropped.
+
+use std::sync::Arc;
+
+use crate::crypto::{DerivedKey, KdfParams, KeySlot, SecureBytes, VaultKey};
+use crate::error::Result;
+
+use super::{check_formats, storage, Container, FileFormat, Section, ServerList, Storage, VaultFile};
+
+/// Decrypted vault contents together with the key that encrypts them
+pub struct UnlockedVault {
+    /// Backend the vault was unlocked from
+    storage: Arc<dyn Storage>,
+    key: VaultKey,
+    private_key: SecureBytes,
+    servers: ServerList,
+    /// All sections of a container vault, kept so rewriting preserves them
+    container: Option<Container>,
+}
+
+impl UnlockedVault {
+    /// Open the vault key with the password and decrypt the private key and servers
+    pub fn unlock(storage: Arc<dyn Storage>, password: &[u8]) -> Result<Self> {
+        let key = storage::open_vault_key(&*storage, password)?;
+
+        let private_key = storage::load_encrypted_key(&*storage, &key)?;
+        let servers = storage::load_servers(&*storage, &key)?;
+        let container = storage::load_container(&*storage, &key)?;
+
+        Ok(Self {
+            storage,
+            key,
+            private_key,
+            servers,
+            container,
+        })
+    }
+
+    /// Write a new private key under a new master key wrapped with an already
+    /// derived password key, replacing any existing vault
+    ///
+    /// The vault is created as separate files; see [`convert_to_container`](Self::convert_to_container).
+    /// The server list starts empty and is written on the first [`save`](Self::save).
+    pub fn create(
+        storage: Arc<dyn Storage>,
+        derived_key: DerivedKey,
+        private_key: SecureBytes,
+        public_key_openssh: &str,
+    ) -> Result<Self> {
+        let key = VaultKey::create(derived_key)?;
+        storage::save_encrypted_key(&*storage, &private_key, public_key_openssh, &key)?;
+
+        Ok(Self {
+            storage,
+            key,
+            private_key,
+            servers: ServerList::new(),
+            container: None,
+        })
+    }
+
+    /// Backend the vault was unlocked from
+    pub fn storage(&self) -> &dyn Storage {
+        &*self.storage
+    }
+
+    /// Whether the vault is a single container file
+    pub fn is_container(&self) -> bool {
+        self.container.is_some()
+    }
+
+    /// Public key in OpenSSH format
+    pub fn public_key(&self) -> Result<String> {
+        storage::load_public_key(&*self.storage, &self.key)
+    }
+
+    /// Decrypted SSH private key
+    pub fn private_key(&self) -> &SecureBytes {
+        &self.private_key
+    }
+
+    /// Argon2id parameters of the key slot the vault was unlocked with
+    pub fn kdf_params(&self) -> KdfParams {
+        self.key.password.params
+    }
+
+    pub fn servers(&self) -> &ServerList {
+        &self.servers
+    }
+
+    pub fn servers_mut(&mut self) -> &mut ServerList {
+        &mut self.servers
+    }
+
+    /// Encrypt and write the server list with the unlocked key
+    ///
+    /// A vault from before format 5 has no master key on storage yet, so the
+    /// whole vault is rewritten first.
+    pub fn save(&self) -> Result<()> {
+        if self.is_current()? {
+            return self.write_servers();
+        }
+
+        self.rewrite()?;
+        // A rewrite leaves a missing servers file missing, but the list has just changed
+        if self.container.is_none() && !self.storage.exists(VaultFile::Servers)? {
+            self.write_servers()?;
+        }
+        Ok(())
+    }
+
+    /// Write the private key and servers again with the same key, in the current format
+    ///
+    /// The key file, which carries the key slots, goes first. A missing
+    /// servers file stays missing.
+    pub fn rewrite(&self) -> Result<()> {
+        if let Some(container) = &self.container {
+            return self.write_container(container);
+        }
+
+        let public_key_openssh = storage::read_public_key(&*self.storage)?;
+        storage::save_encrypted_key(&*self.storage, &self.private_key, &public_key_openssh, &self.key)?;
+        if self.storage.exists(VaultFile::Servers)? {
+            self.write_servers()?;
+        }
+        Ok(())
+    }
+
+    fn write_servers(&self) -> Result<()> {
+        match &self.container {
+            Some(container) => self.write_container(container),
+            None => storage::save_servers(&*self.storage, &self.servers, &self.key),
+        }
+    }
+
+    /// Whether every encrypted file is in the current format
+    fn is_current(&self) -> Result<bool> {
+        Ok(check_formats(&*self.storage)?.iter().all(FileFormat::is_current))
+    }
+
+    /// Decrypt the private key and servers from storage again, without Argon2
+    pub fn read_back(&self) -> Result<(SecureBytes, ServerList)> {
+        Ok((
+            storage::load_encrypted_key(&*self.storage, &self.key)?,
+            storage::load_servers(&*self.storage, &self.key)?,
+        ))
+    }
+
+    /// Key slots of the vault, one per password
+    pub fn key_slots(&self) -> &[KeySlot] {
+        &self.key.slots
+    }
+
+    /// Index of the key slot the vault was unlocked with
+    pub fn current_key_slot(&self) -> usize {
+        self.key.slot
+    }
+
+    /// Wrap the master key with a newly derived password key in the slot the
+    /// vault was unlocked with
+    ///
+    /// Used both for a new password and for new KDF parameters; the other
+    /// slots are left as they are.
+    pub fn rekey(&mut self, derived_key: DerivedKey) -> Result<()> {
+        self.key.rewrap(derived_key)?;
+        self.write_key_slots()
+    }
+
+    /// Add a key slot for another password
+    pub fn add_key_slot(&mut self, derived_key: &DerivedKey) -> Result<()> {
+        self.key.add_slot(derived_key)?;
+        self.write_key_slots()
+    }
+
+    /// Remove a key slot other than [`current_key_slot`](Self::current_key_slot)
+    pub fn remove_key_slot(&mut self, index: usize) -> Result<()> {
+        self.key.remove_slot(index);
+        self.write_key_slots()
+    }
+
+    /// Give a backup of the vault the current key slots
+    ///
+    /// See [`storage::save_backup_key_slots`]; returns how many copies from
+    /// before format 5 were removed from the backup.
+    pub fn save_backup_key_slots(&self, backup: &dyn Storage) -> Result<usize> {
+        storage::save_backup_key_slots(backup, &self.key)
+    }
+
+    /// Write the key slots; a vault from before format 5 is rewritten whole
+    ///
+    /// The generations a rewrite replaces are encrypted with the old password
+    /// key itself, so they are dropped rather than kept for recovery.
+    fn write_key_slots(&self) -> Result<()> {
+        if self.is_current()? {
+            return storage::save_key_slots(&*self.storage, &self.key);
+        }
+
+        self.rewrite()?;
+        for file in [VaultFile::Key, VaultFile::Servers, VaultFile::Container] {
+            self.storage.remove_previous(file)?;
+        }
+        Ok(())
+    }
+
+    /// Move a vault kept in separate files into a single container file
+    ///
+    /// The container is written before `key.enc`, `key.pub` and `servers.enc`
+    /// are deleted; readers prefer the container once it exists.
+    pub fn convert_to_container(&mut self) -> Result<()> {
+        if self.is_container() {
+            return Ok(());
+        }
+
+        let mut container = Container::new();
+        let public_key_openssh = storage::read_public_key(&*self.storage)?;
+        container.set(Section::PublicKey, public_key_openssh.as_bytes());
+        self.write_container(&container)?;
+
+        for file in [VaultFile::Key, VaultFile::PublicKey, VaultFile::Servers] {
+            self.storage.remove(file)?;
+        }
+        self.container = Some(container);
+        Ok(())
+    }
+
+    /// Write `base` with the current private key and servers
+    fn write_container(&self, base: &Container) -> Result<()> {
+        let mut container = base.clone();
+        container.set(Section::PrivateKey, &self.private_key);
+        container.set(Section::Servers, &serde_json::to_vec(&self.servers)?);
+        storage::save_container(&*self.storage, &container, &self.key)
+    }
+
+    /// Take the private key and servers, dropping the vault key
+    pub fn into_parts(self) -> (SecureBytes, ServerList) {
+        (self.private_key, self.servers)
+    }
+}
@@ -6,8 +6,13 @@
 //! - Side-channel attacks (hybrid approach)
 //! - Time-memory trade-off attacks
 
+use std::fmt;
+use std::time::{Duration, Instant};
+
 use argon2::{Algorithm, Argon2, Params, Version};
+use hmac::{Hmac, Mac};
 use rand::rngs::OsRng;
+use sha2::Sha256;
 use zeroize::Zeroize;
 
 use super::SecureBytes;
@@ -19,20 +24,197 @@ pub const SALT_LEN: usize = 32;
 /// Derived key length in bytes (256 bits for ChaCha20)
 pub const KEY_LEN: usize = 32;
 
-/// Argon2id parameters (OWASP recommended for high security)
+/// Key check value length in bytes (128 bits)
+pub const KEY_CHECK_LEN: usize = 16;
+
+/// HMAC message of the key check value; never used as anything else
+const KEY_CHECK_LABEL: &[u8] = b"secure-ssh key check";
+
+/// Default Argon2id parameters (OWASP recommended for high security)
 /// - Memory: 64 MB (65536 KB)
 /// - Iterations: 3
 /// - Parallelism: 4 threads
+///
+/// Vaults written before parameters were stored in the header use these.
 const MEMORY_COST: u32 = 65536; // 64 MB
 const TIME_COST: u32 = 3;
 const PARALLELISM: u32 = 4;
 
+/// Upper limits accepted from a file header or the command line,
+/// so a damaged header cannot make unlocking allocate unbounded memory
+const MAX_MEMORY_COST: u32 = 4 * 1024 * 1024; // 4 GB
+const MAX_TIME_COST: u32 = 100;
+const MAX_PARALLELISM: u32 = 64;
+
+/// Largest memory cost chosen by calibration; the drive may be
+/// unlocked on weaker machines than the one it was created on
+const CALIBRATION_MAX_MEMORY: u32 = 1024 * 1024; // 1 GB
+
+/// Argon2id cost parameters, stored in the header of every encrypted file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes over memory
+    pub iterations: u32,
+    /// Number of lanes
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: MEMORY_COST,
+            iterations: TIME_COST,
+            parallelism: PARALLELISM,
+        }
+    }
+}
+
+#[cfg(test)]
+impl KdfParams {
+    /// Cheap parameters so the tests do not spend seconds in Argon2
+    pub(crate) const TEST: Self = Self {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+}
+
+impl KdfParams {
+    /// Check the parameters are within the supported limits
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |what: String| Err(SecureSshError::InvalidConfig(what));
+
+        if !(1..=MAX_PARALLELISM).contains(&self.parallelism) {
+            return invalid(t!(KDF_PARALLELISM_RANGE, MAX_PARALLELISM));
+        }
+        if !(1..=MAX_TIME_COST).contains(&self.iterations) {
+            return invalid(t!(KDF_ITERATIONS_RANGE, MAX_TIME_COST));
+        }
+        if self.memory_kib < 8 * self.parallelism || self.memory_kib > MAX_MEMORY_COST {
+            return invalid(t!(KDF_MEMORY_RANGE, 8 * self.parallelism, MAX_MEMORY_COST));
+        }
+        Ok(())
+    }
+
+    /// Parse `m=256M,t=4,p=4` (also `memory=`, `iterations=`, `parallelism=`)
+    ///
+    /// Memory takes a `K`, `M` or `G` suffix (KiB, MiB, GiB); a bare number is KiB.
+    /// Omitted parameters keep their defaults.
+    pub fn parse(spec: &str) -> std::result::Result<Self, String> {
+        let mut params = Self::default();
+
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| t!(KDF_KEY_VALUE, part))?;
+            let number = |value: &str| {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| t!(KDF_NUMBER, value, key))
+            };
+
+            match key.trim() {
+                "m" | "memory" => params.memory_kib = parse_memory(value.trim())?,
+                "t" | "iterations" => params.iterations = number(value.trim())?,
+                "p" | "parallelism" => params.parallelism = number(value.trim())?,
+                other => return Err(t!(KDF_UNKNOWN, other)),
+            }
+        }
+
+        params.validate().map_err(|e| match e {
+            SecureSshError::InvalidConfig(message) => message,
+            other => other.to_string(),
+        })?;
+        Ok(params)
+    }
+
+    /// Pick parameters that take about `target` to derive a key on this machine
+    ///
+    /// Memory is doubled from the default (up to 1 GB) while three passes
+    /// stay well under the target, then iterations fill the remaining time.
+    /// The result is never weaker than the defaults.
+    pub fn calibrate(target: Duration) -> Result<Self> {
+        let mut params = Self {
+            iterations: 1,
+            ..Self::default()
+        };
+
+        let mut pass = time_derivation(&params)?;
+        while pass * TIME_COST * 2 < target && params.memory_kib * 2 <= CALIBRATION_MAX_MEMORY {
+            params.memory_kib *= 2;
+            pass = time_derivation(&params)?;
+        }
+
+        let passes = target.as_secs_f64() / pass.as_secs_f64().max(f64::EPSILON);
+        params.iterations = (passes.round() as u32).clamp(TIME_COST, MAX_TIME_COST);
+        Ok(params)
+    }
+}
+
+impl fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.memory_kib % 1024 == 0 {
+            write!(f, "Argon2id m={}M", self.memory_kib / 1024)?;
+        } else {
+            write!(f, "Argon2id m={}K", self.memory_kib)?;
+        }
+        write!(f, ",t={},p={}", self.iterations, self.parallelism)
+    }
+}
+
+/// Parse a memory size: `65536`, `64M`, `1G` (binary units, KiB by default)
+fn parse_memory(value: &str) -> std::result::Result<u32, String> {
+    let (digits, multiplier) = match value.chars().last() {
+        Some('k' | 'K') => (&value[..value.len() - 1], 1),
+        Some('m' | 'M') => (&value[..value.len() - 1], 1024),
+        Some('g' | 'G') => (&value[..value.len() - 1], 1024 * 1024),
+        _ => (value, 1),
+    };
+
+    digits
+        .parse::<u32>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| t!(KDF_MEMORY_SIZE, value))
+}
+
+/// Time one key derivation with the given parameters
+fn time_derivation(params: &KdfParams) -> Result<Duration> {
+    let started = Instant::now();
+    derive_key(b"calibration", Some(&[0u8; SALT_LEN]), params)?;
+    Ok(started.elapsed())
+}
+
 /// A derived encryption key with its associated salt
 pub struct DerivedKey {
     /// The derived key material (32 bytes)
     pub key: SecureBytes,
     /// The salt used for derivation (32 bytes)
     pub salt: [u8; SALT_LEN],
+    /// The Argon2id parameters used for derivation
+    pub params: KdfParams,
+}
+
+impl DerivedKey {
+    /// Key check value stored next to the ciphertext
+    ///
+    /// HMAC-SHA256 of a fixed label under the key, truncated. A file whose
+    /// value differs was encrypted under another key, i.e. the password is
+    /// wrong; a matching value with a failing tag means the file is damaged.
+    pub fn check_value(&self) -> [u8; KEY_CHECK_LEN] {
+        key_check(&self.key)
+    }
+}
+
+/// Key check value of any key, see [`DerivedKey::check_value`]
+pub fn key_check(key: &[u8]) -> [u8; KEY_CHECK_LEN] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(KEY_CHECK_LABEL);
+    let mut check = [0u8; KEY_CHECK_LEN];
+    check.copy_from_slice(&mac.finalize().into_bytes()[..KEY_CHECK_LEN]);
+    check
 }
 
 impl Zeroize for DerivedKey {
@@ -53,15 +235,22 @@ impl Drop for DerivedKey {
 /// # Arguments
 /// * `password` - The user's password
 /// * `salt` - Optional salt (if None, generates a new random salt)
+/// * `params` - Argon2id cost parameters
 ///
 /// # Returns
-/// A DerivedKey containing the key material and salt
+/// A DerivedKey containing the key material, salt and parameters
 ///
 /// # Security Notes
 /// - Uses memory-hard function to resist GPU attacks
 /// - Salt prevents rainbow table attacks
 /// - High iteration count slows brute-force attempts
-pub fn derive_key(password: &[u8], salt: Option<&[u8; SALT_LEN]>) -> Result<DerivedKey> {
+pub fn derive_key(
+    password: &[u8],
+    salt: Option<&[u8; SALT_LEN]>,
+    params: &KdfParams,
+) -> Result<DerivedKey> {
+    params.validate()?;
+
     // Generate or use provided salt
     let salt_bytes: [u8; SALT_LEN] = match salt {
         Some(s) => *s,
@@ -73,28 +262,62 @@ pub fn derive_key(password: &[u8], salt: Option<&[u8; SALT_LEN]>) -> Result<Deri
         }
     };
 
-    // Configure Argon2id with secure parameters
-    let params = Params::new(MEMORY_COST, TIME_COST, PARALLELISM, Some(KEY_LEN))
-        .map_err(|e| SecureSshError::Other(format!("Argon2 params error: {}", e)))?;
+    // Configure Argon2id with the requested parameters
+    let argon_params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(KEY_LEN),
+    )
+    .map_err(|e| SecureSshError::Other(t!(KDF_PARAMS_FAILED, e)))?;
 
-    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params);
 
     // Derive the key
     let mut key_bytes = vec![0u8; KEY_LEN];
     argon2
         .hash_password_into(password, &salt_bytes, &mut key_bytes)
-        .map_err(|e| SecureSshError::Other(format!("Key derivation failed: {}", e)))?;
+        .map_err(|e| SecureSshError::Other(t!(KDF_FAILED, e)))?;
 
     Ok(DerivedKey {
         key: SecureBytes::new(key_bytes),
         salt: salt_bytes,
+        params: *params,
     })
 }
 
 /// Derive a key using an existing salt (for decryption)
 #[allow(dead_code)]
-pub fn derive_key_with_salt(password: &[u8], salt: &[u8; SALT_LEN]) -> Result<DerivedKey> {
-    derive_key(password, Some(salt))
+pub fn derive_key_with_salt(
+    password: &[u8],
+    salt: &[u8; SALT_LEN],
+    params: &KdfParams,
+) -> Result<DerivedKey> {
+    derive_key(password, Some(salt), params)
+}
+
+/// Password key with [`KdfParams::TEST`] and a fixed salt, for tests
+#[cfg(test)]
+pub(crate) fn test_password_key(password: &[u8]) -> DerivedKey {
+    derive_key(password, Some(&[3u8; SALT_LEN]), &KdfParams::TEST).unwrap()
+}
+
+/// Key for the password `password` as a version 1 file needs it, for tests
+///
+/// Version 1 implies the default parameters, so [`KdfParams::TEST`] cannot
+/// stand in; the key is derived once and shared by the tests that need it.
+#[cfg(test)]
+pub(crate) fn test_v1_password_key() -> DerivedKey {
+    static KEY: std::sync::OnceLock<Vec<u8>> = std::sync::OnceLock::new();
+    let salt = [3u8; SALT_LEN];
+    let key = KEY.get_or_init(|| {
+        derive_key(b"password", Some(&salt), &KdfParams::default()).unwrap().key.to_vec()
+    });
+    DerivedKey {
+        key: SecureBytes::new(key.clone()),
+        salt,
+        params: KdfParams::default(),
+    }
 }
 
 #[cfg(test)]
@@ -106,8 +329,8 @@ mod tests {
         let password = b"test_password_123";
         let salt = [0x42u8; SALT_LEN];
 
-        let key1 = derive_key(password, Some(&salt)).unwrap();
-        let key2 = derive_key(password, Some(&salt)).unwrap();
+        let key1 = derive_key(password, Some(&salt), &KdfParams::default()).unwrap();
+        let key2 = derive_key(password, Some(&salt), &KdfParams::default()).unwrap();
 
         assert_eq!(&*key1.key, &*key2.key);
     }
@@ -118,8 +341,8 @@ mod tests {
         let salt1 = [0x42u8; SALT_LEN];
         let salt2 = [0x43u8; SALT_LEN];
 
-        let key1 = derive_key(password, Some(&salt1)).unwrap();
-        let key2 = derive_key(password, Some(&salt2)).unwrap();
+        let key1 = derive_key(password, Some(&salt1), &KdfParams::default()).unwrap();
+        let key2 = derive_key(password, Some(&salt2), &KdfParams::default()).unwrap();
 
         assert_ne!(&*key1.key, &*key2.key);
     }
@@ -128,11 +351,65 @@ mod tests {
     fn test_derive_key_random_salt() {
         let password = b"test_password_123";
 
-        let key1 = derive_key(password, None).unwrap();
-        let key2 = derive_key(password, None).unwrap();
+        let key1 = derive_key(password, None, &KdfParams::default()).unwrap();
+        let key2 = derive_key(password, None, &KdfParams::default()).unwrap();
 
         // Different random salts should produce different keys
         assert_ne!(key1.salt, key2.salt);
         assert_ne!(&*key1.key, &*key2.key);
     }
+
+    #[test]
+    fn test_params_change_key() {
+        let password = b"test_password_123";
+        let salt = [0x42u8; SALT_LEN];
+        let light = KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let heavier = KdfParams {
+            iterations: 2,
+            ..light
+        };
+
+        let key1 = derive_key(password, Some(&salt), &light).unwrap();
+        let key2 = derive_key(password, Some(&salt), &heavier).unwrap();
+
+        assert_ne!(&*key1.key, &*key2.key);
+        assert_eq!(key2.params, heavier);
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let params = KdfParams::parse("m=256M,t=4,p=2").unwrap();
+        assert_eq!(params.memory_kib, 256 * 1024);
+        assert_eq!(params.iterations, 4);
+        assert_eq!(params.parallelism, 2);
+
+        let params = KdfParams::parse("iterations=5").unwrap();
+        assert_eq!(params.memory_kib, MEMORY_COST);
+        assert_eq!(params.iterations, 5);
+
+        assert_eq!(KdfParams::parse("m=1G").unwrap().memory_kib, 1024 * 1024);
+        assert_eq!(KdfParams::parse("m=65536").unwrap().memory_kib, 65536);
+
+        assert!(KdfParams::parse("m=8G").is_err());
+        assert!(KdfParams::parse("t=0").is_err());
+        assert!(KdfParams::parse("x=1").is_err());
+        assert!(KdfParams::parse("m").is_err());
+    }
+
+    #[test]
+    fn test_check_value_depends_on_key() {
+        let salt = [0x42u8; SALT_LEN];
+
+        let key1 = derive_key(b"password one", Some(&salt), &KdfParams::TEST).unwrap();
+        let key2 = derive_key(b"password one", Some(&salt), &KdfParams::TEST).unwrap();
+        let key3 = derive_key(b"password two", Some(&salt), &KdfParams::TEST).unwrap();
+
+        assert_eq!(key1.check_value(), key2.check_value());
+        assert_ne!(key1.check_value(), key3.check_value());
+        assert_ne!(&key1.check_value()[..], &key1.key[..KEY_CHECK_LEN]);
+    }
 }
@@ -5,7 +5,7 @@
 //! on systems without AES hardware acceleration.
 
 use chacha20poly1305::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     ChaCha20Poly1305, Nonce,
 };
 use rand::RngCore;
@@ -18,12 +18,18 @@ use crate::error::{Result, SecureSshError};
 pub const NONCE_LEN: usize = 12;
 
 /// Authentication tag length (128 bits)
-#[allow(dead_code)]
 pub const TAG_LEN: usize = 16;
 
 /// Key length (256 bits)
 pub const KEY_LEN: usize = 32;
 
+/// Generate a random nonce
+pub fn generate_nonce() -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    nonce
+}
+
 /// Encrypt data using ChaCha20-Poly1305
 ///
 /// # Arguments
@@ -37,29 +43,35 @@ pub const KEY_LEN: usize = 32;
 /// - Uses random nonce for each encryption
 /// - Authentication tag prevents tampering
 /// - Ciphertext is slightly larger than plaintext (+16 bytes for tag)
+#[allow(dead_code)]
 pub fn encrypt(key: &[u8], plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let nonce = generate_nonce();
+    let ciphertext = encrypt_with_aad(key, &nonce, plaintext, &[])?;
+    Ok((nonce.to_vec(), ciphertext))
+}
+
+/// Encrypt data, authenticating `aad` alongside it
+///
+/// `aad` is not encrypted or stored; decryption must pass the same bytes.
+/// The caller supplies the nonce so it can be part of `aad`, and must never
+/// reuse it with the same key.
+pub fn encrypt_with_aad(
+    key: &[u8],
+    nonce: &[u8; NONCE_LEN],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
     if key.len() != KEY_LEN {
-        return Err(SecureSshError::EncryptionFailed(format!(
-            "Invalid key length: expected {}, got {}",
-            KEY_LEN,
-            key.len()
-        )));
+        return Err(SecureSshError::EncryptionFailed(t!(KEY_LENGTH, KEY_LEN, key.len())));
     }
 
-    // Generate random nonce
-    let mut nonce_bytes = [0u8; NONCE_LEN];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-
     // Create cipher and encrypt
     let cipher = ChaCha20Poly1305::new_from_slice(key)
         .map_err(|e| SecureSshError::EncryptionFailed(e.to_string()))?;
 
-    let ciphertext = cipher
-        .encrypt(nonce, plaintext)
-        .map_err(|e| SecureSshError::EncryptionFailed(e.to_string()))?;
-
-    Ok((nonce_bytes.to_vec(), ciphertext))
+    cipher
+        .encrypt(Nonce::from_slice(nonce), Payload { msg: plaintext, aad })
+        .map_err(|e| SecureSshError::EncryptionFailed(e.to_string()))
 }
 
 /// Decrypt data using ChaCha20-Poly1305
@@ -76,7 +88,20 @@ pub fn encrypt(key: &[u8], plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
 /// Returns DecryptionFailed if:
 /// - Key or nonce has wrong length
 /// - Authentication tag verification fails (wrong key or tampered data)
+#[allow(dead_code)]
 pub fn decrypt(key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Result<SecureBytes> {
+    decrypt_with_aad(key, nonce, ciphertext, &[])
+}
+
+/// Decrypt data that was encrypted with [`encrypt_with_aad`]
+///
+/// Fails with DecryptionFailed if `aad` differs from the bytes used to encrypt.
+pub fn decrypt_with_aad(
+    key: &[u8],
+    nonce: &[u8],
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<SecureBytes> {
     if key.len() != KEY_LEN {
         return Err(SecureSshError::DecryptionFailed);
     }
@@ -91,7 +116,7 @@ pub fn decrypt(key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Result<SecureByte
         .map_err(|_| SecureSshError::DecryptionFailed)?;
 
     let plaintext = cipher
-        .decrypt(nonce, ciphertext)
+        .decrypt(nonce, Payload { msg: ciphertext, aad })
         .map_err(|_| SecureSshError::DecryptionFailed)?;
 
     Ok(SecureBytes::new(plaintext))
@@ -153,4 +178,17 @@ mod tests {
         // Ciphertexts should be different
         assert_ne!(ciphertext1, ciphertext2);
     }
+
+    #[test]
+    fn test_aad_must_match() {
+        let key = [0x42u8; KEY_LEN];
+        let nonce = generate_nonce();
+
+        let ciphertext = encrypt_with_aad(&key, &nonce, b"Secret message", b"header").unwrap();
+        let decrypted = decrypt_with_aad(&key, &nonce, &ciphertext, b"header").unwrap();
+        assert_eq!(&*decrypted, b"Secret message");
+
+        assert!(decrypt_with_aad(&key, &nonce, &ciphertext, b"Header").is_err());
+        assert!(decrypt(&key, &nonce, &ciphertext).is_err());
+    }
 }
@@ -0,0 +1,468 @@
+//! Header of encrypted files
+//!
+//! Version 1 (KDF parameters implied, always the defaults):
+//! [4 bytes: version][32 bytes: salt][12 bytes: nonce]
+//!
+//! Version 2:
+//! [4 bytes: version][1 byte: KDF id][4 bytes: memory KiB][4 bytes: iterations]
+//! [4 bytes: parallelism][32 bytes: salt][12 bytes: nonce]
+//!
+//! Version 3 adds a file type byte after the version, and the whole header
+//! is passed to ChaCha20-Poly1305 as associated data, so changing any header
+//! byte or moving a file into another file's place fails authentication:
+//! [4 bytes: version][1 byte: file type][1 byte: KDF id][4 bytes: memory KiB]
+//! [4 bytes: iterations][4 bytes: parallelism][32 bytes: salt][12 bytes: nonce]
+//!
+//! Version 4 adds the key check value of the derived key (see
+//! [`super::DerivedKey::check_value`]) before the salt, which tells a wrong
+//! password from a damaged file:
+//! [4 bytes: version][1 byte: file type][1 byte: KDF id][4 bytes: memory KiB]
+//! [4 bytes: iterations][4 bytes: parallelism][16 bytes: key check]
+//! [32 bytes: salt][12 bytes: nonce]
+//!
+//! Version 5 encrypts the file with a subkey of the vault master key (see
+//! [`super::MasterKey`]) instead of the password key. The master file
+//! carries the key slots the master key is wrapped in; the other files
+//! carry none. The key check is that of the subkey:
+//! [4 bytes: version][1 byte: file type][1 byte: slot count N]
+//! [N × key slot][16 bytes: key check][12 bytes: nonce]
+//!
+//! The associated data of a version 5 file is its header with the slot
+//! count set to 0 and no slots, so re-wrapping the master key under a new
+//! password leaves the ciphertext valid.
+//!
+//! All integers are big-endian. The ciphertext with its tag follows the header.
+
+use super::{key_check, KdfParams, KeySlot, KEY_CHECK_LEN, MAX_KEY_SLOTS, NONCE_LEN, SALT_LEN, TAG_LEN};
+
+/// Version written by this build
+pub const FORMAT_VERSION: u32 = 5;
+
+/// KDF id of Argon2id (version 0x13)
+const KDF_ARGON2ID: u8 = 1;
+
+/// What a file is encrypted with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileKey {
+    /// Versions 1 to 4: the key derived from the password with these parameters
+    Password { kdf: KdfParams, salt: [u8; SALT_LEN] },
+    /// Version 5: a subkey of the vault master key; the master file carries
+    /// the slots the master key is wrapped in, other files none
+    Master { slots: Vec<KeySlot> },
+}
+
+/// Parsed file header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: u32,
+    /// What the file holds; `None` in versions before 3
+    pub file_type: Option<u8>,
+    pub key: FileKey,
+    /// Key check value of the key the file is encrypted with; `None` before version 4
+    pub key_check: Option<[u8; KEY_CHECK_LEN]>,
+    pub nonce: [u8; NONCE_LEN],
+}
+
+impl Header {
+    /// Header of a file of `file_type` encrypted with the master key subkey
+    /// `subkey` under `nonce`, in the current format
+    pub fn new(file_type: u8, slots: Vec<KeySlot>, subkey: &[u8], nonce: [u8; NONCE_LEN]) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            file_type: Some(file_type),
+            key: FileKey::Master { slots },
+            key_check: Some(key_check(subkey)),
+            nonce,
+        }
+    }
+
+    /// Serialize in the layout of its version
+    ///
+    /// New headers are always in the current format; a parsed header from
+    /// version 3 on encodes back to the exact bytes it was read from, which
+    /// its file was authenticated with.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = self.version.to_be_bytes().to_vec();
+        data.push(self.file_type.unwrap_or_default());
+        match &self.key {
+            FileKey::Password { kdf, salt } => {
+                write_kdf(&mut data, kdf);
+                if let Some(key_check) = &self.key_check {
+                    data.extend_from_slice(key_check);
+                }
+                data.extend_from_slice(salt);
+            }
+            FileKey::Master { slots } => {
+                data.push(slots.len() as u8);
+                for slot in slots {
+                    slot.encode_into(&mut data);
+                }
+                data.extend_from_slice(&self.key_check.unwrap_or_default());
+            }
+        }
+        data.extend_from_slice(&self.nonce);
+        data
+    }
+
+    /// Associated data the ciphertext is authenticated with
+    ///
+    /// The encoded header from version 3 on, nothing for older files, which
+    /// were encrypted without associated data. From version 5 the key slots
+    /// are left out: each authenticates itself, and a new password replaces
+    /// them without touching the ciphertext.
+    pub fn aad(&self) -> Vec<u8> {
+        match &self.key {
+            FileKey::Master { .. } => Self {
+                key: FileKey::Master { slots: Vec::new() },
+                ..self.clone()
+            }
+            .encode(),
+            FileKey::Password { .. } if self.version >= 3 => self.encode(),
+            FileKey::Password { .. } => Vec::new(),
+        }
+    }
+
+    /// Split a file into its header and ciphertext
+    ///
+    /// Every version ever written is accepted; older versions lack the fields
+    /// added later and read them as `None` or defaults.
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), String> {
+        let mut reader = Reader(data);
+        let version = reader.u32().ok_or(t!(FILE_CORRUPTED))?;
+
+        let fields = match version {
+            1 => read_v1(&mut reader)?,
+            2 => read_v2(&mut reader)?,
+            3 => read_v3(&mut reader)?,
+            4 => read_v4(&mut reader)?,
+            5 => read_v5(&mut reader)?,
+            other => return Err(t!(FILE_VERSION_UNSUPPORTED, other)),
+        };
+
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(reader.take(NONCE_LEN).ok_or(t!(FILE_CORRUPTED))?);
+
+        let ciphertext = reader.0;
+        if ciphertext.len() < TAG_LEN {
+            return Err(t!(FILE_CORRUPTED).into());
+        }
+
+        Ok((
+            Self {
+                version,
+                file_type: fields.file_type,
+                key: fields.key,
+                key_check: fields.key_check,
+                nonce,
+            },
+            ciphertext,
+        ))
+    }
+}
+
+/// Fields between the version and the nonce, as read by each version
+struct VersionFields {
+    file_type: Option<u8>,
+    key: FileKey,
+    key_check: Option<[u8; KEY_CHECK_LEN]>,
+}
+
+/// Version 1: the salt; the parameters were fixed
+fn read_v1(reader: &mut Reader) -> Result<VersionFields, String> {
+    Ok(VersionFields {
+        file_type: None,
+        key: FileKey::Password {
+            kdf: KdfParams::default(),
+            salt: read_salt(reader)?,
+        },
+        key_check: None,
+    })
+}
+
+/// Version 2: KDF id and Argon2id parameters, then the salt
+fn read_v2(reader: &mut Reader) -> Result<VersionFields, String> {
+    let kdf = read_kdf(reader)?;
+    Ok(VersionFields {
+        file_type: None,
+        key: FileKey::Password {
+            kdf,
+            salt: read_salt(reader)?,
+        },
+        key_check: None,
+    })
+}
+
+/// Version 3: file type, then the version 2 fields
+fn read_v3(reader: &mut Reader) -> Result<VersionFields, String> {
+    let file_type = reader.u8().ok_or(t!(FILE_CORRUPTED))?;
+    Ok(VersionFields {
+        file_type: Some(file_type),
+        ..read_v2(reader)?
+    })
+}
+
+/// Version 4: file type, KDF, key check value, salt
+fn read_v4(reader: &mut Reader) -> Result<VersionFields, String> {
+    let file_type = reader.u8().ok_or(t!(FILE_CORRUPTED))?;
+    let kdf = read_kdf(reader)?;
+    let key_check = read_key_check(reader)?;
+    Ok(VersionFields {
+        file_type: Some(file_type),
+        key: FileKey::Password {
+            kdf,
+            salt: read_salt(reader)?,
+        },
+        key_check: Some(key_check),
+    })
+}
+
+/// Version 5: file type, key slots, key check value of the subkey
+fn read_v5(reader: &mut Reader) -> Result<VersionFields, String> {
+    let file_type = reader.u8().ok_or(t!(FILE_CORRUPTED))?;
+    let count = reader.u8().ok_or(t!(FILE_CORRUPTED))?;
+    // Only the master file has slots, so zero is for the caller to reject
+    if usize::from(count) > MAX_KEY_SLOTS {
+        return Err(t!(FILE_CORRUPTED).into());
+    }
+    let slots = (0..count)
+        .map(|_| KeySlot::read(reader))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(VersionFields {
+        file_type: Some(file_type),
+        key: FileKey::Master { slots },
+        key_check: Some(read_key_check(reader)?),
+    })
+}
+
+fn read_salt(reader: &mut Reader) -> Result<[u8; SALT_LEN], String> {
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(reader.take(SALT_LEN).ok_or(t!(FILE_CORRUPTED))?);
+    Ok(salt)
+}
+
+fn read_key_check(reader: &mut Reader) -> Result<[u8; KEY_CHECK_LEN], String> {
+    let mut key_check = [0u8; KEY_CHECK_LEN];
+    key_check.copy_from_slice(reader.take(KEY_CHECK_LEN).ok_or(t!(FILE_CORRUPTED))?);
+    Ok(key_check)
+}
+
+/// KDF id and Argon2id parameters, validated
+pub(super) fn read_kdf(reader: &mut Reader) -> Result<KdfParams, String> {
+    let id = reader.u8().ok_or(t!(FILE_CORRUPTED))?;
+    if id != KDF_ARGON2ID {
+        return Err(t!(FILE_KDF_UNSUPPORTED, id));
+    }
+    let params = KdfParams {
+        memory_kib: reader.u32().ok_or(t!(FILE_CORRUPTED))?,
+        iterations: reader.u32().ok_or(t!(FILE_CORRUPTED))?,
+        parallelism: reader.u32().ok_or(t!(FILE_CORRUPTED))?,
+    };
+    params.validate().map_err(|e| e.to_string())?;
+    Ok(params)
+}
+
+pub(super) fn write_kdf(data: &mut Vec<u8>, kdf: &KdfParams) {
+    data.push(KDF_ARGON2ID);
+    data.extend_from_slice(&kdf.memory_kib.to_be_bytes());
+    data.extend_from_slice(&kdf.iterations.to_be_bytes());
+    data.extend_from_slice(&kdf.parallelism.to_be_bytes());
+}
+
+/// Cursor over the header bytes
+pub(super) struct Reader<'a>(pub(super) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(super) fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(head)
+    }
+
+    pub(super) fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{test_password_key, MasterKey};
+
+    fn v4_header(kdf: KdfParams) -> Header {
+        Header {
+            version: 4,
+            file_type: Some(2),
+            key: FileKey::Password {
+                kdf,
+                salt: [7u8; SALT_LEN],
+            },
+            key_check: Some([5u8; KEY_CHECK_LEN]),
+            nonce: [9u8; NONCE_LEN],
+        }
+    }
+
+    fn slot() -> KeySlot {
+        KeySlot::wrap(&test_password_key(b"password"), &MasterKey::generate()).unwrap()
+    }
+
+    fn password_fields(header: &Header) -> (KdfParams, [u8; SALT_LEN]) {
+        match &header.key {
+            FileKey::Password { kdf, salt } => (*kdf, *salt),
+            FileKey::Master { .. } => panic!("expected a password header"),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let header = Header::new(1, vec![slot(), slot()], &[4u8; 32], [9u8; NONCE_LEN]);
+        let mut data = header.encode();
+        data.extend_from_slice(&[1u8; TAG_LEN]);
+
+        let (parsed, ciphertext) = Header::parse(&data).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(parsed.version, FORMAT_VERSION);
+        assert_eq!(ciphertext, &[1u8; TAG_LEN]);
+    }
+
+    #[test]
+    fn test_aad_leaves_out_slots() {
+        let header = Header::new(1, vec![slot()], &[4u8; 32], [9u8; NONCE_LEN]);
+        let other = Header {
+            key: FileKey::Master {
+                slots: vec![slot(), slot()],
+            },
+            ..header.clone()
+        };
+        assert_eq!(header.aad(), other.aad());
+        assert_ne!(header.encode(), other.encode());
+
+        // Everything else is still covered
+        let other_type = Header::new(2, vec![slot()], &[4u8; 32], [9u8; NONCE_LEN]);
+        assert_ne!(header.aad(), other_type.aad());
+    }
+
+    #[test]
+    fn test_v4_roundtrip() {
+        let kdf = KdfParams {
+            memory_kib: 262144,
+            iterations: 4,
+            parallelism: 2,
+        };
+        let mut data = v4_header(kdf).encode();
+        assert_eq!(v4_header(kdf).aad(), data);
+        data.extend_from_slice(&[1u8; TAG_LEN]);
+
+        let (parsed, ciphertext) = Header::parse(&data).unwrap();
+        assert_eq!(parsed, v4_header(kdf));
+        assert_eq!(ciphertext, &[1u8; TAG_LEN]);
+    }
+
+    #[test]
+    fn test_v1_uses_default_params() {
+        let mut data = 1u32.to_be_bytes().to_vec();
+        data.extend_from_slice(&[7u8; SALT_LEN]);
+        data.extend_from_slice(&[9u8; NONCE_LEN]);
+        data.extend_from_slice(&[1u8; TAG_LEN]);
+
+        let (parsed, ciphertext) = Header::parse(&data).unwrap();
+        assert_eq!(parsed.version, 1);
+        assert_eq!(parsed.file_type, None);
+        assert_eq!(password_fields(&parsed), (KdfParams::default(), [7u8; SALT_LEN]));
+        assert!(parsed.aad().is_empty());
+        assert_eq!(ciphertext.len(), TAG_LEN);
+    }
+
+    #[test]
+    fn test_v2_reads_params() {
+        let mut data = 2u32.to_be_bytes().to_vec();
+        data.push(KDF_ARGON2ID);
+        for value in [131072u32, 5, 2] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        data.extend_from_slice(&[7u8; SALT_LEN]);
+        data.extend_from_slice(&[9u8; NONCE_LEN]);
+        data.extend_from_slice(&[1u8; TAG_LEN]);
+
+        let (parsed, _) = Header::parse(&data).unwrap();
+        assert_eq!(parsed.version, 2);
+        assert_eq!(parsed.file_type, None);
+        let (kdf, _) = password_fields(&parsed);
+        assert_eq!(kdf.memory_kib, 131072);
+        assert_eq!(kdf.iterations, 5);
+        assert_eq!(kdf.parallelism, 2);
+        assert_eq!(parsed.key_check, None);
+        assert!(parsed.aad().is_empty());
+    }
+
+    #[test]
+    fn test_v3_encodes_back_to_its_bytes() {
+        let mut data = 3u32.to_be_bytes().to_vec();
+        data.extend_from_slice(&[2, KDF_ARGON2ID]);
+        for value in [65536u32, 3, 4] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        data.extend_from_slice(&[7u8; SALT_LEN]);
+        data.extend_from_slice(&[9u8; NONCE_LEN]);
+        let header_len = data.len();
+        data.extend_from_slice(&[1u8; TAG_LEN]);
+
+        let (parsed, _) = Header::parse(&data).unwrap();
+        assert_eq!(parsed.file_type, Some(2));
+        assert_eq!(parsed.key_check, None);
+        assert_eq!(parsed.aad(), &data[..header_len]);
+    }
+
+    #[test]
+    fn test_rejects_bad_headers() {
+        let good = {
+            let mut data = v4_header(KdfParams::default()).encode();
+            data.extend_from_slice(&[1u8; TAG_LEN]);
+            data
+        };
+        let header_len = good.len() - TAG_LEN;
+
+        // Truncated before the tag
+        assert!(Header::parse(&good[..header_len + 3]).is_err());
+
+        // Unknown version
+        let mut data = good.clone();
+        data[3] = 9;
+        assert!(Header::parse(&data).is_err());
+
+        // Unknown KDF
+        let mut data = good.clone();
+        data[5] = 2;
+        assert!(Header::parse(&data).is_err());
+
+        // Absurd memory cost
+        let mut data = good;
+        data[6..10].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(Header::parse(&data).is_err());
+
+        // More slots than the file holds
+        let mut data = Header::new(1, vec![slot()], &[4u8; 32], [9u8; NONCE_LEN]).encode();
+        data.extend_from_slice(&[1u8; TAG_LEN]);
+        data[5] = 3;
+        assert!(Header::parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_rejects_more_slots_than_the_limit() {
+        let parse = |slots: Vec<KeySlot>| {
+            let mut data = Header::new(1, slots, &[4u8; 32], [9u8; NONCE_LEN]).encode();
+            data.extend_from_slice(&[1u8; TAG_LEN]);
+            Header::parse(&data).map(|_| ())
+        };
+
+        assert!(parse(vec![]).is_ok());
+        assert!(parse((0..MAX_KEY_SLOTS).map(|_| slot()).collect()).is_ok());
+        assert!(parse((0..=MAX_KEY_SLOTS).map(|_| slot()).collect()).is_err());
+    }
+}
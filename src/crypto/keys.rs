@@ -34,11 +34,14 @@ impl KeyPair {
     }
 
     /// Create a KeyPair from an existing private key
+    #[allow(dead_code)]
     pub fn from_private_key(private_key: SecureBytes) -> Result<Self> {
         if private_key.len() != 32 {
-            return Err(SecureSshError::KeyGenerationFailed(
-                "Invalid private key length".to_string(),
-            ));
+            return Err(SecureSshError::KeyGenerationFailed(t!(
+                PRIVATE_KEY_LENGTH,
+                32,
+                private_key.len()
+            )));
         }
 
         let mut key_bytes = [0u8; 32];
@@ -57,6 +60,7 @@ impl KeyPair {
     }
 
     /// Get the private key bytes (for encryption/storage)
+    #[allow(dead_code)]
     pub fn private_key_bytes(&self) -> &[u8] {
         &self.private_key
     }
@@ -93,7 +97,6 @@ impl KeyPair {
 
     /// Consume the keypair and return the private key
     /// Uses mem::take to safely extract the private key while still running Drop
-    #[allow(dead_code)]
     pub fn into_private_key(mut self) -> SecureBytes {
         std::mem::take(&mut self.private_key)
     }
@@ -112,6 +115,13 @@ impl Drop for KeyPair {
     }
 }
 
+/// SHA-256 fingerprint of an OpenSSH public key line, as shown by `ssh-keygen -l`
+pub fn fingerprint(public_key_openssh: &str) -> Result<String> {
+    let key = ssh_key::PublicKey::from_openssh(public_key_openssh)
+        .map_err(|e| SecureSshError::InvalidConfig(t!(PUBLIC_KEY_INVALID, e)))?;
+    Ok(key.fingerprint(ssh_key::HashAlg::Sha256).to_string())
+}
+
 /// Generate a new Ed25519 keypair
 #[allow(dead_code)]
 pub fn generate_keypair() -> Result<KeyPair> {
@@ -141,6 +151,16 @@ mod tests {
         assert_eq!(restored.public_key_bytes(), &original_public);
     }
 
+    #[test]
+    fn test_fingerprint() {
+        let keypair = generate_keypair().unwrap();
+        let fp = fingerprint(&keypair.public_key_openssh("test")).unwrap();
+
+        assert!(fp.starts_with("SHA256:"));
+        assert_eq!(fp, fingerprint(&keypair.public_key_openssh("other")).unwrap());
+        assert!(fingerprint("ssh-ed25519 garbage").is_err());
+    }
+
     #[test]
     fn test_public_key_openssh_format() {
         let keypair = generate_keypair().unwrap();
@@ -0,0 +1,196 @@
+//! Vault master key and per-purpose subkeys
+//!
+//! From format 5 the vault files are not encrypted with the key derived from
+//! the password. Each vault has a random 256-bit master key, and every file
+//! is encrypted with its own subkey, derived from the master key with
+//! HKDF-SHA256 and the file's purpose as info. No two files (or sections
+//! added later) share a key, and each password only wraps the master key in
+//! a [`KeySlot`], so changing one rewrites its slot and nothing else.
+
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+
+use super::{derive_key, DerivedKey, KeySlot, SecureBytes};
+use crate::error::{Result, SecureSshError};
+
+/// Master key length in bytes (256 bits)
+pub const MASTER_KEY_LEN: usize = 32;
+
+/// HKDF info prefix; the purpose follows
+const SUBKEY_INFO: &str = "secure-ssh subkey ";
+
+/// Random key the vault files are encrypted under, through subkeys
+pub struct MasterKey(SecureBytes);
+
+impl MasterKey {
+    pub fn generate() -> Self {
+        let mut key = SecureBytes::zeroed(MASTER_KEY_LEN);
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    pub(super) fn from_bytes(key: SecureBytes) -> Result<Self> {
+        if key.len() != MASTER_KEY_LEN {
+            return Err(SecureSshError::DecryptionFailed);
+        }
+        Ok(Self(key))
+    }
+
+    pub(super) fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Key for one purpose, e.g. one vault file
+    ///
+    /// The master key is uniformly random already, so HKDF runs without salt.
+    pub fn subkey(&self, purpose: &str) -> SecureBytes {
+        let hkdf = Hkdf::<Sha256>::new(None, &self.0);
+        let mut key = SecureBytes::zeroed(MASTER_KEY_LEN);
+        hkdf.expand_multi_info(&[SUBKEY_INFO.as_bytes(), purpose.as_bytes()], &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        key
+    }
+}
+
+/// Keys of an unlocked vault
+pub struct VaultKey {
+    /// Derived from the password; wraps the master key, and decrypts files
+    /// from before format 5 directly
+    pub password: DerivedKey,
+    pub master: MasterKey,
+    /// Slots the master file carries
+    pub slots: Vec<KeySlot>,
+    /// Index of the slot `password` opened
+    pub slot: usize,
+}
+
+impl VaultKey {
+    /// A new master key in a single slot under `password`
+    pub fn create(password: DerivedKey) -> Result<Self> {
+        let master = MasterKey::generate();
+        let slots = vec![KeySlot::wrap(&password, &master)?];
+        Ok(Self {
+            password,
+            master,
+            slots,
+            slot: 0,
+        })
+    }
+
+    /// Open the first slot `password` fits
+    ///
+    /// Runs Argon2 once per slot until one matches. No match is
+    /// [`SecureSshError::InvalidPassword`]; a matching but damaged slot is
+    /// [`SecureSshError::DecryptionFailed`].
+    pub fn open(slots: Vec<KeySlot>, password: &[u8]) -> Result<Self> {
+        let mut opened = None;
+        for (index, slot) in slots.iter().enumerate() {
+            let password_key = derive_key(password, Some(&slot.salt), &slot.kdf)?;
+            match slot.open(&password_key) {
+                Ok(master) => {
+                    opened = Some((index, password_key, master));
+                    break;
+                }
+                Err(SecureSshError::InvalidPassword) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let (slot, password, master) = opened.ok_or(SecureSshError::InvalidPassword)?;
+        Ok(Self {
+            password,
+            master,
+            slots,
+            slot,
+        })
+    }
+
+    /// Wrap the same master key under a new password key in the slot that
+    /// was opened; the other slots keep their passwords
+    pub fn rewrap(&mut self, password: DerivedKey) -> Result<()> {
+        self.slots[self.slot] = KeySlot::wrap(&password, &self.master)?;
+        self.password = password;
+        Ok(())
+    }
+
+    /// Wrap the master key under another password in a new last slot
+    ///
+    /// The caller keeps the count within [`super::MAX_KEY_SLOTS`].
+    pub fn add_slot(&mut self, password: &DerivedKey) -> Result<()> {
+        self.slots.push(KeySlot::wrap(password, &self.master)?);
+        Ok(())
+    }
+
+    /// Remove a slot other than the one that was opened, revoking its password
+    ///
+    /// The slots after it move down by one.
+    pub fn remove_slot(&mut self, index: usize) -> KeySlot {
+        assert_ne!(index, self.slot, "the slot in use cannot be removed");
+        if index < self.slot {
+            self.slot -= 1;
+        }
+        self.slots.remove(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::test_password_key as password_key;
+
+    #[test]
+    fn test_subkeys_are_independent() {
+        let master = MasterKey::generate();
+        assert_eq!(&*master.subkey("key"), &*master.subkey("key"));
+        assert_ne!(&*master.subkey("key"), &*master.subkey("servers"));
+        assert_ne!(&*master.subkey("key"), master.as_bytes());
+        assert_ne!(&*master.subkey("key"), &*MasterKey::generate().subkey("key"));
+    }
+
+    #[test]
+    fn test_open_and_rewrap() {
+        let mut key = VaultKey::create(password_key(b"password")).unwrap();
+        let subkey = key.master.subkey("servers");
+
+        let opened = VaultKey::open(key.slots.clone(), b"password").unwrap();
+        assert_eq!(&*opened.master.subkey("servers"), &*subkey);
+        assert!(matches!(
+            VaultKey::open(key.slots.clone(), b"other"),
+            Err(SecureSshError::InvalidPassword)
+        ));
+
+        // A new password wraps the same master key
+        key.rewrap(password_key(b"new password")).unwrap();
+        assert!(VaultKey::open(key.slots.clone(), b"password").is_err());
+        let reopened = VaultKey::open(key.slots, b"new password").unwrap();
+        assert_eq!(&*reopened.master.subkey("servers"), &*subkey);
+    }
+
+    #[test]
+    fn test_slots_are_independent() {
+        let mut key = VaultKey::create(password_key(b"first")).unwrap();
+        key.add_slot(&password_key(b"second")).unwrap();
+        key.add_slot(&password_key(b"third")).unwrap();
+
+        let mut second = VaultKey::open(key.slots.clone(), b"second").unwrap();
+        assert_eq!(second.slot, 1);
+        assert_eq!(second.master.as_bytes(), key.master.as_bytes());
+
+        // Changing one password leaves the others
+        second.rewrap(password_key(b"second, changed")).unwrap();
+        assert!(VaultKey::open(second.slots.clone(), b"first").is_ok());
+        assert!(VaultKey::open(second.slots.clone(), b"second").is_err());
+        assert_eq!(VaultKey::open(second.slots.clone(), b"third").unwrap().slot, 2);
+
+        // Removing a slot revokes its password and renumbers the rest
+        second.remove_slot(0);
+        assert_eq!(second.slot, 0);
+        assert!(matches!(
+            VaultKey::open(second.slots.clone(), b"first"),
+            Err(SecureSshError::InvalidPassword)
+        ));
+        assert_eq!(VaultKey::open(second.slots, b"third").unwrap().slot, 1);
+    }
+}
@@ -3,23 +3,27 @@
 //! This module provides:
 //! - Argon2id for password-based key derivation
 //! - ChaCha20-Poly1305 for authenticated encryption
+//! - A random vault master key with HKDF subkeys, wrapped in password key slots
 //! - Ed25519 for SSH key generation
 //! - Secure memory handling with automatic zeroing
 
 mod argon;
 mod chacha;
+mod header;
 mod keys;
+mod master;
 mod secure_bytes;
+mod slot;
 
-pub use argon::{derive_key, DerivedKey, SALT_LEN};
-pub use chacha::{decrypt, encrypt, NONCE_LEN};
+pub use argon::{derive_key, key_check, DerivedKey, KdfParams, KEY_CHECK_LEN, SALT_LEN};
+#[cfg(test)]
+pub(crate) use argon::{test_password_key, test_v1_password_key};
 #[allow(unused_imports)]
-pub use keys::{generate_keypair, KeyPair};
+pub use chacha::{decrypt, encrypt, NONCE_LEN, TAG_LEN};
+pub use chacha::{decrypt_with_aad, encrypt_with_aad, generate_nonce};
+pub use header::{FileKey, Header, FORMAT_VERSION};
+#[allow(unused_imports)]
+pub use keys::{fingerprint, generate_keypair, KeyPair};
+pub use master::{MasterKey, VaultKey, MASTER_KEY_LEN};
 pub use secure_bytes::SecureBytes;
-
-/// Current version of the encrypted file format
-pub const FORMAT_VERSION: u32 = 1;
-
-/// File header structure:
-/// [4 bytes: version][32 bytes: salt][12 bytes: nonce][N bytes: ciphertext][16 bytes: tag]
-pub const HEADER_LEN: usize = 4 + SALT_LEN + NONCE_LEN;
+pub use slot::{KeySlot, MAX_KEY_SLOTS};
@@ -9,7 +9,7 @@ use std::ops::{Deref, DerefMut};
 use zeroize::Zeroize;
 
 /// A secure container for sensitive bytes that automatically zeroes on drop
-#[derive(Zeroize)]
+#[derive(Default, Zeroize)]
 #[zeroize(drop)]
 pub struct SecureBytes(Vec<u8>);
 
@@ -95,12 +95,6 @@ impl From<&[u8]> for SecureBytes {
     }
 }
 
-impl Default for SecureBytes {
-    fn default() -> Self {
-        Self(Vec::new())
-    }
-}
-
 // Prevent accidental debug printing of secrets
 impl std::fmt::Debug for SecureBytes {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -118,8 +112,6 @@ mod tests {
     #[test]
     fn test_secure_bytes_zeroed_on_drop() {
         let data = vec![0xDE, 0xAD, 0xBE, 0xEF];
-        let ptr = data.as_ptr();
-        let len = data.len();
 
         {
             let secure = SecureBytes::new(data);
@@ -0,0 +1,137 @@
+//! Key slots: the vault master key wrapped under a password
+//!
+//! A slot stores the master key encrypted with ChaCha20-Poly1305 under a
+//! key derived from a password, together with what is needed to derive
+//! that key again:
+//! [1 byte: KDF id][4 bytes: memory KiB][4 bytes: iterations][4 bytes: parallelism]
+//! [32 bytes: salt][16 bytes: key check][12 bytes: nonce][48 bytes: wrapped master key]
+//!
+//! Everything before the wrapped key is authenticated as associated data.
+//!
+//! A vault holds up to [`MAX_KEY_SLOTS`] slots, one per password; any of them
+//! unlocks it, and removing a slot revokes its password.
+
+use super::header::{read_kdf, write_kdf, Reader};
+use super::{
+    decrypt_with_aad, encrypt_with_aad, generate_nonce, DerivedKey, KdfParams, MasterKey,
+    KEY_CHECK_LEN, NONCE_LEN, SALT_LEN, TAG_LEN,
+};
+use crate::error::{Result, SecureSshError};
+
+/// Most slots a vault holds, as in LUKS1
+pub const MAX_KEY_SLOTS: usize = 8;
+
+/// Length of the wrapped master key with its tag
+const WRAPPED_LEN: usize = super::MASTER_KEY_LEN + TAG_LEN;
+
+/// The master key wrapped under one password
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySlot {
+    /// Argon2id parameters of the password key
+    pub kdf: KdfParams,
+    pub salt: [u8; SALT_LEN],
+    /// Key check value of the password key, so a wrong password is told apart
+    pub key_check: [u8; KEY_CHECK_LEN],
+    nonce: [u8; NONCE_LEN],
+    wrapped: [u8; WRAPPED_LEN],
+}
+
+impl KeySlot {
+    /// Wrap `master` under a key derived from a password
+    pub fn wrap(password_key: &DerivedKey, master: &MasterKey) -> Result<Self> {
+        let mut slot = Self {
+            kdf: password_key.params,
+            salt: password_key.salt,
+            key_check: password_key.check_value(),
+            nonce: generate_nonce(),
+            wrapped: [0u8; WRAPPED_LEN],
+        };
+        let wrapped = encrypt_with_aad(&password_key.key, &slot.nonce, master.as_bytes(), &slot.aad())?;
+        slot.wrapped.copy_from_slice(&wrapped);
+        Ok(slot)
+    }
+
+    /// Unwrap the master key with a key derived with this slot's salt and parameters
+    ///
+    /// A key for another password fails with [`SecureSshError::InvalidPassword`];
+    /// a damaged slot with [`SecureSshError::DecryptionFailed`].
+    pub fn open(&self, password_key: &DerivedKey) -> Result<MasterKey> {
+        if password_key.check_value() != self.key_check {
+            return Err(SecureSshError::InvalidPassword);
+        }
+        let master = decrypt_with_aad(&password_key.key, &self.nonce, &self.wrapped, &self.aad())?;
+        MasterKey::from_bytes(master)
+    }
+
+    pub(super) fn encode_into(&self, data: &mut Vec<u8>) {
+        data.extend_from_slice(&self.aad());
+        data.extend_from_slice(&self.wrapped);
+    }
+
+    pub(super) fn read(reader: &mut Reader) -> std::result::Result<Self, String> {
+        let kdf = read_kdf(reader)?;
+        let mut slot = Self {
+            kdf,
+            salt: [0u8; SALT_LEN],
+            key_check: [0u8; KEY_CHECK_LEN],
+            nonce: [0u8; NONCE_LEN],
+            wrapped: [0u8; WRAPPED_LEN],
+        };
+        slot.salt.copy_from_slice(reader.take(SALT_LEN).ok_or(t!(KEY_SLOT_TRUNCATED))?);
+        slot.key_check.copy_from_slice(reader.take(KEY_CHECK_LEN).ok_or(t!(KEY_SLOT_TRUNCATED))?);
+        slot.nonce.copy_from_slice(reader.take(NONCE_LEN).ok_or(t!(KEY_SLOT_TRUNCATED))?);
+        slot.wrapped.copy_from_slice(reader.take(WRAPPED_LEN).ok_or(t!(KEY_SLOT_TRUNCATED))?);
+        Ok(slot)
+    }
+
+    /// Slot fields before the wrapped key
+    fn aad(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        write_kdf(&mut data, &self.kdf);
+        data.extend_from_slice(&self.salt);
+        data.extend_from_slice(&self.key_check);
+        data.extend_from_slice(&self.nonce);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::test_password_key as password_key;
+
+    #[test]
+    fn test_wrap_and_open() {
+        let master = MasterKey::generate();
+        let slot = KeySlot::wrap(&password_key(b"password"), &master).unwrap();
+
+        let mut data = Vec::new();
+        slot.encode_into(&mut data);
+        let read = KeySlot::read(&mut Reader(&data)).unwrap();
+        assert_eq!(read, slot);
+
+        let opened = read.open(&password_key(b"password")).unwrap();
+        assert_eq!(opened.as_bytes(), master.as_bytes());
+    }
+
+    #[test]
+    fn test_wrong_password_and_damage() {
+        let slot = KeySlot::wrap(&password_key(b"password"), &MasterKey::generate()).unwrap();
+        assert!(matches!(
+            slot.open(&password_key(b"other")),
+            Err(SecureSshError::InvalidPassword)
+        ));
+
+        let mut damaged = slot.clone();
+        damaged.wrapped[0] ^= 1;
+        assert!(matches!(
+            damaged.open(&password_key(b"password")),
+            Err(SecureSshError::DecryptionFailed)
+        ));
+
+        // The parameters are authenticated too
+        let mut relabelled = slot;
+        relabelled.nonce[0] ^= 1;
+        assert!(relabelled.open(&password_key(b"password")).is_err());
+    }
+}
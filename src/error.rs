@@ -1,64 +1,201 @@
+use std::fmt;
+
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, SecureSshError>;
 
+/// Errors of secure-ssh commands
+///
+/// Messages are localised (see [`crate::i18n`]); `code()` and `exit_code()`
+/// stay the same in every language.
 #[derive(Debug, Error)]
 #[allow(dead_code)]
 pub enum SecureSshError {
-    #[error("Не инициализировано. Сначала выполните 'secure-ssh init'.")]
     NotInitialized,
-
-    #[error("Уже инициализировано. Используйте --force для повторной инициализации (это удалит существующие ключи).")]
-    AlreadyInitialized,
-
-    #[error("Неверный пароль")]
     InvalidPassword,
-
-    #[error("Пароль слишком короткий (минимум 12 символов)")]
     PasswordTooShort,
-
-    #[error("Пароли не совпадают")]
     PasswordMismatch,
-
-    #[error("Ошибка расшифровки: данные повреждены или пароль неверный")]
     DecryptionFailed,
-
-    #[error("Ошибка шифрования: {0}")]
+    /// The password is right but the file fails to authenticate
+    VaultCorrupted(String),
     EncryptionFailed(String),
-
-    #[error("Сервер '{0}' не найден")]
     ServerNotFound(String),
-
-    #[error("Сервер '{0}' уже существует")]
     ServerAlreadyExists(String),
-
-    #[error("Серверы не настроены. Сначала выполните 'secure-ssh server add'.")]
     NoServersConfigured,
-
-    #[error("Ошибка SSH-подключения: {0}")]
+    ProfileNotFound(String),
     SshConnectionFailed(String),
-
-    #[error("Ошибка SSH-аутентификации")]
+    ConnectionTimeout(String),
+    ConnectionRefused(String),
     SshAuthFailed,
-
-    #[error("USB-накопитель извлечён - соединение прервано")]
+    RemoteCommandFailed { failed: usize, total: usize },
+    ForwardFailed(String),
     UsbRemoved,
-
-    #[error("Операция отменена пользователем")]
+    VaultLocked,
+    MigrationNeeded,
     Cancelled,
-
-    #[error("Неверная конфигурация: {0}")]
     InvalidConfig(String),
-
-    #[error("Ошибка генерации ключа: {0}")]
     KeyGenerationFailed(String),
-
-    #[error("Ошибка ввода-вывода: {0}")]
     Io(#[from] std::io::Error),
-
-    #[error("Ошибка JSON: {0}")]
     Json(#[from] serde_json::Error),
-
-    #[error("{0}")]
     Other(String),
 }
+
+impl fmt::Display for SecureSshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::NotInitialized => t!(ERR_NOT_INITIALIZED).to_string(),
+            Self::InvalidPassword => t!(ERR_INVALID_PASSWORD).to_string(),
+            Self::PasswordTooShort => t!(ERR_PASSWORD_TOO_SHORT).to_string(),
+            Self::PasswordMismatch => t!(ERR_PASSWORD_MISMATCH).to_string(),
+            Self::DecryptionFailed => t!(ERR_DECRYPTION_FAILED).to_string(),
+            Self::VaultCorrupted(file) => t!(ERR_VAULT_CORRUPTED, file),
+            Self::EncryptionFailed(e) => t!(ERR_ENCRYPTION_FAILED, e),
+            Self::ServerNotFound(name) => t!(ERR_SERVER_NOT_FOUND, name),
+            Self::ServerAlreadyExists(name) => t!(ERR_SERVER_EXISTS, name),
+            Self::NoServersConfigured => t!(ERR_NO_SERVERS).to_string(),
+            Self::ProfileNotFound(name) => t!(ERR_PROFILE_NOT_FOUND, name),
+            Self::SshConnectionFailed(e) => t!(ERR_SSH_CONNECTION, e),
+            Self::ConnectionTimeout(host) => t!(ERR_CONNECTION_TIMEOUT, host),
+            Self::ConnectionRefused(host) => t!(ERR_CONNECTION_REFUSED, host),
+            Self::SshAuthFailed => t!(ERR_AUTH_FAILED).to_string(),
+            Self::RemoteCommandFailed { failed, total } => t!(ERR_REMOTE_COMMAND, failed, total),
+            Self::ForwardFailed(e) => t!(ERR_FORWARD, e),
+            Self::UsbRemoved => t!(ERR_USB_REMOVED).to_string(),
+            Self::VaultLocked => t!(ERR_VAULT_LOCKED).to_string(),
+            Self::MigrationNeeded => t!(ERR_MIGRATION_NEEDED).to_string(),
+            Self::Cancelled => t!(ERR_CANCELLED).to_string(),
+            Self::InvalidConfig(e) => t!(ERR_INVALID_CONFIG, e),
+            Self::KeyGenerationFailed(e) => t!(ERR_KEY_GENERATION, e),
+            Self::Io(e) => t!(ERR_IO, e),
+            Self::Json(e) => t!(ERR_JSON, e),
+            Self::Other(message) => message.clone(),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl SecureSshError {
+    /// Stable machine-readable code for `--output json`
+    ///
+    /// Codes never change once released; messages may be reworded.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotInitialized => "not_initialized",
+            Self::InvalidPassword => "invalid_password",
+            Self::PasswordTooShort => "password_too_short",
+            Self::PasswordMismatch => "password_mismatch",
+            Self::DecryptionFailed => "decryption_failed",
+            Self::VaultCorrupted(_) => "vault_corrupted",
+            Self::EncryptionFailed(_) => "encryption_failed",
+            Self::ServerNotFound(_) => "server_not_found",
+            Self::ServerAlreadyExists(_) => "server_already_exists",
+            Self::NoServersConfigured => "no_servers_configured",
+            Self::ProfileNotFound(_) => "profile_not_found",
+            Self::SshConnectionFailed(_) => "ssh_connection_failed",
+            Self::ConnectionTimeout(_) => "connection_timeout",
+            Self::ConnectionRefused(_) => "connection_refused",
+            Self::SshAuthFailed => "ssh_auth_failed",
+            Self::RemoteCommandFailed { .. } => "remote_command_failed",
+            Self::ForwardFailed(_) => "forward_failed",
+            Self::UsbRemoved => "usb_removed",
+            Self::VaultLocked => "vault_locked",
+            Self::MigrationNeeded => "migration_needed",
+            Self::Cancelled => "cancelled",
+            Self::InvalidConfig(_) => "invalid_config",
+            Self::KeyGenerationFailed(_) => "key_generation_failed",
+            Self::Io(_) => "io",
+            Self::Json(_) => "json",
+            Self::Other(_) => "other",
+        }
+    }
+}
+
+/// Process exit codes, one per error class (see README)
+///
+/// 2 matches clap's exit code for invalid command-line arguments.
+pub mod exit_code {
+    pub const OTHER: u8 = 1;
+    pub const INVALID_INPUT: u8 = 2;
+    pub const NOT_INITIALIZED: u8 = 3;
+    pub const WRONG_PASSWORD: u8 = 4;
+    pub const SERVER_NOT_FOUND: u8 = 5;
+    pub const CONNECTION_FAILED: u8 = 6;
+    pub const CONNECTION_TIMEOUT: u8 = 7;
+    pub const CONNECTION_REFUSED: u8 = 8;
+    pub const AUTH_FAILED: u8 = 9;
+    /// Reserved for a host key mismatch and never returned: host keys are
+    /// not verified yet. No other class may take it.
+    #[allow(dead_code)]
+    pub const HOST_KEY_MISMATCH: u8 = 10;
+    pub const FORWARD_FAILED: u8 = 11;
+    pub const USB_REMOVED: u8 = 12;
+    pub const REMOTE_COMMAND_FAILED: u8 = 13;
+    pub const IO: u8 = 14;
+    pub const VAULT_CORRUPTED: u8 = 15;
+    pub const MIGRATION_NEEDED: u8 = 16;
+    pub const CANCELLED: u8 = 130;
+}
+
+impl SecureSshError {
+    /// Exit code of the process when a command fails with this error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::InvalidConfig(_)
+            | Self::PasswordTooShort
+            | Self::PasswordMismatch
+            | Self::ServerAlreadyExists(_) => exit_code::INVALID_INPUT,
+            Self::NotInitialized => exit_code::NOT_INITIALIZED,
+            Self::InvalidPassword | Self::DecryptionFailed => exit_code::WRONG_PASSWORD,
+            Self::ServerNotFound(_)
+            | Self::NoServersConfigured
+            | Self::ProfileNotFound(_) => exit_code::SERVER_NOT_FOUND,
+            Self::SshConnectionFailed(_) => exit_code::CONNECTION_FAILED,
+            Self::ConnectionTimeout(_) => exit_code::CONNECTION_TIMEOUT,
+            Self::ConnectionRefused(_) => exit_code::CONNECTION_REFUSED,
+            Self::SshAuthFailed => exit_code::AUTH_FAILED,
+            Self::ForwardFailed(_) => exit_code::FORWARD_FAILED,
+            Self::UsbRemoved => exit_code::USB_REMOVED,
+            Self::RemoteCommandFailed { .. } => exit_code::REMOTE_COMMAND_FAILED,
+            Self::Io(_) => exit_code::IO,
+            Self::VaultCorrupted(_) => exit_code::VAULT_CORRUPTED,
+            Self::MigrationNeeded => exit_code::MIGRATION_NEEDED,
+            Self::Cancelled => exit_code::CANCELLED,
+            Self::EncryptionFailed(_)
+            | Self::KeyGenerationFailed(_)
+            | Self::VaultLocked
+            | Self::Json(_)
+            | Self::Other(_) => exit_code::OTHER,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_distinguish_classes() {
+        let usb = SecureSshError::UsbRemoved.exit_code();
+        let password = SecureSshError::InvalidPassword.exit_code();
+        let remote = SecureSshError::RemoteCommandFailed { failed: 1, total: 2 }.exit_code();
+        let timeout = SecureSshError::ConnectionTimeout("h:22".into()).exit_code();
+        let refused = SecureSshError::ConnectionRefused("h:22".into()).exit_code();
+        let corrupted = SecureSshError::VaultCorrupted("key.enc".into()).exit_code();
+        let migration = SecureSshError::MigrationNeeded.exit_code();
+
+        let codes = [usb, password, remote, timeout, refused, corrupted, migration];
+        for (i, a) in codes.iter().enumerate() {
+            assert!(codes[i + 1..].iter().all(|b| a != b));
+        }
+        assert!(codes.iter().all(|&c| c != 0 && c != exit_code::HOST_KEY_MISMATCH));
+        assert_eq!(SecureSshError::InvalidConfig(String::new()).exit_code(), 2);
+        assert_ne!(migration, exit_code::OTHER);
+    }
+
+    #[test]
+    fn test_duplicate_server_is_not_a_missing_one() {
+        let duplicate = SecureSshError::ServerAlreadyExists("web".into()).exit_code();
+        assert_ne!(duplicate, exit_code::SERVER_NOT_FOUND);
+        assert_eq!(duplicate, exit_code::INVALID_INPUT);
+    }
+}
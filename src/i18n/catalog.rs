@@ -0,0 +1,573 @@
+//! Message catalog: every user-facing string in English and Russian
+//!
+//! Names are grouped by the module that uses them. `{}` placeholders are
+//! filled in order, so both translations must have the same number of them.
+
+use super::Msg;
+
+messages! {
+    // Shared
+    LABEL_ERROR: "Error:" | "Ошибка:";
+    LABEL_WARNING: "Warning:" | "Внимание:";
+    LABEL_SUCCESS: "Success:" | "Успех:";
+    DONE: "done" | "готово";
+    FAILED: "failed" | "ошибка";
+    CANCELLED: "Cancelled." | "Отменено.";
+    CONFIRM_HINT: "[y/N]" | "[д/Н]";
+    OUTPUT_JSON_UNSUPPORTED: "'{}' is interactive and does not support --output json"
+        | "команда '{}' интерактивная и не поддерживает --output json";
+    OUTPUT_JSON_NEEDS_YES: "--output json cannot ask for confirmation, pass --yes"
+        | "с --output json подтверждение не запрашивается, укажите --yes";
+    RUNTIME_FAILED: "Failed to create async runtime: {}" | "Не удалось создать async runtime: {}";
+    HOME_DIR_UNKNOWN: "could not determine the home directory" | "не удалось определить домашний каталог";
+    EXE_DIR_UNKNOWN: "Could not determine the executable's directory" | "Не удалось определить директорию исполняемого файла";
+    SERVER_CONTEXT: "server '{}': {}" | "сервер '{}': {}";
+    VIA: " (via {})" | " (через {})";
+    FORWARD_COUNT: ", forwards: {}" | ", пробросов: {}";
+    DECRYPTING_KEY: "Decrypting SSH key... " | "Расшифровка SSH-ключа... ";
+    FORWARDS: "Forwards:" | "Пробросы:";
+    FORWARDS_FOR: "Forwards for {}:" | "Пробросы {}:";
+
+    // Server fields (server show, picker preview)
+    FIELD_HOST: "Host:" | "Хост:";
+    FIELD_PORT: "Port:" | "Порт:";
+    FIELD_USER: "User:" | "Пользователь:";
+    FIELD_CONNECTION: "Connection:" | "Подключение:";
+    FIELD_DESCRIPTION: "Description:" | "Описание:";
+    FIELD_GROUP: "Group:" | "Группа:";
+    FIELD_TAGS: "Tags:" | "Теги:";
+    FIELD_VIA: "Via:" | "Через:";
+
+    // Errors
+    ERR_NOT_INITIALIZED: "Not initialized. Run 'secure-ssh init' first."
+        | "Не инициализировано. Сначала выполните 'secure-ssh init'.";
+    ERR_INVALID_PASSWORD: "Wrong password" | "Неверный пароль";
+    ERR_PASSWORD_TOO_SHORT: "Password is too short (at least 12 characters)"
+        | "Пароль слишком короткий (минимум 12 символов)";
+    ERR_PASSWORD_MISMATCH: "Passwords do not match" | "Пароли не совпадают";
+    ERR_DECRYPTION_FAILED: "Decryption failed: the data is corrupted or the password is wrong"
+        | "Ошибка расшифровки: данные повреждены или пароль неверный";
+    ERR_VAULT_CORRUPTED: "{} is damaged: the password is right but the data does not authenticate; try `secure-ssh repair`"
+        | "{} повреждён: пароль верный, но данные не проходят проверку; попробуйте `secure-ssh repair`";
+    ERR_ENCRYPTION_FAILED: "Encryption failed: {}" | "Ошибка шифрования: {}";
+    ERR_SERVER_NOT_FOUND: "Server '{}' not found" | "Сервер '{}' не найден";
+    ERR_SERVER_EXISTS: "Server '{}' already exists" | "Сервер '{}' уже существует";
+    ERR_NO_SERVERS: "No servers configured. Run 'secure-ssh server add' first."
+        | "Серверы не настроены. Сначала выполните 'secure-ssh server add'.";
+    ERR_PROFILE_NOT_FOUND: "Profile '{}' not found" | "Профиль '{}' не найден";
+    ERR_SSH_CONNECTION: "SSH connection failed: {}" | "Ошибка SSH-подключения: {}";
+    ERR_CONNECTION_TIMEOUT: "Timed out connecting to {}" | "Истекло время ожидания подключения к {}";
+    ERR_CONNECTION_REFUSED: "{}: connection refused" | "{}: в подключении отказано";
+    ERR_AUTH_FAILED: "SSH authentication failed" | "Ошибка SSH-аутентификации";
+    ERR_REMOTE_COMMAND: "Command failed on {} of {} servers" | "Команда завершилась неудачно на {} из {} серверов";
+    ERR_FORWARD: "Forwarding failed: {}" | "Ошибка проброса: {}";
+    ERR_USB_REMOVED: "USB drive removed - aborted" | "USB-накопитель извлечён - работа прервана";
+    ERR_VAULT_LOCKED: "Vault is locked - unlock it with the master password first"
+        | "Хранилище заблокировано - сначала разблокируйте его мастер-паролем";
+    ERR_MIGRATION_NEEDED: "Vault files use an old format. Run 'secure-ssh migrate' to upgrade them."
+        | "Файлы хранилища в старом формате. Обновите их командой 'secure-ssh migrate'.";
+    ERR_CANCELLED: "Cancelled by user" | "Операция отменена пользователем";
+    ERR_INVALID_CONFIG: "Invalid configuration: {}" | "Неверная конфигурация: {}";
+    ERR_KEY_GENERATION: "Key generation failed: {}" | "Ошибка генерации ключа: {}";
+    ERR_IO: "I/O error: {}" | "Ошибка ввода-вывода: {}";
+    ERR_JSON: "JSON error: {}" | "Ошибка JSON: {}";
+
+    // clap help: layout
+    HELP_TEMPLATE: "{before-help}{about-with-newline}\nUsage: {usage}\n\n{all-args}{after-help}"
+        | "{before-help}{about-with-newline}\nИспользование: {usage}\n\n{all-args}{after-help}";
+    HELP_COMMANDS: "Commands" | "Команды";
+    HELP_OPTIONS: "Options" | "Параметры";
+    HELP_ARGUMENTS: "Arguments" | "Аргументы";
+    HELP_FLAG: "Print help" | "Показать справку";
+    HELP_VERSION: "Print version" | "Показать версию";
+    HELP_SUBCOMMAND: "Print this message or the help of the given subcommand(s)"
+        | "Показать эту справку или справку по указанной команде";
+    VALUE_PATH: "PATH" | "ПУТЬ";
+    VALUE_SERVERS: "SERVERS" | "СЕРВЕРЫ";
+    VALUE_SECONDS: "SECS" | "СЕК";
+    VALUE_TEXT: "TEXT" | "ТЕКСТ";
+    VALUE_HOSTS: "HOSTS" | "ХОСТЫ";
+    VALUE_NAME: "NAME" | "ИМЯ";
+    VALUE_DURATION: "TIME" | "ВРЕМЯ";
+    VALUE_KDF: "PARAMS" | "ПАРАМЕТРЫ";
+
+    // clap help: commands and arguments
+    HELP_ABOUT: "Secure SSH client with hardware-token-style protection"
+        | "Безопасный SSH-клиент с защитой по типу аппаратного токена";
+    HELP_OUTPUT: "Output format: json for scripts (results of non-interactive commands and errors)"
+        | "Формат вывода: json - для скриптов (результаты неинтерактивных команд и ошибки)";
+    HELP_OUTPUT_TEXT: "Human-readable text" | "Текст для человека";
+    HELP_OUTPUT_JSON: "JSON for scripts; errors go to stderr as a JSON object"
+        | "JSON для скриптов; ошибки - объектом JSON в stderr";
+    HELP_LANG: "Interface language (default: from LC_ALL, LC_MESSAGES or LANG)"
+        | "Язык интерфейса (по умолчанию - из LC_ALL, LC_MESSAGES или LANG)";
+    HELP_DATA_DIR: "Data directory (default: SECURE_SSH_DATA_DIR or 'data' next to the executable)"
+        | "Директория данных (по умолчанию - SECURE_SSH_DATA_DIR или 'data' рядом с исполняемым файлом)";
+    HELP_PROFILE_FLAG: "Profile with its own password, key and servers (default: default)"
+        | "Профиль со своим паролем, ключом и серверами (по умолчанию - default)";
+    HELP_INIT: "Initialize with a new master password and SSH key"
+        | "Инициализация с новым мастер-паролем и SSH-ключом";
+    HELP_KDF_TARGET: "Benchmark this machine and pick Argon2id parameters for the given unlock time, e.g. 1s or 500ms"
+        | "Замерить эту машину и подобрать параметры Argon2id под заданное время разблокировки, например 1s или 500ms";
+    HELP_INIT_CONTAINER: "Keep the whole vault in one padded encrypted file (vault.enc), public key included"
+        | "Хранить всё хранилище в одном дополненном зашифрованном файле (vault.enc), включая публичный ключ";
+    HELP_PUBKEY: "Show the public SSH key" | "Показать публичный SSH-ключ";
+    HELP_SERVER: "Manage server configurations" | "Управление конфигурациями серверов";
+    HELP_CONNECT: "Connect to a configured server" | "Подключиться к настроенному серверу";
+    HELP_CONNECT_NAMES: "Server names or @tag/@group; several servers open sessions switched with Ctrl+] (optional if only one is configured)"
+        | "Имена серверов или @тег/@группа; несколько серверов открывают сессии с переключением по Ctrl+] (необязательно, если настроен только один)";
+    HELP_BROADCAST: "Send input to all sessions at once, shown as tiles"
+        | "Рассылать ввод во все сессии сразу, показывая их плитками";
+    HELP_TUNNEL: "Set up port forwards only, without a shell (like ssh -N)"
+        | "Установить только пробросы портов, без shell (как ssh -N)";
+    HELP_TUNNEL_NAME: "Server name (optional if only one is configured)"
+        | "Имя сервера (необязательно, если настроен только один)";
+    HELP_RUN: "Run a command on several servers in parallel"
+        | "Выполнить команду на нескольких серверах параллельно";
+    HELP_RUN_ON: "Servers: names, @tag/@group separated by commas, or all"
+        | "Серверы: имена, @тег/@группа через запятую или all";
+    HELP_PARALLEL: "Maximum number of simultaneous connections" | "Максимум одновременных подключений";
+    HELP_CONNECT_TIMEOUT: "Connection timeout in seconds" | "Таймаут подключения в секундах";
+    HELP_RUN_COMMAND: "Command to run" | "Команда для выполнения";
+    HELP_AGENT: "Start an SSH agent with the drive's key for ssh, scp, rsync and Ansible"
+        | "Запустить SSH-агент с ключом накопителя для ssh, scp, rsync и Ansible";
+    HELP_AGENT_SOCKET: "Socket path (default: in $XDG_RUNTIME_DIR/secure-ssh)"
+        | "Путь к сокету (по умолчанию в $XDG_RUNTIME_DIR/secure-ssh)";
+    HELP_CHANGE_PASS: "Change the master password" | "Сменить мастер-пароль";
+    HELP_KEYSLOT: "Manage the passwords that open the vault (key slots)"
+        | "Управление паролями, которые открывают хранилище (слоты ключа)";
+    HELP_KEYSLOT_LIST: "List key slots" | "Показать слоты ключа";
+    HELP_KEYSLOT_ADD: "Add a key slot with another password" | "Добавить слот ключа с ещё одним паролем";
+    HELP_KEYSLOT_REMOVE: "Remove a key slot, revoking its password" | "Удалить слот ключа, отозвав его пароль";
+    HELP_KEYSLOT_NUMBER: "Slot number from 'keyslot list'" | "Номер слота из 'keyslot list'";
+    HELP_REKEY: "Re-wrap the vault master key with new Argon2id parameters (same password)"
+        | "Перешифровать мастер-ключ хранилища с новыми параметрами Argon2id (пароль тот же)";
+    HELP_REKEY_KDF: "Argon2id parameters, e.g. m=256M,t=4,p=4 (memory, iterations, lanes)"
+        | "Параметры Argon2id, например m=256M,t=4,p=4 (память, итерации, потоки)";
+    HELP_MIGRATE: "Upgrade vault files to the current format, keeping a backup"
+        | "Обновить файлы хранилища до текущего формата с резервной копией";
+    HELP_MIGRATE_CHECK: "Only report which files use an old format (fails if any do)"
+        | "Только показать файлы в старом формате (завершается с ошибкой, если они есть)";
+    HELP_MIGRATE_CONTAINER: "Move key.enc, key.pub and servers.enc into a single container file"
+        | "Перенести key.enc, key.pub и servers.enc в один файл-контейнер";
+    HELP_REPAIR: "Check the vault files and restore damaged ones from earlier copies"
+        | "Проверить файлы хранилища и восстановить повреждённые из прежних копий";
+    HELP_PROFILE: "Manage profiles" | "Управление профилями";
+    HELP_PROFILE_LIST: "List profiles" | "Показать список профилей";
+    HELP_PROFILE_CREATE: "Create a profile with a new master password and SSH key"
+        | "Создать профиль с новым мастер-паролем и SSH-ключом";
+    HELP_PROFILE_DELETE: "Delete a profile with its key and servers"
+        | "Удалить профиль вместе с ключом и серверами";
+    HELP_PROFILE_NAME: "Profile name" | "Имя профиля";
+    HELP_PROFILE_YES: "Do not ask for confirmation" | "Не спрашивать подтверждение";
+    HELP_SERVER_ADD: "Add a new server (interactive without flags)"
+        | "Добавить новый сервер (без флагов - интерактивно)";
+    HELP_SERVER_NAME: "Server name" | "Имя сервера";
+    HELP_SERVER_EDIT: "Change server fields" | "Изменить поля сервера";
+    HELP_SERVER_RENAME: "Rename a server" | "Переименовать сервер";
+    HELP_RENAME_OLD: "Current name" | "Текущее имя";
+    HELP_RENAME_NEW: "New name" | "Новое имя";
+    HELP_SERVER_SHOW: "Show server settings" | "Показать настройки сервера";
+    HELP_SERVER_LIST: "List servers by group" | "Показать список серверов по группам";
+    HELP_LIST_TAG: "Show only servers with this tag" | "Показать только серверы с этим тегом";
+    HELP_SERVER_REMOVE: "Remove a server" | "Удалить сервер";
+    HELP_REMOVE_NAME: "Name of the server to remove" | "Имя сервера для удаления";
+    HELP_SERVER_IMPORT: "Import servers from the OpenSSH config" | "Импортировать серверы из конфигурации OpenSSH";
+    HELP_IMPORT_FROM: "Config file (default: ~/.ssh/config)" | "Файл конфигурации (по умолчанию ~/.ssh/config)";
+    HELP_IMPORT_DRY_RUN: "Only show what would be imported" | "Только показать, что будет импортировано";
+    HELP_ON_CONFLICT: "What to do if a server with the same name exists"
+        | "Что делать, если сервер с таким именем уже есть";
+    HELP_ON_CONFLICT_SKIP: "Keep the existing server" | "Оставить существующий сервер";
+    HELP_ON_CONFLICT_OVERWRITE: "Replace the existing server" | "Заменить существующий сервер";
+    HELP_ON_CONFLICT_RENAME: "Import under a new name (name-2, name-3, ...)"
+        | "Импортировать под новым именем (имя-2, имя-3, ...)";
+    HELP_SERVER_EXPORT: "Export servers in plain text for other tools"
+        | "Экспортировать серверы в открытом виде для других инструментов";
+    HELP_EXPORT_FORMAT: "Output format" | "Формат вывода";
+    HELP_EXPORT_SSH_CONFIG: "OpenSSH client config; the key comes from `secure-ssh agent`"
+        | "Конфигурация клиента OpenSSH; ключ берётся у `secure-ssh agent`";
+    HELP_EXPORT_ANSIBLE_INI: "Ansible INI inventory (groups and tags become Ansible groups)"
+        | "Инвентарь Ansible в формате INI (группы и теги - группы Ansible)";
+    HELP_EXPORT_JSON: "Server list as JSON" | "Список серверов в JSON";
+    HELP_EXPORT_FILE: "Write to a file (mode 0600) instead of stdout"
+        | "Записать в файл (права 0600) вместо stdout";
+    HELP_EXPORT_AGENT_SOCKET: "Agent socket for ssh-config and ansible-ini (default: the 'secure-ssh agent' socket)"
+        | "Сокет агента для ssh-config и ansible-ini (по умолчанию - сокет 'secure-ssh agent')";
+    HELP_EXPORT_YES: "Do not ask for confirmation" | "Не спрашивать подтверждение";
+    HELP_SERVER_FORWARD: "Manage a server's persistent forwards" | "Управление постоянными пробросами сервера";
+    HELP_FORWARD_ADD: "Add forwards (set up on every connection)"
+        | "Добавить пробросы (устанавливаются при каждом подключении)";
+    HELP_FORWARD_LIST: "Show a server's forwards" | "Показать пробросы сервера";
+    HELP_FORWARD_REMOVE: "Remove a forward by its number in the list" | "Удалить проброс по номеру из списка";
+    HELP_FORWARD_NUMBER: "Forward number (see 'server forward list')" | "Номер проброса (см. 'server forward list')";
+    HELP_LOCAL_FORWARD: "Local forward: [addr:]port:host:port, [addr:]port:/remote/socket or /local/socket:/remote/socket (may be repeated)"
+        | "Локальный проброс: [адрес:]порт:хост:порт, [адрес:]порт:/удалённый/сокет или /локальный/сокет:/удалённый/сокет (можно указать несколько раз)";
+    HELP_REMOTE_FORWARD: "Remote forward: [addr:]port:host:port or [addr:]port:/local/socket"
+        | "Удалённый проброс: [адрес:]порт:хост:порт или [адрес:]порт:/локальный/сокет";
+    HELP_DYNAMIC_FORWARD: "Dynamic forward (SOCKS5 proxy): [addr:]port"
+        | "Динамический проброс (SOCKS5-прокси): [адрес:]порт";
+    HELP_FIELD_HOST: "Host or IP address (IPv6 with or without brackets)"
+        | "Хост или IP-адрес (IPv6 - со скобками или без)";
+    HELP_FIELD_PORT: "SSH port (1-65535)" | "SSH-порт (1-65535)";
+    HELP_FIELD_USER: "Username" | "Имя пользователя";
+    HELP_FIELD_DESC: "Description" | "Описание";
+    HELP_FIELD_GROUP: "Group, e.g. prod/eu (empty string - no group)"
+        | "Группа, например prod/eu (пустая строка - без группы)";
+    HELP_FIELD_TAGS: "Comma-separated tags; replace the previous ones (empty string - no tags)"
+        | "Теги через запятую; заменяют прежние (пустая строка - без тегов)";
+    HELP_FIELD_JUMP: "Comma-separated jump hosts: server names or [user@]host[:port] (empty string - connect directly)"
+        | "Jump-хосты через запятую: имена серверов или [user@]host[:port] (пустая строка - подключаться напрямую)";
+
+    // Passwords and prompts
+    PROMPT_PASSWORD: "Enter master password: " | "Введите мастер-пароль: ";
+    PROMPT_PASSWORD_CONFIRM: "Confirm master password: " | "Подтвердите мастер-пароль: ";
+    NEW_PASSWORD_TITLE: "Creating the master password" | "Создание мастер-пароля";
+    NEW_PASSWORD_INTRO: "This password encrypts your SSH key. Choose a strong one."
+        | "Этот пароль шифрует ваш SSH-ключ. Выберите надёжный пароль.";
+    NEW_PASSWORD_MIN_LEN: "Minimum length: {} characters" | "Минимальная длина: {} символов";
+    PROMPT_NAME: "Server name: " | "Имя сервера: ";
+    PROMPT_NAME_DEFAULT: "Server name [{}]: " | "Имя сервера [{}]: ";
+    PROMPT_HOST: "Host/IP: " | "Хост/IP: ";
+    PROMPT_HOST_DEFAULT: "Host/IP [{}]: " | "Хост/IP [{}]: ";
+    PROMPT_PORT: "Port [22]: " | "Порт [22]: ";
+    PROMPT_USER: "Username: " | "Имя пользователя: ";
+    PROMPT_USER_DEFAULT: "Username [{}]: " | "Имя пользователя [{}]: ";
+    PROMPT_DESCRIPTION: "Description (optional): " | "Описание (опционально): ";
+    PROMPT_GROUP: "Group, e.g. prod/eu (optional): " | "Группа, например prod/eu (опционально): ";
+    PROMPT_TAGS: "Comma-separated tags (optional): " | "Теги через запятую (опционально): ";
+    INVALID_PORT: "Invalid port number" | "Неверный номер порта";
+    INVALID_CHOICE: "Invalid choice" | "Неверный выбор";
+    INVALID_FORWARD: "invalid forward '{}': {}" | "неверный проброс '{}': {}";
+    INVALID_KDF: "invalid KDF parameters '{}': {}" | "неверные параметры KDF '{}': {}";
+    INVALID_KDF_TARGET: "invalid --kdf-target '{}': {}" | "неверный --kdf-target '{}': {}";
+    KDF_CALIBRATING: "Calibrating Argon2id for {} per unlock... "
+        | "Подбор параметров Argon2id под {} на разблокировку... ";
+
+    // init
+    INIT_TITLE: "=== Secure SSH initialization ===" | "=== Инициализация Secure SSH ===";
+    INIT_ALREADY: "secure-ssh is already initialized." | "secure-ssh уже инициализирован.";
+    INIT_NEW_KEY: "Reinitializing creates a new SSH key." | "Реинициализация создаст новый SSH-ключ.";
+    INIT_UPDATE_KEYS: "You will need to update authorized_keys on every server."
+        | "Вам нужно будет обновить authorized_keys на всех серверах.";
+    INIT_CONFIRM: "Reinitialize?" | "Выполнить реинициализацию?";
+    INIT_DERIVING: "Deriving the encryption key (this takes a while)... "
+        | "Вычисление ключа шифрования (это займёт некоторое время)... ";
+    INIT_GENERATING: "Generating Ed25519 SSH key... " | "Генерация SSH-ключа Ed25519... ";
+    INIT_SAVING_KEY: "Saving the encrypted key... " | "Сохранение зашифрованного ключа... ";
+    INIT_MARKER: "Creating the marker file... " | "Создание файла-маркера... ";
+    INIT_SERVER_TITLE: "=== Server setup ===" | "=== Настройка сервера ===";
+    INIT_ADD_SERVER: "Add a server now?" | "Добавить сервер сейчас?";
+    INIT_SERVER_ADDED: "Server added!" | "Сервер добавлен!";
+    INIT_DONE_TITLE: "=== Initialization complete ===" | "=== Инициализация завершена ===";
+    INIT_PUBKEY: "Your public SSH key (add it to your servers):" | "Ваш публичный SSH-ключ (добавьте на серверы):";
+    INIT_ADD_KEY_HINT: "To add the key to a server, run:" | "Для добавления ключа на сервер выполните:";
+    INIT_COPY_KEY_HINT: "Or copy the public key from the file:" | "Или скопируйте публичный ключ из файла:";
+    INIT_PUBKEY_HINT: "The public key is encrypted in the container; show it again with {}"
+        | "Публичный ключ зашифрован в контейнере; показать его снова: {}";
+    INIT_CONNECT_HINT: "To connect to a server, run: {}" | "Для подключения к серверу выполните: {}";
+
+    // change-pass
+    CHANGE_PASS_TITLE: "=== Changing the master password ===" | "=== Смена мастер-пароля ===";
+    CHANGE_PASS_CURRENT: "Enter the current password:" | "Введите текущий пароль:";
+    CHANGE_PASS_CHECKING: "Checking the current password... " | "Проверка текущего пароля... ";
+    CHANGE_PASS_DERIVING: "Deriving the new encryption key... " | "Вычисление нового ключа шифрования... ";
+    CHANGE_PASS_REWRAPPING: "Re-wrapping the master key... " | "Перешифровка мастер-ключа... ";
+    CHANGE_PASS_DONE: "Password changed successfully!" | "Пароль успешно изменён!";
+
+    // keyslot
+    KEYSLOT_LIST_TITLE: "=== Key slots (up to {}) ===" | "=== Слоты ключа (до {}) ===";
+    KEYSLOT_CURRENT: "this password" | "этот пароль";
+    KEYSLOT_ADD_TITLE: "=== Adding a password ===" | "=== Добавление пароля ===";
+    KEYSLOT_FULL: "all {} key slots are in use; remove one first" | "заняты все {} слотов ключа; сначала удалите один";
+    KEYSLOT_ADDED: "Key slot {} added; the new password opens the vault."
+        | "Слот ключа {} добавлен; новый пароль открывает хранилище.";
+    KEYSLOT_NO_NUMBER: "there is no key slot {}" | "слота ключа {} нет";
+    KEYSLOT_REMOVE_CURRENT: "key slot {} holds the password just entered; unlock with another password to remove it"
+        | "в слоте ключа {} введённый сейчас пароль; чтобы удалить его, откройте хранилище другим паролем";
+    KEYSLOT_REMOVE_WARNING: "The password of key slot {} will no longer open the vault."
+        | "Пароль из слота ключа {} больше не откроет хранилище.";
+    KEYSLOT_REMOVE_CONFIRM: "Remove the key slot?" | "Удалить слот ключа?";
+    KEYSLOT_REMOVED: "Key slot {} removed; the slots after it are renumbered."
+        | "Слот ключа {} удалён; следующие слоты перенумерованы.";
+    BACKUP_FILES_REMOVED: "Backup files from before format 5 removed: {}; their password cannot be changed or revoked."
+        | "Удалено файлов резервных копий до формата 5: {}; их пароль нельзя сменить или отозвать.";
+
+    // rekey
+    REKEY_TITLE: "=== Changing the key derivation parameters ===" | "=== Смена параметров вычисления ключа ===";
+    REKEY_CURRENT: "Current: {}" | "Сейчас: {}";
+    REKEY_NEW: "New:     {}" | "Новые:  {}";
+    REKEY_DONE: "Master key re-wrapped with the new parameters!" | "Мастер-ключ перешифрован с новыми параметрами!";
+
+    // migrate
+    MIGRATE_TITLE: "=== Vault format (current: v{}) ===" | "=== Формат хранилища (текущий: v{}) ===";
+    MIGRATE_FILE_CURRENT: "up to date" | "актуален";
+    MIGRATE_FILE_OLD: "old format" | "старый формат";
+    MIGRATE_UP_TO_DATE: "All vault files use the current format." | "Все файлы хранилища в текущем формате.";
+    MIGRATE_BACKUP: "The current files are backed up to {}" | "Текущие файлы копируются в {}";
+    MIGRATE_REWRITING: "Rewriting the files in format v{}... " | "Перезапись файлов в формате v{}... ";
+    MIGRATE_RESTORED: "The original files were restored." | "Исходные файлы восстановлены.";
+    MIGRATE_DONE: "Vault migrated to format v{}!" | "Хранилище переведено на формат v{}!";
+    MIGRATE_ALREADY_CONTAINER: "The vault is already a single container file." | "Хранилище уже хранится в одном файле-контейнере.";
+    MIGRATE_CONTAINER: "Moving the vault into {}... " | "Перенос хранилища в {}... ";
+    MIGRATE_CONTAINER_DONE: "The vault is now a single container file." | "Теперь хранилище - один файл-контейнер.";
+    MIGRATE_BACKUP_HINT: "The old files are kept in {}; delete them once everything works."
+        | "Старые файлы сохранены в {}; удалите их, когда убедитесь, что всё работает.";
+
+    // repair
+    REPAIR_TITLE: "=== Vault files ===" | "=== Файлы хранилища ===";
+    REPAIR_INTACT: "intact" | "в порядке";
+    REPAIR_RESTORED_PREVIOUS: "damaged, restored from the previous version" | "повреждён, восстановлен из предыдущей версии";
+    REPAIR_RESTORED_BACKUP: "damaged, restored from {}" | "повреждён, восстановлен из {}";
+    REPAIR_LOST: "damaged, no intact copy found" | "повреждён, целой копии не найдено";
+    REPAIR_ALL_INTACT: "All vault files are intact." | "Все файлы хранилища в порядке.";
+    REPAIR_DONE: "The vault is repaired." | "Хранилище восстановлено.";
+    REPAIR_DAMAGED_KEPT: "The damaged files are kept in {}." | "Повреждённые файлы сохранены в {}.";
+
+    // profile
+    PROFILE_LIST_TITLE: "=== Profiles ===" | "=== Профили ===";
+    PROFILE_NOT_INITIALIZED: "not initialized" | "не инициализирован";
+    PROFILE_CURRENT_HINT: "* - current profile; select another with {}"
+        | "* - текущий профиль; другой выбирается через {}";
+    PROFILE_EXISTS: "profile '{}' already exists" | "профиль '{}' уже существует";
+    PROFILE_DELETE_DEFAULT: "the default profile cannot be deleted" | "профиль по умолчанию удалить нельзя";
+    PROFILE_DELETE_WARNING: "Profile '{}' will be deleted with its key and servers. This cannot be undone."
+        | "Профиль '{}' будет удалён вместе с ключом и серверами. Это необратимо.";
+    PROFILE_DELETE_CONFIRM: "Delete the profile?" | "Удалить профиль?";
+    PROFILE_DELETED: "Profile '{}' deleted." | "Профиль '{}' удалён.";
+
+    // pubkey
+    PUBKEY_TITLE: "Your public SSH key:" | "Ваш публичный SSH-ключ:";
+    PUBKEY_FINGERPRINT: "Fingerprint:" | "Отпечаток:";
+    PUBKEY_HINT: "Add this key to {} on your servers." | "Добавьте этот ключ в {} на ваших серверах.";
+
+    // server
+    SERVER_ADD_TITLE: "=== Adding a server ===" | "=== Добавление сервера ===";
+    SERVER_ADDED: "Server '{}' added!" | "Сервер '{}' добавлен!";
+    SERVER_MISSING_FLAG: "{} is required" | "не указан {}";
+    SERVER_ADD_JSON_NEEDS_FLAGS: "with --output json pass the server as flags (--name, --host, --user)"
+        | "с --output json сервер задаётся флагами (--name, --host, --user)";
+    SERVER_EDIT_NO_FIELDS: "specify at least one field (--host, --port, --user, --desc, --group, --tags, --jump)"
+        | "укажите хотя бы одно поле (--host, --port, --user, --desc, --group, --tags, --jump)";
+    SERVER_UPDATED: "Server '{}' updated." | "Сервер '{}' обновлён.";
+    SERVER_RENAMED: "Server '{}' renamed to '{}'." | "Сервер '{}' переименован в '{}'.";
+    SERVER_SHOW_TITLE: "=== Server '{}' ===" | "=== Сервер '{}' ===";
+    SERVER_LIST_TITLE: "=== Configured servers ===" | "=== Настроенные серверы ===";
+    SERVER_LIST_EMPTY: "No servers configured." | "Серверы не настроены.";
+    SERVER_LIST_ADD_HINT: "Run {} to add a server." | "Выполните {} для добавления сервера.";
+    SERVER_LIST_NO_TAG: "No servers tagged '{}'." | "Нет серверов с тегом '{}'.";
+    COLUMN_NAME: "NAME" | "ИМЯ";
+    COLUMN_CONNECTION: "CONNECTION" | "ПОДКЛЮЧЕНИЕ";
+    COLUMN_DESCRIPTION: "DESCRIPTION" | "ОПИСАНИЕ";
+    COLUMN_TAGS: "TAGS" | "ТЕГИ";
+    SERVER_REMOVE_TITLE: "=== Removing a server ===" | "=== Удаление сервера ===";
+    SERVER_REMOVED: "Server '{}' removed." | "Сервер '{}' удалён.";
+    FORWARD_ADD_NO_FORWARDS: "specify at least one forward (-L, -R or -D)"
+        | "укажите хотя бы один проброс (-L, -R или -D)";
+    FORWARDS_UPDATED: "Forwards of server '{}' updated." | "Пробросы сервера '{}' обновлены.";
+    FORWARDS_NONE: "Server '{}' has no persistent forwards." | "У сервера '{}' нет постоянных пробросов.";
+    FORWARDS_TITLE: "=== Forwards of server '{}' ===" | "=== Пробросы сервера '{}' ===";
+    FORWARD_NO_NUMBER: "server '{}' has no forward number {}" | "у сервера '{}' нет проброса с номером {}";
+    FORWARD_REMOVED: "Forward {} removed." | "Проброс {} удалён.";
+
+    // import
+    IMPORT_FROM: "Importing from" | "Импорт из";
+    IMPORT_NO_HOSTS: "The config has no hosts to import." | "В конфигурации нет хостов для импорта.";
+    IMPORT_DRY_RUN: "Dry run: no changes saved." | "Пробный запуск: изменения не сохранены.";
+    IMPORT_NOTHING: "Nothing to import." | "Нечего импортировать.";
+    IMPORT_DONE: "Servers imported: {}" | "Импортировано серверов: {}";
+    IMPORT_NAME_TAKEN: "a server with this name already exists" | "сервер с таким именем уже есть";
+    IMPORT_NO_USER: "User is not set" | "не указан User";
+    IMPORT_REPLACES: "(replaces the existing one)" | "(заменит существующий)";
+    IMPORT_RENAMED: "(name '{}' is taken)" | "(имя '{}' занято)";
+
+    // export
+    EXPORT_PLAINTEXT: "server addresses, ports and usernames will be written in plain text."
+        | "адреса, порты и имена пользователей серверов будут записаны в открытом виде.";
+    EXPORT_NEEDS_YES: "export without a terminal requires --yes" | "экспорт без терминала требует --yes";
+    EXPORT_CONFIRM: "Continue the export?" | "Продолжить экспорт?";
+    EXPORT_FIELD_UNSAFE: "{} {} contains whitespace or control characters and cannot be exported"
+        | "{} {} содержит пробелы или управляющие символы и не может быть экспортировано";
+    EXPORT_DONE: "Servers exported: {} to {}" | "Экспортировано серверов: {} в {}";
+    EXPORT_AGENT_HINT: "To connect through the exported config, start 'secure-ssh agent'."
+        | "Для подключения через экспортированную конфигурацию запустите 'secure-ssh agent'.";
+    EXPORT_SSH_CONFIG_HEADER: "# secure-ssh export. The private key is not exported:\n# start 'secure-ssh agent' before connecting.\n"
+        | "# Экспорт secure-ssh. Приватный ключ не экспортируется:\n# перед подключением запустите 'secure-ssh agent'.\n";
+    EXPORT_ANSIBLE_HEADER: "# secure-ssh export. Start 'secure-ssh agent' before running Ansible.\n"
+        | "# Экспорт secure-ssh. Перед запуском Ansible запустите 'secure-ssh agent'.\n";
+
+    // agent
+    AGENT_STARTED: "Agent running:" | "Агент запущен:";
+    AGENT_EXPORT_HINT: "Configs from 'secure-ssh server export --format ssh-config' use this socket."
+        | "Конфигурация из 'secure-ssh server export --format ssh-config' использует этот сокет.";
+    AGENT_WATCHDOG: "USB watchdog active - removing the drive stops the agent"
+        | "USB watchdog активен - извлечение накопителя остановит агент";
+    AGENT_STOP_HINT: "Ctrl+C - stop the agent" | "Ctrl+C - остановить агент";
+    AGENT_STOPPED: "Agent stopped." | "Агент остановлен.";
+    // Only used on platforms without Unix sockets
+    #[cfg_attr(unix, allow(dead_code))]
+    AGENT_UNSUPPORTED: "the agent is only supported on Linux and macOS" | "агент поддерживается только на Linux и macOS";
+    AGENT_ALREADY_RUNNING: "agent already running: {}" | "агент уже запущен: {}";
+    AGENT_LOAD_FAILED: "failed to load the key into the agent: {}" | "не удалось загрузить ключ в агент: {}";
+    AGENT_UNSAFE_DIR: "{}: the socket directory must be a directory owned by you with mode 0700"
+        | "{}: каталог сокета должен быть каталогом текущего пользователя с правами 0700";
+
+    // connect, tunnel
+    CONNECT_BROADCAST_NO_SERVERS: "--broadcast requires servers" | "для --broadcast укажите серверы";
+    CONNECT_FORWARDS_SINGLE: "-L/-R/-D forwards can only be given when connecting to a single server"
+        | "пробросы -L/-R/-D можно указать только при подключении к одному серверу";
+    CONNECT_WATCHDOG: "USB watchdog active - removing the drive drops the connection"
+        | "USB watchdog активен - извлечение накопителя прервёт соединение";
+    CONNECT_BROADCAST_HINT: "Input goes to all servers. Ctrl+] and a pane number detach it from the broadcast"
+        | "Ввод рассылается на все серверы. Ctrl+] и номер панели - отключить её от рассылки";
+    CONNECT_SWITCH_HINT: "Switch sessions: Ctrl+] and the session number (n/p - next/previous)"
+        | "Переключение сессий: Ctrl+] и номер сессии (n/p - следующая/предыдущая)";
+    CONNECT_DISCONNECTED: "Disconnected." | "Отключено.";
+    CONNECT_AMBIGUOUS: "'{}' matches several servers - give a name"
+        | "'{}' соответствует нескольким серверам - укажите имя";
+    CONNECT_AVAILABLE: "Available servers:" | "Доступные серверы:";
+    CONNECT_CHOOSE: "Choose a server [1-{}]: " | "Выберите сервер [1-{}]: ";
+    CONNECT_CONNECTING: "Connecting to:" | "Подключение к:";
+    CONNECT_NONE_REACHED: "could not connect to any server" | "не удалось подключиться ни к одному серверу";
+    TUNNEL_NO_FORWARDS: "no forwards configured for server '{}' (add them with 'secure-ssh server forward add' or -L/-R/-D)"
+        | "для сервера '{}' не настроено ни одного проброса (добавьте через 'secure-ssh server forward add' или -L/-R/-D)";
+    TUNNEL_WATCHDOG: "USB watchdog active - removing the drive closes the tunnel"
+        | "USB watchdog активен - извлечение накопителя закроет туннель";
+    TUNNEL_CLOSED: "Tunnel closed." | "Туннель закрыт.";
+    TUNNEL_NONE_STARTED: "none of the forwards could be started" | "ни один проброс не удалось запустить";
+    TUNNEL_ACTIVE: "Tunnel active. Press Ctrl+C to stop." | "Туннель активен. Нажмите Ctrl+C для завершения.";
+
+    // picker
+    PICKER_NO_MATCHES: "No matches" | "Нет совпадений";
+    PICKER_HINT: "↑/↓ select  Enter connect  Esc cancel  Ctrl+U clear"
+        | "↑/↓ выбор  Enter подключиться  Esc отмена  Ctrl+U очистить";
+
+    // run
+    RUN_PARALLEL_ZERO: "--parallel must be greater than zero" | "--parallel должен быть больше нуля";
+    RUN_RUNNING: "Running on {} servers:" | "Выполнение на серверах ({}):";
+    RUN_COLUMN_SERVER: "SERVER" | "СЕРВЕР";
+    RUN_COLUMN_TIME: "TIME" | "ВРЕМЯ";
+    RUN_COLUMN_RESULT: "RESULT" | "РЕЗУЛЬТАТ";
+    RUN_SECONDS: "s" | "с";
+    RUN_SUCCEEDED: "Succeeded: {} of {}" | "Успешно: {} из {}";
+    RUN_TASK_LOST: "task did not finish: {}" | "задача не завершилась: {}";
+    EXIT_SIGNAL: "signal {}" | "сигнал {}";
+    EXIT_UNKNOWN: "no status" | "нет статуса";
+
+    // Interactive sessions
+    SESSION_SWITCHER_HELP: "Ctrl+] then: 1-9 session, n/p next/prev, x close, Ctrl+] send Ctrl+]"
+        | "Ctrl+] затем: 1-9 сессия, n/p след./пред., x закрыть, Ctrl+] отправить Ctrl+]";
+    SESSION_CLOSED_BY_SERVER: "connection closed by the server" | "соединение закрыто сервером";
+    SESSION_USB_REMOVED: "USB drive removed - disconnecting..." | "USB-накопитель извлечён - отключение...";
+    SESSION_CLOSED_NUMBER: "session {} closed" | "сессия {} закрыта";
+    SESSION_NO_NUMBER: "no session {}" | "нет сессии {}";
+    SESSION_CLOSED: "session closed" | "сессия закрыта";
+    SESSION_SIGNAL: "Process terminated by signal: {}" | "Процесс завершён сигналом: {}";
+    BROADCAST_HELP: "Ctrl+] then: 1-9 detach/attach pane, a attach all, q quit, Ctrl+] send Ctrl+]"
+        | "Ctrl+] затем: 1-9 отключить/вернуть панель, a вернуть все, q выйти, Ctrl+] отправить Ctrl+]";
+    BROADCAST_DETACHED: "detached from the broadcast" | "отключена от рассылки";
+    BROADCAST_ATTACHED: "receives input again" | "снова получает ввод";
+    BROADCAST_NO_PANE: "no pane {}" | "нет панели {}";
+    BROADCAST_STATUS: " Broadcasting input: {} of {}" | " Рассылка ввода: {} из {}";
+    BROADCAST_HELP_HINT: "Ctrl+] ? - help" | "Ctrl+] ? - справка";
+    BROADCAST_TILE_CLOSED: " [closed]" | " [закрыта]";
+    BROADCAST_TILE_DETACHED: " [not broadcasting]" | " [вне рассылки]";
+
+    // Forwarding and SOCKS
+    FORWARD_FILE_EXISTS: "{}: file already exists" | "{}: файл уже существует";
+    // Only used on platforms without Unix sockets
+    #[cfg_attr(unix, allow(dead_code))]
+    FORWARD_UNIX_UNSUPPORTED: "Unix sockets are not supported on this platform"
+        | "Unix-сокеты не поддерживаются на этой платформе";
+    FORWARD_TARGET_FAILED: "Forward to {} failed: {}" | "Проброс к {} не удался: {}";
+    SOCKS_VERSION: "unsupported SOCKS version" | "неподдерживаемая версия SOCKS";
+    SOCKS_AUTH_REQUIRED: "client requires authentication" | "клиент требует аутентификацию";
+    SOCKS_BAD_DOMAIN: "invalid domain name" | "неверное доменное имя";
+    SOCKS_ADDRESS_TYPE: "unsupported address type" | "неподдерживаемый тип адреса";
+    SOCKS_CONNECT_ONLY: "only CONNECT is supported" | "поддерживается только CONNECT";
+
+    // Server fields: names, hosts, ports, tags and groups
+    SERVER_NAME_TAKEN: "Server with this name already exists" | "Сервер с таким именем уже существует";
+    SERVER_NAME_UNKNOWN: "Server not found" | "Сервер не найден";
+    SERVER_NAME_EMPTY: "server name must not be empty" | "имя сервера не должно быть пустым";
+    SERVER_NAME_TAG_PREFIX: "server name must not start with '{}' (reserved for tags)"
+        | "имя сервера не должно начинаться с '{}' (это префикс тегов)";
+    SERVER_NAME_INVALID: "invalid server name '{}': whitespace and commas are not allowed"
+        | "неверное имя сервера '{}': пробелы и запятые не допускаются";
+    HOST_EMPTY: "host must not be empty" | "хост не должен быть пустым";
+    HOST_INVALID: "invalid host '{}'" | "неверный хост '{}'";
+    PORT_OUT_OF_RANGE: "port must be between 1 and 65535" | "порт должен быть от 1 до 65535";
+    PORT_INVALID: "invalid port '{}'" | "неверный порт '{}'";
+    USER_EMPTY: "user name must not be empty" | "имя пользователя не должно быть пустым";
+    USER_INVALID: "invalid user name '{}'" | "неверное имя пользователя '{}'";
+    TAG_INVALID: "invalid tag '{}'" | "неверный тег '{}'";
+    GROUP_INVALID: "invalid group name '{}'" | "неверное имя группы '{}'";
+    JUMP_INVALID: "invalid jump host '{}'" | "неверный jump-хост '{}'";
+    JUMP_CHAIN_TOO_LONG: "jump host chain of '{}' is too long or loops"
+        | "цепочка jump-хостов сервера '{}' слишком длинная или зациклена";
+    UNTERMINATED_BRACKET: "unterminated '[' in '{}'" | "незакрытая '[' в '{}'";
+
+    // Forward specs (-L, -R, -D)
+    FORWARD_SPEC_EMPTY: "empty forward specification" | "пустое описание проброса";
+    FORWARD_EMPTY_FIELD: "empty field in '{}'" | "пустое поле в '{}'";
+    FORWARD_FIELD_COUNT: "unexpected number of fields in '{}'" | "неожиданное число полей в '{}'";
+    FORWARD_TARGET_INVALID: "invalid forward target '{}'" | "неверная цель проброса '{}'";
+    FORWARD_DYNAMIC_INVALID: "invalid dynamic forward '{}'" | "неверный динамический проброс '{}'";
+    FORWARD_REMOTE_PORT: "remote port must be specified explicitly" | "удалённый порт нужно указать явно";
+
+    // OpenSSH config (server import)
+    SSH_CONFIG_READ: "cannot read '{}': {}" | "не удалось прочитать '{}': {}";
+    SSH_CONFIG_HOST_PATTERN: "{}: Host requires at least one pattern" | "{}: для Host нужен хотя бы один шаблон";
+    SSH_CONFIG_MATCH: "{}: Match blocks are not supported, skipped" | "{}: блоки Match не поддерживаются, пропущено";
+    SSH_CONFIG_INCLUDE_DEPTH: "{}: Include nested too deeply" | "{}: слишком глубокая вложенность Include";
+    SSH_CONFIG_PORT: "invalid Port '{}'" | "неверный Port '{}'";
+    SSH_CONFIG_PROXY_COMMAND: "ProxyCommand is not supported, ignored" | "ProxyCommand не поддерживается, пропущено";
+    SSH_CONFIG_JUMP_URI: "ProxyJump URI '{}' is not supported" | "URI '{}' в ProxyJump не поддерживается";
+    SSH_CONFIG_QUOTE: "unterminated quote" | "незакрытая кавычка";
+    SSH_CONFIG_REMOTE_DYNAMIC: "remote dynamic forwarding is not supported"
+        | "удалённый динамический проброс не поддерживается";
+    SSH_CONFIG_ARG_COUNT: "wrong number of arguments" | "неверное число аргументов";
+
+    // Profiles
+    PROFILE_NAME_EMPTY: "profile name must not be empty" | "имя профиля не должно быть пустым";
+    PROFILE_NAME_TOO_LONG: "profile name '{}' is longer than 64 characters"
+        | "имя профиля '{}' длиннее 64 символов";
+    PROFILE_NAME_INVALID: "invalid profile name '{}': use letters, digits, '-', '_' and '.', not starting with '.'"
+        | "неверное имя профиля '{}': допустимы буквы, цифры, '-', '_' и '.', но не '.' в начале";
+
+    // Argon2id parameters and --kdf-target durations
+    DURATION_FORMAT: "expected a duration like 1s, 2.5s or 500ms" | "ожидается длительность вида 1s, 2.5s или 500ms";
+    DURATION_INVALID: "invalid duration '{}'" | "неверная длительность '{}'";
+    DURATION_RANGE: "duration must be between 50ms and 60s" | "длительность должна быть от 50ms до 60s";
+    KDF_PARALLELISM_RANGE: "KDF parallelism must be 1..={}" | "параллелизм KDF должен быть в пределах 1..={}";
+    KDF_ITERATIONS_RANGE: "KDF iterations must be 1..={}" | "число итераций KDF должно быть в пределах 1..={}";
+    KDF_MEMORY_RANGE: "KDF memory must be between {} KiB and {} KiB" | "память KDF должна быть от {} до {} КиБ";
+    KDF_KEY_VALUE: "expected key=value, got '{}'" | "ожидается ключ=значение, получено '{}'";
+    KDF_NUMBER: "invalid number '{}' for '{}'" | "неверное число '{}' для '{}'";
+    KDF_UNKNOWN: "unknown KDF parameter '{}'" | "неизвестный параметр KDF '{}'";
+    KDF_MEMORY_SIZE: "invalid memory size '{}'" | "неверный объём памяти '{}'";
+    KDF_PARAMS_FAILED: "Argon2 params error: {}" | "ошибка параметров Argon2: {}";
+    KDF_FAILED: "Key derivation failed: {}" | "не удалось вычислить ключ: {}";
+
+    // Keys and encrypted files
+    KEY_LENGTH: "Invalid key length: expected {}, got {}" | "неверная длина ключа: ожидалось {}, получено {}";
+    PRIVATE_KEY_LENGTH: "Invalid private key length: expected {}, got {}"
+        | "неверная длина приватного ключа: ожидалось {}, получено {}";
+    PRIVATE_KEY_INVALID: "Invalid key bytes" | "неверные байты ключа";
+    PUBLIC_KEY_INVALID: "invalid public key: {}" | "неверный публичный ключ: {}";
+    FILE_CORRUPTED: "file is corrupted" | "файл повреждён";
+    FILE_VERSION_UNSUPPORTED: "unsupported file version {}" | "неподдерживаемая версия файла {}";
+    FILE_KDF_UNSUPPORTED: "unsupported KDF id {}" | "неподдерживаемый идентификатор KDF {}";
+    KEY_SLOT_TRUNCATED: "key slot is truncated" | "слот ключа обрезан";
+    CONTAINER_TRUNCATED: "container is truncated" | "контейнер обрезан";
+    CONTAINER_SECTION_TWICE: "section {} appears twice" | "раздел {} встречается дважды";
+    CONTAINER_NO_SECTION: "{} has no {} section" | "в {} нет раздела {}";
+    VAULT_NO_KEY_SLOTS: "{} is in format {}, which has no key slots" | "{} в формате {}, в котором нет слотов ключа";
+    VAULT_WRONG_FILE: "{} holds a different vault file" | "{} содержит другой файл хранилища";
+    VAULT_OTHER_MASTER: "{} was not encrypted with this vault's master key"
+        | "{} зашифрован не мастер-ключом этого хранилища";
+    VAULT_OTHER_KDF: "{} was encrypted with a different salt or KDF parameters than {}"
+        | "{} зашифрован с другой солью или другими параметрами KDF, чем {}";
+    VAULT_READ_BACK: "the vault did not read back identically after rewriting it in format {}"
+        | "после перезаписи в формате {} хранилище прочиталось не так, как было";
+    ROUTE_EMPTY: "empty connection route" | "пустой маршрут подключения";
+}
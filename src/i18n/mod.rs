@@ -0,0 +1,173 @@
+//! Localised user-facing messages (English and Russian)
+//!
+//! Every message is defined once in [`catalog`] with both translations and
+//! looked up with `t!(NAME)` or `t!(NAME, args...)`. The language is chosen
+//! at startup: `--lang`, otherwise `LC_ALL`, `LC_MESSAGES` or `LANG`.
+
+use std::ffi::OsStr;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Look up a catalog message: `t!(NAME)` gives `&'static str`,
+/// `t!(NAME, a, b)` substitutes the arguments for `{}` and gives `String`
+macro_rules! t {
+    ($name:ident) => {
+        $crate::i18n::catalog::$name.text()
+    };
+    ($name:ident, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::catalog::$name.format(&[$(&$arg as &dyn ::std::fmt::Display),+])
+    };
+}
+
+/// Declare catalog messages as `NAME: "English" | "Русский";`
+macro_rules! messages {
+    ($($(#[$meta:meta])* $name:ident: $en:literal | $ru:literal;)*) => {
+        $($(#[$meta])* pub const $name: Msg = Msg { en: $en, ru: $ru };)*
+
+        /// Every message, for consistency checks
+        #[cfg(test)]
+        pub(crate) const ALL: &[(&str, Msg)] = &[$((stringify!($name), $name)),*];
+    };
+}
+
+pub mod catalog;
+
+/// Interface language
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Ru,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Fix the language for this process; `None` falls back to the locale
+pub fn init(lang: Option<Lang>) {
+    LANG.set(lang.unwrap_or_else(from_env)).ok();
+}
+
+/// Current language
+pub fn lang() -> Lang {
+    *LANG.get_or_init(from_env)
+}
+
+/// Language from the POSIX locale variables, in their order of precedence
+fn from_env() -> Lang {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .map_or(Lang::En, |value| from_locale(&value))
+}
+
+/// `ru`, `ru_RU.UTF-8` and the like are Russian; anything else (`C`, `en_US`) is English
+fn from_locale(locale: &str) -> Lang {
+    let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+    if language.eq_ignore_ascii_case("ru") {
+        Lang::Ru
+    } else {
+        Lang::En
+    }
+}
+
+/// Find `--lang <value>` or `--lang=<value>` before clap parses the
+/// arguments, so that help and errors from clap are already localised
+pub fn from_args<I, S>(args: I) -> Option<Lang>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref().to_str()?;
+        let value = match arg {
+            "--" => return None,
+            "--lang" => args.next()?.as_ref().to_str()?.to_string(),
+            _ => match arg.strip_prefix("--lang=") {
+                Some(value) => value.to_string(),
+                None => continue,
+            },
+        };
+        return clap::ValueEnum::from_str(&value, true).ok();
+    }
+    None
+}
+
+/// A message in both languages; `{}` marks where arguments go
+#[derive(Debug, Clone, Copy)]
+pub struct Msg {
+    pub en: &'static str,
+    pub ru: &'static str,
+}
+
+impl Msg {
+    /// Text in the current language
+    pub fn text(&self) -> &'static str {
+        match lang() {
+            Lang::En => self.en,
+            Lang::Ru => self.ru,
+        }
+    }
+
+    /// Text with each `{}` replaced by the next argument
+    pub fn format(&self, args: &[&dyn Display]) -> String {
+        substitute(self.text(), args)
+    }
+}
+
+fn substitute(template: &str, args: &[&dyn Display]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+
+    if let Some(first) = parts.next() {
+        result.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            result.push_str(&arg.to_string());
+        }
+        result.push_str(part);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_locale() {
+        assert_eq!(from_locale("ru_RU.UTF-8"), Lang::Ru);
+        assert_eq!(from_locale("ru"), Lang::Ru);
+        assert_eq!(from_locale("en_US.UTF-8"), Lang::En);
+        assert_eq!(from_locale("C"), Lang::En);
+        assert_eq!(from_locale("rus"), Lang::En);
+    }
+
+    #[test]
+    fn test_from_args() {
+        assert_eq!(from_args(["secure-ssh", "--lang", "ru", "list"]), Some(Lang::Ru));
+        assert_eq!(from_args(["secure-ssh", "list", "--lang=EN"]), Some(Lang::En));
+        assert_eq!(from_args(["secure-ssh", "run", "--", "--lang", "ru"]), None);
+        assert_eq!(from_args(["secure-ssh", "--lang", "de"]), None);
+    }
+
+    #[test]
+    fn test_substitute() {
+        assert_eq!(substitute("{} of {} servers", &[&1, &3]), "1 of 3 servers");
+        assert_eq!(substitute("no args", &[]), "no args");
+    }
+
+    #[test]
+    fn test_catalog_placeholders_match() {
+        for (name, msg) in catalog::ALL {
+            assert_eq!(
+                msg.en.matches("{}").count(),
+                msg.ru.matches("{}").count(),
+                "placeholder count differs in {}",
+                name
+            );
+        }
+    }
+}
@@ -5,12 +5,21 @@
 //! - Requires a master password to decrypt and use the keys
 //! - Automatically disconnects when the USB drive is removed
 //! - Provides resistance to reverse engineering
+//!
+//! Besides the CLI, [`Vault`] opens the same data directory from other
+//! programs without touching the terminal (see [`vault`]).
+
+#[macro_use]
+pub mod i18n;
 
 pub mod cli;
 pub mod config;
 pub mod crypto;
 pub mod error;
 pub mod ssh;
+pub mod vault;
 pub mod watchdog;
 
+pub use config::{Server, ServerList};
 pub use error::{Result, SecureSshError};
+pub use vault::{Session, Vault};
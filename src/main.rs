@@ -1,7 +1,9 @@
-use clap::{Parser, Subcommand};
-use colored::Colorize;
+use clap::{Arg, ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand};
 use std::process::ExitCode;
 
+#[macro_use]
+mod i18n;
+
 mod cli;
 mod config;
 mod crypto;
@@ -9,79 +11,413 @@ mod error;
 mod ssh;
 mod watchdog;
 
-use error::Result;
+use error::{Result, SecureSshError};
 
 #[derive(Parser)]
 #[command(name = "secure-ssh")]
 #[command(author = "Oleg")]
 #[command(version = "0.1.0")]
-#[command(about = "Безопасный SSH-клиент с защитой по типу аппаратного токена", long_about = None)]
+#[command(about = t!(HELP_ABOUT), long_about = None)]
 struct Cli {
+    #[arg(long, global = true, value_enum, default_value_t = cli::OutputFormat::Text, help = t!(HELP_OUTPUT))]
+    output: cli::OutputFormat,
+
+    // Язык выбирается в main до разбора (i18n::from_args), чтобы перевести
+    // справку clap; поле нужно для справки и проверки значения
+    #[arg(long, global = true, value_enum, help = t!(HELP_LANG))]
+    #[allow(dead_code)]
+    lang: Option<i18n::Lang>,
+
+    #[arg(long, global = true, value_name = t!(VALUE_PATH), help = t!(HELP_DATA_DIR))]
+    data_dir: Option<std::path::PathBuf>,
+
+    #[arg(long, global = true, value_name = t!(VALUE_NAME), help = t!(HELP_PROFILE_FLAG))]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Инициализация с новым мастер-паролем и SSH-ключом
-    Init,
+    #[command(about = t!(HELP_INIT))]
+    Init {
+        #[arg(long, value_name = t!(VALUE_DURATION), help = t!(HELP_KDF_TARGET))]
+        kdf_target: Option<String>,
+
+        #[arg(long, help = t!(HELP_INIT_CONTAINER))]
+        container: bool,
+    },
 
-    /// Показать публичный SSH-ключ
+    #[command(about = t!(HELP_PUBKEY))]
     Pubkey,
 
-    /// Управление конфигурациями серверов
+    #[command(about = t!(HELP_SERVER))]
     Server {
         #[command(subcommand)]
         action: ServerCommands,
     },
 
-    /// Подключиться к настроенному серверу
+    #[command(about = t!(HELP_CONNECT))]
     Connect {
-        /// Имя сервера (необязательно, если настроен только один)
+        #[arg(help = t!(HELP_CONNECT_NAMES))]
+        names: Vec<String>,
+
+        #[arg(short, long, help = t!(HELP_BROADCAST))]
+        broadcast: bool,
+
+        #[command(flatten)]
+        forwards: cli::ForwardArgs,
+    },
+
+    #[command(about = t!(HELP_TUNNEL))]
+    Tunnel {
+        #[arg(help = t!(HELP_TUNNEL_NAME))]
         name: Option<String>,
+
+        #[command(flatten)]
+        forwards: cli::ForwardArgs,
     },
 
-    /// Сменить мастер-пароль
+    #[command(about = t!(HELP_RUN))]
+    Run {
+        #[arg(long, value_name = t!(VALUE_SERVERS), value_delimiter = ',', required = true, help = t!(HELP_RUN_ON))]
+        on: Vec<String>,
+
+        #[arg(short, long, default_value_t = 10, help = t!(HELP_PARALLEL))]
+        parallel: usize,
+
+        #[arg(long, value_name = t!(VALUE_SECONDS), default_value_t = 15, help = t!(HELP_CONNECT_TIMEOUT))]
+        connect_timeout: u64,
+
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true, help = t!(HELP_RUN_COMMAND))]
+        command: Vec<String>,
+    },
+
+    #[command(about = t!(HELP_AGENT))]
+    Agent {
+        #[arg(long, value_name = t!(VALUE_PATH), help = t!(HELP_AGENT_SOCKET))]
+        socket: Option<std::path::PathBuf>,
+    },
+
+    #[command(about = t!(HELP_CHANGE_PASS))]
     ChangePass,
+
+    #[command(about = t!(HELP_KEYSLOT))]
+    Keyslot {
+        #[command(subcommand)]
+        action: KeyslotCommands,
+    },
+
+    #[command(about = t!(HELP_REKEY))]
+    #[command(group(clap::ArgGroup::new("params").required(true).args(["kdf", "kdf_target"])))]
+    Rekey {
+        #[arg(long, value_name = t!(VALUE_KDF), help = t!(HELP_REKEY_KDF))]
+        kdf: Option<String>,
+
+        #[arg(long, value_name = t!(VALUE_DURATION), help = t!(HELP_KDF_TARGET))]
+        kdf_target: Option<String>,
+    },
+
+    #[command(about = t!(HELP_MIGRATE))]
+    Migrate {
+        #[arg(long, help = t!(HELP_MIGRATE_CHECK))]
+        check: bool,
+
+        #[arg(long, conflicts_with = "check", help = t!(HELP_MIGRATE_CONTAINER))]
+        container: bool,
+    },
+
+    #[command(about = t!(HELP_REPAIR))]
+    Repair,
+
+    #[command(about = t!(HELP_PROFILE))]
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommands,
+    },
 }
 
 #[derive(Subcommand)]
-enum ServerCommands {
-    /// Добавить новый сервер
+enum KeyslotCommands {
+    #[command(about = t!(HELP_KEYSLOT_LIST))]
+    List,
+    #[command(about = t!(HELP_KEYSLOT_ADD))]
     Add,
-    /// Показать список серверов
+    #[command(about = t!(HELP_KEYSLOT_REMOVE))]
+    Remove {
+        #[arg(help = t!(HELP_KEYSLOT_NUMBER))]
+        number: usize,
+
+        #[arg(short, long, help = t!(HELP_PROFILE_YES))]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    #[command(about = t!(HELP_PROFILE_LIST))]
     List,
-    /// Удалить сервер
+    #[command(about = t!(HELP_PROFILE_CREATE))]
+    Create {
+        #[arg(help = t!(HELP_PROFILE_NAME))]
+        name: String,
+    },
+    #[command(about = t!(HELP_PROFILE_DELETE))]
+    Delete {
+        #[arg(help = t!(HELP_PROFILE_NAME))]
+        name: String,
+
+        #[arg(short, long, help = t!(HELP_PROFILE_YES))]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ServerCommands {
+    #[command(about = t!(HELP_SERVER_ADD))]
+    Add {
+        #[arg(long, help = t!(HELP_SERVER_NAME))]
+        name: Option<String>,
+
+        #[command(flatten)]
+        fields: cli::server::ServerFields,
+    },
+    #[command(about = t!(HELP_SERVER_EDIT))]
+    Edit {
+        #[arg(help = t!(HELP_SERVER_NAME))]
+        name: String,
+
+        #[command(flatten)]
+        fields: cli::server::ServerFields,
+    },
+    #[command(about = t!(HELP_SERVER_RENAME))]
+    Rename {
+        #[arg(help = t!(HELP_RENAME_OLD))]
+        old: String,
+        #[arg(help = t!(HELP_RENAME_NEW))]
+        new: String,
+    },
+    #[command(about = t!(HELP_SERVER_SHOW))]
+    Show {
+        #[arg(help = t!(HELP_SERVER_NAME))]
+        name: String,
+    },
+    #[command(about = t!(HELP_SERVER_LIST))]
+    List {
+        #[arg(long, help = t!(HELP_LIST_TAG))]
+        tag: Option<String>,
+    },
+    #[command(about = t!(HELP_SERVER_REMOVE))]
+    Remove {
+        #[arg(help = t!(HELP_REMOVE_NAME))]
+        name: String,
+    },
+    #[command(about = t!(HELP_SERVER_IMPORT))]
+    Import {
+        #[arg(long = "from-ssh-config", value_name = t!(VALUE_PATH), num_args = 0..=1, help = t!(HELP_IMPORT_FROM))]
+        from_ssh_config: Option<Option<std::path::PathBuf>>,
+
+        #[arg(long, help = t!(HELP_IMPORT_DRY_RUN))]
+        dry_run: bool,
+
+        #[arg(long, value_enum, default_value_t = cli::import::OnConflict::Skip, help = t!(HELP_ON_CONFLICT))]
+        on_conflict: cli::import::OnConflict,
+    },
+    #[command(about = t!(HELP_SERVER_EXPORT))]
+    Export {
+        #[arg(long, value_enum, help = t!(HELP_EXPORT_FORMAT))]
+        format: cli::export::ExportFormat,
+
+        #[arg(long, value_name = t!(VALUE_PATH), help = t!(HELP_EXPORT_FILE))]
+        file: Option<std::path::PathBuf>,
+
+        #[arg(long, value_name = t!(VALUE_PATH), help = t!(HELP_EXPORT_AGENT_SOCKET))]
+        agent_socket: Option<std::path::PathBuf>,
+
+        #[arg(short, long, help = t!(HELP_EXPORT_YES))]
+        yes: bool,
+    },
+    #[command(about = t!(HELP_SERVER_FORWARD))]
+    Forward {
+        #[command(subcommand)]
+        action: ForwardCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ForwardCommands {
+    #[command(about = t!(HELP_FORWARD_ADD))]
+    Add {
+        #[arg(help = t!(HELP_SERVER_NAME))]
+        name: String,
+
+        #[command(flatten)]
+        forwards: cli::ForwardArgs,
+    },
+    #[command(about = t!(HELP_FORWARD_LIST))]
+    List {
+        #[arg(help = t!(HELP_SERVER_NAME))]
+        name: String,
+    },
+    #[command(about = t!(HELP_FORWARD_REMOVE))]
     Remove {
-        /// Имя сервера для удаления
+        #[arg(help = t!(HELP_SERVER_NAME))]
         name: String,
+        #[arg(help = t!(HELP_FORWARD_NUMBER))]
+        number: usize,
     },
 }
 
 fn main() -> ExitCode {
-    let cli = Cli::parse();
+    // Язык нужен до построения справки clap
+    i18n::init(i18n::from_args(std::env::args_os()));
 
+    let command = Cli::command().disable_version_flag(true).arg(
+        Arg::new("version")
+            .short('V')
+            .long("version")
+            .action(ArgAction::Version)
+            .help(t!(HELP_VERSION)),
+    );
+    let mut command = localize(command);
+    // Команду help clap добавляет при сборке, поэтому её описание - после build
+    command.build();
+    let matches = localize_help_subcommand(command).get_matches();
+    let cli = match Cli::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(e) => e.exit(),
+    };
+
+    let output = cli.output;
     let result = run(cli);
 
     match result {
         Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
-            eprintln!("{} {}", "Ошибка:".red().bold(), e);
-            ExitCode::FAILURE
+            cli::print_error(&e, output);
+            ExitCode::from(e.exit_code())
         }
     }
 }
 
+/// Перевести заголовки справки clap и флаг --help на язык интерфейса
+fn localize(command: clap::Command) -> clap::Command {
+    let command = command
+        .help_template(t!(HELP_TEMPLATE))
+        .subcommand_help_heading(t!(HELP_COMMANDS))
+        .mut_args(|arg| {
+            let heading = if arg.is_positional() { t!(HELP_ARGUMENTS) } else { t!(HELP_OPTIONS) };
+            arg.help_heading(heading)
+        })
+        .disable_help_flag(true)
+        .arg(
+            Arg::new("help")
+                .short('h')
+                .long("help")
+                .action(ArgAction::Help)
+                .help(t!(HELP_FLAG))
+                .help_heading(t!(HELP_OPTIONS)),
+        );
+
+    let names: Vec<String> = command.get_subcommands().map(|c| c.get_name().to_string()).collect();
+    names.iter().fold(command, |command, name| command.mut_subcommand(name, localize))
+}
+
+/// Перевести описание встроенной команды help на всех уровнях
+fn localize_help_subcommand(command: clap::Command) -> clap::Command {
+    let names: Vec<String> = command.get_subcommands().map(|c| c.get_name().to_string()).collect();
+    names.iter().fold(command, |command, name| {
+        if name == "help" {
+            command.mut_subcommand(name, |help| help.about(t!(HELP_SUBCOMMAND)))
+        } else {
+            command.mut_subcommand(name, localize_help_subcommand)
+        }
+    })
+}
+
+/// Интерактивные команды: результата для `--output json` у них нет
+fn json_unsupported(command: &Commands) -> Option<&'static str> {
+    match command {
+        Commands::Init { .. } => Some("init"),
+        Commands::Connect { .. } => Some("connect"),
+        Commands::Tunnel { .. } => Some("tunnel"),
+        Commands::Agent { .. } => Some("agent"),
+        Commands::ChangePass => Some("change-pass"),
+        Commands::Keyslot {
+            action: KeyslotCommands::Add,
+        } => Some("keyslot add"),
+        Commands::Profile {
+            action: ProfileCommands::Create { .. },
+        } => Some("profile create"),
+        _ => None,
+    }
+}
+
 fn run(cli: Cli) -> Result<()> {
+    let output = cli.output;
+    if let Some(name) = json_unsupported(&cli.command).filter(|_| output.is_json()) {
+        return Err(SecureSshError::InvalidConfig(t!(OUTPUT_JSON_UNSUPPORTED, name)));
+    }
+    config::init_location(cli.data_dir, cli.profile)?;
+
     match cli.command {
-        Commands::Init => cli::init::run(),
-        Commands::Pubkey => cli::pubkey::run(),
+        Commands::Init { kdf_target, container } => cli::init::run(kdf_target, container),
+        Commands::Pubkey => cli::pubkey::run(output),
         Commands::Server { action } => match action {
-            ServerCommands::Add => cli::server::add(),
-            ServerCommands::List => cli::server::list(),
-            ServerCommands::Remove { name } => cli::server::remove(&name),
+            ServerCommands::Add { name, fields } => cli::server::add(name, fields, output),
+            ServerCommands::Edit { name, fields } => cli::server::edit(&name, fields, output),
+            ServerCommands::Rename { old, new } => cli::server::rename(&old, &new, output),
+            ServerCommands::Show { name } => cli::server::show(&name, output),
+            ServerCommands::List { tag } => cli::server::list(tag, output),
+            ServerCommands::Remove { name } => cli::server::remove(&name, output),
+            ServerCommands::Import {
+                from_ssh_config,
+                dry_run,
+                on_conflict,
+            } => cli::import::run(from_ssh_config.flatten(), dry_run, on_conflict, output),
+            ServerCommands::Export {
+                format,
+                file,
+                agent_socket,
+                yes,
+            } => cli::export::run(format, file, agent_socket, yes, output),
+            ServerCommands::Forward { action } => match action {
+                ForwardCommands::Add { name, forwards } => {
+                    cli::server::forward_add(&name, forwards, output)
+                }
+                ForwardCommands::List { name } => cli::server::forward_list(&name, output),
+                ForwardCommands::Remove { name, number } => {
+                    cli::server::forward_remove(&name, number, output)
+                }
+            },
         },
-        Commands::Connect { name } => cli::connect::run(name),
+        Commands::Connect {
+            names,
+            broadcast,
+            forwards,
+        } => cli::connect::run(names, broadcast, forwards),
+        Commands::Tunnel { name, forwards } => cli::tunnel::run(name, forwards),
+        Commands::Run {
+            on,
+            parallel,
+            connect_timeout,
+            command,
+        } => cli::run::run(command, on, parallel, connect_timeout, output),
+        Commands::Agent { socket } => cli::agent::run(socket),
         Commands::ChangePass => cli::change_pass::run(),
+        Commands::Keyslot { action } => match action {
+            KeyslotCommands::List => cli::keyslot::list(output),
+            KeyslotCommands::Add => cli::keyslot::add(),
+            KeyslotCommands::Remove { number, yes } => cli::keyslot::remove(number, yes, output),
+        },
+        Commands::Rekey { kdf, kdf_target } => cli::rekey::run(kdf, kdf_target, output),
+        Commands::Migrate { check, container } => cli::migrate::run(check, container, output),
+        Commands::Repair => cli::repair::run(output),
+        Commands::Profile { action } => match action {
+            ProfileCommands::List => cli::profile::list(output),
+            ProfileCommands::Create { name } => cli::profile::create(&name),
+            ProfileCommands::Delete { name, yes } => cli::profile::delete(&name, yes, output),
+        },
     }
 }
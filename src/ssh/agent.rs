@@ -0,0 +1,187 @@
+//! SSH-агент, отдающий ключ с накопителя программам на базе OpenSSH
+//!
+//! Ключ остаётся в памяти процесса secure-ssh; ssh, scp, rsync и Ansible
+//! получают только подписи через сокет агента (`IdentityAgent`).
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, SecureSshError};
+use crate::watchdog::UsbWatchdog;
+
+/// Сокет агента по умолчанию
+///
+/// `$XDG_RUNTIME_DIR/secure-ssh/agent.sock`, иначе
+/// `<временный каталог>/secure-ssh-<uid>/agent.sock`. Экспорт ssh-config
+/// ссылается на этот же путь.
+#[cfg(unix)]
+pub fn default_agent_socket() -> Result<PathBuf> {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) if !runtime.is_empty() => PathBuf::from(runtime).join("secure-ssh"),
+        _ => {
+            // SAFETY: getuid не имеет побочных эффектов
+            let uid = unsafe { libc::getuid() };
+            std::env::temp_dir().join(format!("secure-ssh-{}", uid))
+        }
+    };
+    Ok(dir.join("agent.sock"))
+}
+
+#[cfg(not(unix))]
+pub fn default_agent_socket() -> Result<PathBuf> {
+    Err(SecureSshError::Other(t!(AGENT_UNSUPPORTED).into()))
+}
+
+/// Обслуживать агент на `socket`, пока не нажат Ctrl+C или не извлечён накопитель
+#[cfg(unix)]
+pub async fn run_agent(
+    socket: &Path,
+    private_key: &[u8],
+    watchdog: Option<Box<dyn UsbWatchdog>>,
+) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use russh_keys::agent::client::AgentClient;
+    use tokio::net::{UnixListener, UnixStream};
+
+    let keypair = super::client::keypair(private_key)?;
+
+    if let Some(dir) = socket.parent() {
+        prepare_socket_dir(dir)?;
+    }
+
+    if socket.exists() {
+        if UnixStream::connect(socket).await.is_ok() {
+            return Err(SecureSshError::Other(t!(AGENT_ALREADY_RUNNING, socket.display())));
+        }
+        // Сокет остался от прерванного запуска
+        std::fs::remove_file(socket)?;
+    }
+
+    let listener = UnixListener::bind(socket)?;
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))?;
+
+    let agent = TokenAgent {
+        sealed: Arc::new(AtomicBool::new(false)),
+    };
+    let sealed = agent.sealed.clone();
+
+    let connections = Box::pin(futures::stream::unfold(listener, |listener| async move {
+        let accepted = listener.accept().await.map(|(stream, _)| stream);
+        Some((accepted, listener))
+    }));
+    let server = tokio::spawn(russh_keys::agent::server::serve(connections, agent));
+
+    // Загрузить ключ через сам сокет, после чего запретить изменение ключей
+    let loaded = async {
+        let mut client = AgentClient::connect_uds(socket).await?;
+        client.add_identity(&keypair, &[]).await
+    }
+    .await;
+    sealed.store(true, Ordering::Relaxed);
+
+    let result = match loaded {
+        Err(e) => Err(SecureSshError::Other(t!(AGENT_LOAD_FAILED, e))),
+        Ok(()) => {
+            let ctrl_c = tokio::signal::ctrl_c();
+            tokio::pin!(ctrl_c);
+
+            loop {
+                tokio::select! {
+                    _ = &mut ctrl_c => break Ok(()),
+                    _ = tokio::time::sleep(std::time::Duration::from_millis(500)) => {
+                        if watchdog.as_ref().is_some_and(|wd| !wd.is_present()) {
+                            break Err(SecureSshError::UsbRemoved);
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    // Соединения агента живут в задачах сервера; завершение процесса закрывает их
+    server.abort();
+    std::fs::remove_file(socket).ok();
+
+    result
+}
+
+/// Создать каталог сокета и проверить, что он принадлежит только владельцу
+///
+/// Каталог только для владельца: кроме него к агенту никто не подключится.
+/// `mode` действует лишь при создании, а в общем `/tmp` каталог мог заранее
+/// создать (или подменить символьной ссылкой) другой пользователь, поэтому
+/// существующий каталог проверяется: не ссылка, владелец - текущий
+/// пользователь, права ровно 0700.
+#[cfg(unix)]
+fn prepare_socket_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+
+    let meta = std::fs::symlink_metadata(dir)?;
+    // SAFETY: getuid не имеет побочных эффектов
+    let uid = unsafe { libc::getuid() };
+    if !meta.is_dir() || meta.uid() != uid || meta.permissions().mode() & 0o7777 != 0o700 {
+        return Err(SecureSshError::Other(t!(AGENT_UNSAFE_DIR, dir.display())));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn run_agent(
+    _socket: &Path,
+    _private_key: &[u8],
+    _watchdog: Option<Box<dyn UsbWatchdog>>,
+) -> Result<()> {
+    default_agent_socket().map(|_| ())
+}
+
+/// Агент, который после загрузки ключа только перечисляет ключи и подписывает
+#[cfg(unix)]
+#[derive(Clone)]
+struct TokenAgent {
+    sealed: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(unix)]
+#[async_trait::async_trait]
+impl russh_keys::agent::server::Agent for TokenAgent {
+    async fn confirm_request(&self, msg: russh_keys::agent::server::MessageType) -> bool {
+        use russh_keys::agent::server::MessageType;
+
+        match msg {
+            MessageType::RequestKeys | MessageType::Sign => true,
+            _ => !self.sealed.load(std::sync::atomic::Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_socket_dir_must_be_private() {
+        let base = std::env::temp_dir().join(format!("secure-ssh-agent-{}", std::process::id()));
+        let dir = base.join("sock");
+        let link = base.join("link");
+
+        let created = prepare_socket_dir(&dir);
+
+        // Каталог заранее создан с другими правами
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let open = prepare_socket_dir(&dir);
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        let symlink = prepare_socket_dir(&link);
+        std::fs::remove_dir_all(&base).ok();
+
+        assert!(created.is_ok());
+        assert!(open.is_err());
+        assert!(symlink.is_err());
+    }
+}
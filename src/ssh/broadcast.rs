@@ -0,0 +1,490 @@
+//! Рассылка ввода на несколько серверов (режим cluster-ssh)
+//!
+//! Каждая сессия отображается в своей плитке. Вывод сервера разбирается
+//! эмулятором терминала размером с плитку, поэтому полноэкранные программы
+//! (top, vim) рисуются в ней так же, как в отдельном окне.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crossterm::terminal::{self, disable_raw_mode, enable_raw_mode};
+use tokio::sync::mpsc;
+
+use crate::error::{Result, SecureSshError};
+use crate::watchdog::UsbWatchdog;
+
+use super::pane::{InteractiveSession, Pane, PaneEvent, PaneInput};
+use super::session::{spawn_terminal_input, spawn_watchdog, start_panes, TerminalInput};
+
+/// Префикс команд (Ctrl+], как и в переключателе сессий)
+const PREFIX_KEY: u8 = 0x1d;
+
+/// Прямоугольник плитки на экране (отсчёт от нуля)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+impl Rect {
+    /// Размер области вывода (колонки, строки) без строки заголовка
+    fn content_size(&self) -> (u16, u16) {
+        (self.width.max(1), self.height.saturating_sub(1).max(1))
+    }
+}
+
+/// Разбить экран на `count` плиток
+///
+/// Плитки располагаются сеткой, близкой к квадратной, с разделителем
+/// в одну колонку между ними. Последняя строка экрана остаётся
+/// под строку состояния.
+fn layout(count: usize, (cols, rows): (u16, u16)) -> Vec<Rect> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let grid_cols = (1..=count).find(|c| c * c >= count).unwrap_or(count) as u16;
+    let grid_rows = count.div_ceil(grid_cols as usize) as u16;
+
+    let area_rows = rows.saturating_sub(1);
+    let tile_width = cols.saturating_sub(grid_cols - 1) / grid_cols;
+    let tile_height = area_rows / grid_rows;
+
+    (0..count)
+        .map(|i| {
+            let col = i as u16 % grid_cols;
+            let row = i as u16 / grid_cols;
+            let x = col * (tile_width + 1);
+            let y = row * tile_height;
+
+            // Последние в строке и столбце забирают остаток
+            let width = if col == grid_cols - 1 || i == count - 1 {
+                cols.saturating_sub(x)
+            } else {
+                tile_width
+            };
+            let height = if row == grid_rows - 1 {
+                area_rows.saturating_sub(y)
+            } else {
+                tile_height
+            };
+
+            Rect { x, y, width, height }
+        })
+        .collect()
+}
+
+/// Запустить интерактивные сессии с рассылкой ввода на все сразу
+///
+/// Нажатия клавиш отправляются во все подключённые к рассылке сессии;
+/// Ctrl+] и номер панели временно отключает её от рассылки (или возвращает).
+pub async fn run_broadcast(
+    sessions: Vec<InteractiveSession>,
+    watchdog: Option<Box<dyn UsbWatchdog>>,
+) -> Result<()> {
+    let size = terminal::size().unwrap_or((80, 24));
+    let rects = layout(sessions.len(), size);
+    // Каждая PTY открывается размером своей плитки: крайние плитки получают остаток
+    let sizes: Vec<_> = rects.iter().map(Rect::content_size).collect();
+
+    // Как и в run_sessions, raw mode - до запуска каналов
+    enable_raw_mode().map_err(|e| SecureSshError::Other(e.to_string()))?;
+
+    // Запустить каналы
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    let panes = match start_panes(sessions, &sizes, events_tx).await {
+        Ok(panes) => panes,
+        Err(e) => {
+            disable_raw_mode().ok();
+            return Err(e);
+        }
+    };
+
+    // Флаг для завершения
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    // Запустить USB watchdog если есть
+    spawn_watchdog(watchdog, &shutdown);
+
+    let TerminalInput {
+        stdin: mut stdin_rx,
+        resize: mut resize_rx,
+    } = spawn_terminal_input(&shutdown, size);
+
+    let mut broadcast = Broadcast::new(panes, size);
+    broadcast.enter();
+    let result = broadcast
+        .run(&mut events_rx, &mut stdin_rx, &mut resize_rx, &shutdown)
+        .await;
+
+    // Очистка
+    shutdown.store(true, Ordering::Relaxed);
+    broadcast.leave();
+    disable_raw_mode().ok();
+
+    for tile in broadcast.tiles {
+        tile.pane.shutdown().await;
+    }
+
+    result
+}
+
+/// Сессия и её плитка на экране
+struct Tile {
+    pane: Pane,
+    /// Эмулятор терминала размером с плитку
+    screen: vt100::Parser,
+    rect: Rect,
+    /// Отключена от рассылки ввода
+    detached: bool,
+    /// Нужно перерисовать
+    dirty: bool,
+}
+
+impl Tile {
+    fn receives_input(&self) -> bool {
+        !self.detached && !self.pane.closed
+    }
+}
+
+/// Состояние экрана в режиме рассылки
+struct Broadcast {
+    tiles: Vec<Tile>,
+    size: (u16, u16),
+    /// Был нажат префикс, ждём команду
+    prefix_pending: bool,
+    /// Временное сообщение в строке состояния
+    notice: Option<String>,
+}
+
+impl Broadcast {
+    fn new(panes: Vec<Pane>, size: (u16, u16)) -> Self {
+        let rects = layout(panes.len(), size);
+        let tiles = panes
+            .into_iter()
+            .zip(rects)
+            .map(|(pane, rect)| {
+                let (cols, rows) = rect.content_size();
+                Tile {
+                    pane,
+                    screen: vt100::Parser::new(rows, cols, 0),
+                    rect,
+                    detached: false,
+                    dirty: true,
+                }
+            })
+            .collect();
+
+        Self {
+            tiles,
+            size,
+            prefix_pending: false,
+            notice: None,
+        }
+    }
+
+    /// Переключиться на альтернативный экран, чтобы вернуть терминал как был
+    fn enter(&mut self) {
+        let mut out = std::io::stdout();
+        write!(out, "\x1b[?1049h").ok();
+        self.draw_all(&mut out);
+        out.flush().ok();
+    }
+
+    fn leave(&mut self) {
+        let mut out = std::io::stdout();
+        write!(out, "\x1b[0m\x1b[?25h\x1b[?1049l").ok();
+        out.flush().ok();
+    }
+
+    /// Основной цикл обработки stdin и событий каналов
+    async fn run(
+        &mut self,
+        events_rx: &mut mpsc::UnboundedReceiver<PaneEvent>,
+        stdin_rx: &mut mpsc::Receiver<Vec<u8>>,
+        resize_rx: &mut mpsc::Receiver<(u16, u16)>,
+        shutdown: &Arc<AtomicBool>,
+    ) -> Result<()> {
+        loop {
+            if shutdown.load(Ordering::Relaxed) {
+                return Err(SecureSshError::UsbRemoved);
+            }
+
+            tokio::select! {
+                // События от сессий
+                Some(event) = events_rx.recv() => {
+                    self.event(event);
+                    // Разобрать всё накопившееся и перерисовать один раз
+                    while let Ok(event) = events_rx.try_recv() {
+                        self.event(event);
+                    }
+                    if self.tiles.iter().all(|t| t.pane.closed) {
+                        return Ok(());
+                    }
+                    self.draw_dirty();
+                }
+
+                // Ввод пользователя
+                Some(data) = stdin_rx.recv() => {
+                    if !self.input(&data) {
+                        return Ok(());
+                    }
+                }
+
+                // Изменение размера терминала
+                Some(size) = resize_rx.recv() => self.resize(size),
+
+                // Периодическая проверка флага завершения
+                _ = tokio::time::sleep(std::time::Duration::from_millis(250)) => {}
+            }
+        }
+    }
+
+    fn event(&mut self, event: PaneEvent) {
+        match event {
+            PaneEvent::Output { index, data, .. } => {
+                let tile = &mut self.tiles[index];
+                tile.screen.process(&data);
+                tile.dirty = true;
+            }
+            PaneEvent::Closed { index, reason } => {
+                let tile = &mut self.tiles[index];
+                tile.pane.closed = true;
+                let message = reason.unwrap_or_else(|| t!(SESSION_CLOSED).into());
+                tile.screen.process(format!("\x1b[0m\r\n[{}]", message).as_bytes());
+                tile.dirty = true;
+            }
+        }
+    }
+
+    /// Обработать ввод; возвращает false, если пользователь вышел
+    fn input(&mut self, data: &[u8]) -> bool {
+        let mut forward = Vec::with_capacity(data.len());
+
+        for &byte in data {
+            if self.prefix_pending {
+                self.prefix_pending = false;
+
+                if byte == PREFIX_KEY {
+                    forward.push(PREFIX_KEY);
+                    continue;
+                }
+
+                // Разослать накопленное до изменения набора панелей
+                self.send(&mut forward);
+                if !self.command(byte) {
+                    return false;
+                }
+            } else if byte == PREFIX_KEY {
+                self.prefix_pending = true;
+            } else {
+                forward.push(byte);
+            }
+        }
+
+        self.send(&mut forward);
+        true
+    }
+
+    /// Разослать ввод всем подключённым панелям
+    fn send(&mut self, data: &mut Vec<u8>) {
+        if data.is_empty() {
+            return;
+        }
+        for tile in self.tiles.iter().filter(|t| t.receives_input()) {
+            tile.pane.send(PaneInput::Data(data.clone()));
+        }
+        data.clear();
+    }
+
+    /// Выполнить команду; возвращает false для выхода
+    fn command(&mut self, key: u8) -> bool {
+        match key {
+            b'1'..=b'9' => {
+                let index = (key - b'1') as usize;
+                match self.tiles.get_mut(index) {
+                    Some(tile) => {
+                        tile.detached = !tile.detached;
+                        tile.dirty = true;
+                        let state = if tile.detached { t!(BROADCAST_DETACHED) } else { t!(BROADCAST_ATTACHED) };
+                        self.notice = Some(format!("{}: {}", tile.pane.name, state));
+                    }
+                    None => self.notice = Some(t!(BROADCAST_NO_PANE, index + 1)),
+                }
+            }
+            b'a' => {
+                for tile in &mut self.tiles {
+                    tile.detached = false;
+                    tile.dirty = true;
+                }
+            }
+            b'q' => return false,
+            _ => self.notice = Some(t!(BROADCAST_HELP).to_string()),
+        }
+
+        self.draw_dirty();
+        true
+    }
+
+    fn resize(&mut self, size: (u16, u16)) {
+        self.size = size;
+
+        let rects = layout(self.tiles.len(), size);
+        for (tile, rect) in self.tiles.iter_mut().zip(rects) {
+            let (cols, rows) = rect.content_size();
+            tile.rect = rect;
+            tile.screen.set_size(rows, cols);
+            tile.pane.send(PaneInput::Resize(cols, rows));
+            tile.dirty = true;
+        }
+
+        let mut out = std::io::stdout();
+        self.draw_all(&mut out);
+        out.flush().ok();
+    }
+
+    /// Перерисовать весь экран: разделители, плитки, строку состояния
+    fn draw_all(&mut self, out: &mut impl Write) {
+        write!(out, "\x1b[0m\x1b[2J").ok();
+
+        for tile in &self.tiles {
+            let rect = tile.rect;
+            if rect.x == 0 {
+                continue;
+            }
+            for row in rect.y..rect.y + rect.height {
+                write!(out, "\x1b[{};{}H\x1b[2m│\x1b[0m", row + 1, rect.x).ok();
+            }
+        }
+
+        for tile in &mut self.tiles {
+            tile.dirty = true;
+        }
+        self.draw_tiles(out);
+    }
+
+    fn draw_dirty(&mut self) {
+        let mut out = std::io::stdout();
+        self.draw_tiles(&mut out);
+        out.flush().ok();
+    }
+
+    /// Перерисовать изменившиеся плитки и строку состояния
+    fn draw_tiles(&mut self, out: &mut impl Write) {
+        // Спрятать курсор на время отрисовки, чтобы он не прыгал по экрану
+        write!(out, "\x1b[?25l").ok();
+
+        for (i, tile) in self.tiles.iter_mut().enumerate() {
+            if tile.dirty {
+                draw_tile(out, i, tile);
+                tile.dirty = false;
+            }
+        }
+
+        self.draw_status(out);
+
+        // Курсор показываем в первой панели, получающей ввод
+        if let Some(tile) = self.tiles.iter().find(|t| t.receives_input()) {
+            let screen = tile.screen.screen();
+            let (row, col) = screen.cursor_position();
+            write!(out, "\x1b[{};{}H", tile.rect.y + 2 + row, tile.rect.x + 1 + col).ok();
+            if !screen.hide_cursor() {
+                write!(out, "\x1b[?25h").ok();
+            }
+        }
+    }
+
+    /// Строка состояния в последней строке терминала
+    fn draw_status(&mut self, out: &mut impl Write) {
+        let receiving = self.tiles.iter().filter(|t| t.receives_input()).count();
+        let mut line = t!(BROADCAST_STATUS, receiving, self.tiles.len());
+
+        line.push_str(" | ");
+        match self.notice.take() {
+            Some(notice) => line.push_str(&notice),
+            None => line.push_str(t!(BROADCAST_HELP_HINT)),
+        }
+
+        write_padded(out, self.size.1.saturating_sub(1), 0, self.size.0, &line, "\x1b[7m");
+    }
+}
+
+/// Нарисовать заголовок и содержимое плитки
+fn draw_tile(out: &mut impl Write, index: usize, tile: &Tile) {
+    let rect = tile.rect;
+
+    let (state, style) = if tile.pane.closed {
+        (t!(BROADCAST_TILE_CLOSED), "\x1b[2m")
+    } else if tile.detached {
+        (t!(BROADCAST_TILE_DETACHED), "\x1b[2;7m")
+    } else {
+        ("", "\x1b[7m")
+    };
+    let title = format!(" {}:{}{}", index + 1, tile.pane.name, state);
+    write_padded(out, rect.y, rect.x, rect.width, &title, style);
+
+    let (_, rows) = rect.content_size();
+    let blank = " ".repeat(rect.width as usize);
+    for (i, row) in tile.screen.screen().rows_formatted(0, rect.width).take(rows as usize).enumerate() {
+        let y = rect.y + 2 + i as u16;
+        // Сначала стереть строку плитки, затем вывести её содержимое
+        write!(out, "\x1b[{};{}H\x1b[0m{}\x1b[{};{}H", y, rect.x + 1, blank, y, rect.x + 1).ok();
+        out.write_all(&row).ok();
+    }
+    write!(out, "\x1b[0m").ok();
+}
+
+/// Вывести строку текста заданной ширины с оформлением `style`
+fn write_padded(out: &mut impl Write, row: u16, col: u16, width: u16, text: &str, style: &str) {
+    let width = width as usize;
+    let text: String = text.chars().take(width).collect();
+    let padding = width.saturating_sub(text.chars().count());
+
+    write!(
+        out,
+        "\x1b[{};{}H{}{}{}\x1b[0m",
+        row + 1,
+        col + 1,
+        style,
+        text,
+        " ".repeat(padding)
+    )
+    .ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_single() {
+        let rects = layout(1, (80, 24));
+        assert_eq!(rects, vec![Rect { x: 0, y: 0, width: 80, height: 23 }]);
+        assert_eq!(rects[0].content_size(), (80, 22));
+    }
+
+    #[test]
+    fn test_layout_grid() {
+        // 3 панели: две сверху, одна снизу на всю ширину
+        let rects = layout(3, (81, 25));
+        assert_eq!(rects[0], Rect { x: 0, y: 0, width: 40, height: 12 });
+        assert_eq!(rects[1], Rect { x: 41, y: 0, width: 40, height: 12 });
+        assert_eq!(rects[2], Rect { x: 0, y: 12, width: 81, height: 12 });
+    }
+
+    #[test]
+    fn test_layout_fills_screen() {
+        for count in 1..=9 {
+            let rects = layout(count, (100, 40));
+            assert_eq!(rects.len(), count);
+            for rect in &rects {
+                assert!(rect.x + rect.width <= 100);
+                assert!(rect.y + rect.height <= 39);
+                assert!(rect.height >= 2);
+            }
+        }
+    }
+}
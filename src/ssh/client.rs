@@ -1,19 +1,69 @@
 //! SSH client handler
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use async_trait::async_trait;
 use russh::client::{self, Msg};
-use russh::{Channel, ChannelId};
+use russh::Channel;
 use russh_keys::key::PublicKey;
 
+use crate::config::{Endpoint, Forward, Hop};
 use crate::error::{Result, SecureSshError};
 
 /// SSH client handler
-pub struct SshClient;
+///
+/// Channel data is consumed through `Channel::wait`/`into_stream` by the
+/// session and forwarding code, so the handler does not touch stdout.
+#[derive(Default)]
+pub struct SshClient {
+    /// Local targets of remote forwards, keyed by the address and port listened on the server
+    remote_targets: HashMap<(String, u32), Endpoint>,
+    /// Local targets of remote forwards, keyed by the socket path listened on the server
+    remote_sockets: HashMap<String, Endpoint>,
+    /// Jump host session carrying this connection; kept alive as long as it
+    jump: Option<client::Handle<SshClient>>,
+}
 
 impl SshClient {
-    pub fn new() -> Self {
-        Self
+    /// Create a handler that accepts connections for the given remote forwards
+    pub fn with_forwards(forwards: &[Forward]) -> Self {
+        let mut handler = Self::default();
+        for forward in forwards {
+            match forward {
+                Forward::Remote {
+                    listen: Endpoint::Tcp { host, port },
+                    target,
+                } => {
+                    handler.remote_targets.insert((host.clone(), *port as u32), target.clone());
+                }
+                Forward::Remote {
+                    listen: Endpoint::Unix { path },
+                    target,
+                } => {
+                    handler.remote_sockets.insert(path.clone(), target.clone());
+                }
+                _ => {}
+            }
+        }
+        handler
+    }
+
+    /// Find the local target for a `forwarded-tcpip` channel
+    ///
+    /// Servers report the listen address as it was requested, so the lookup is
+    /// exact first. Some report the resolved address instead (`127.0.0.1` for
+    /// `localhost`); then a forward is still matched if it is the only one on
+    /// that port.
+    fn remote_target(&self, address: &str, port: u32) -> Option<&Endpoint> {
+        if let Some(target) = self.remote_targets.get(&(address.to_string(), port)) {
+            return Some(target);
+        }
+
+        let mut on_port = self.remote_targets.iter().filter(|((_, p), _)| *p == port);
+        match (on_port.next(), on_port.next()) {
+            (Some((_, target)), None) => Some(target),
+            _ => None,
+        }
     }
 }
 
@@ -22,72 +72,85 @@ impl client::Handler for SshClient {
     type Error = russh::Error;
 
     /// Called when server sends its public key for verification
-    /// In a production system, you should verify against known_hosts
+    ///
+    /// Host keys are not verified yet: every key is accepted, like ssh with
+    /// `StrictHostKeyChecking=no` (see the README limitations).
     async fn check_server_key(
         &mut self,
         _server_public_key: &PublicKey,
     ) -> std::result::Result<bool, Self::Error> {
-        // TODO: Implement proper host key verification
-        // For now, accept all keys (like ssh with StrictHostKeyChecking=no)
-        // This should be improved in production!
         Ok(true)
     }
 
-    /// Called when data is received on a channel
-    async fn data(
+    /// Called when a connection arrives on a remote forward (`-R`)
+    async fn server_channel_open_forwarded_tcpip(
         &mut self,
-        _channel: ChannelId,
-        data: &[u8],
+        channel: Channel<Msg>,
+        connected_address: &str,
+        connected_port: u32,
+        _originator_address: &str,
+        _originator_port: u32,
         _session: &mut client::Session,
     ) -> std::result::Result<(), Self::Error> {
-        // Write received data to stdout
-        use std::io::Write;
-        std::io::stdout().write_all(data).ok();
-        std::io::stdout().flush().ok();
+        match self.remote_target(connected_address, connected_port) {
+            Some(target) => {
+                tokio::spawn(super::forward::serve_remote(channel, target.clone()));
+            }
+            None => {
+                channel.close().await.ok();
+            }
+        }
         Ok(())
     }
 
-    /// Called when extended data (stderr) is received
-    async fn extended_data(
+    /// Called when a connection arrives on a remote Unix socket forward (`-R /path:...`)
+    async fn server_channel_open_forwarded_streamlocal(
         &mut self,
-        _channel: ChannelId,
-        _ext: u32,
-        data: &[u8],
+        channel: Channel<Msg>,
+        socket_path: &str,
         _session: &mut client::Session,
     ) -> std::result::Result<(), Self::Error> {
-        // Write stderr data
-        use std::io::Write;
-        std::io::stderr().write_all(data).ok();
-        std::io::stderr().flush().ok();
+        match self.remote_sockets.get(socket_path) {
+            Some(target) => {
+                tokio::spawn(super::forward::serve_remote(channel, target.clone()));
+            }
+            None => {
+                channel.close().await.ok();
+            }
+        }
         Ok(())
     }
 }
 
-/// Connect to an SSH server using Ed25519 key
+/// Connect to a server through its route of jump hosts using Ed25519 key
+///
+/// Every hop except the last is a jump host; the connection to each next
+/// hop is tunnelled through a `direct-tcpip` channel of the previous one.
 pub async fn connect(
-    host: &str,
-    port: u16,
-    user: &str,
+    route: &[Hop],
     private_key_bytes: &[u8],
-) -> Result<(client::Handle<SshClient>, Channel<Msg>)> {
-    // For Ed25519, the private key is 32 bytes (seed)
-    if private_key_bytes.len() != 32 {
-        return Err(SecureSshError::InvalidConfig(format!(
-            "Invalid private key length: expected 32, got {}",
-            private_key_bytes.len()
-        )));
-    }
+    handler: SshClient,
+) -> Result<client::Handle<SshClient>> {
+    let (target, jumps) = route
+        .split_last()
+        .ok_or_else(|| SecureSshError::InvalidConfig(t!(ROUTE_EMPTY).into()))?;
 
-    // Create ed25519_dalek signing key from bytes
-    let key_bytes: [u8; 32] = private_key_bytes.try_into().map_err(|_| {
-        SecureSshError::KeyGenerationFailed("Invalid key bytes".into())
-    })?;
+    let mut jump = None;
+    for hop in jumps {
+        jump = Some(connect_hop(hop, private_key_bytes, SshClient::default(), jump).await?);
+    }
 
-    let signing_key = ed25519_dalek::SigningKey::from_bytes(&key_bytes);
+    connect_hop(target, private_key_bytes, handler, jump).await
+}
 
-    // Convert to russh_keys format
-    // russh_keys 0.45 uses its own key types
-    let keypair = russh_keys::key::KeyPair::Ed25519(signing_key);
+/// Connect to one hop, directly or through the previous hop's session
+async fn connect_hop(
+    hop: &Hop,
+    private_key_bytes: &[u8],
+    mut handler: SshClient,
+    via: Option<client::Handle<SshClient>>,
+) -> Result<client::Handle<SshClient>> {
+    let keypair = keypair(private_key_bytes)?;
 
     // SSH client configuration
     let config = client::Config {
@@ -98,17 +161,24 @@ pub async fn connect(
     };
 
     let config = Arc::new(config);
-    let handler = SshClient::new();
 
-    // Connect to the server
-    let addr = format!("{}:{}", host, port);
-    let mut session = client::connect(config, addr, handler)
-        .await
-        .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))?;
+    // Connect to the server (a (host, port) pair also handles IPv6 literals)
+    let connected = match via {
+        None => client::connect(config, (hop.host.as_str(), hop.port), handler).await,
+        Some(jump) => {
+            let channel = jump
+                .channel_open_direct_tcpip(hop.host.as_str(), hop.port as u32, "127.0.0.1", 0)
+                .await
+                .map_err(|e| connection_error(hop, e))?;
+            handler.jump = Some(jump);
+            client::connect_stream(config, channel.into_stream(), handler).await
+        }
+    };
+    let mut session = connected.map_err(|e| connection_error(hop, e))?;
 
     // Authenticate with our key
     let auth_result = session
-        .authenticate_publickey(user, Arc::new(keypair))
+        .authenticate_publickey(hop.user.as_str(), Arc::new(keypair))
         .await
         .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))?;
 
@@ -116,11 +186,86 @@ pub async fn connect(
         return Err(SecureSshError::SshAuthFailed);
     }
 
-    // Open a session channel
-    let channel = session
+    Ok(session)
+}
+
+/// Classify a connection error so that a timeout, a refusal and a bad
+/// host key are reported (and exit) differently
+fn connection_error(hop: &Hop, error: russh::Error) -> SecureSshError {
+    let address = if hop.host.contains(':') {
+        format!("[{}]:{}", hop.host, hop.port)
+    } else {
+        format!("{}:{}", hop.host, hop.port)
+    };
+
+    match &error {
+        russh::Error::IO(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            SecureSshError::ConnectionRefused(address)
+        }
+        russh::Error::IO(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            SecureSshError::ConnectionTimeout(address)
+        }
+        russh::Error::ConnectionTimeout => SecureSshError::ConnectionTimeout(address),
+        _ => SecureSshError::SshConnectionFailed(format!("{}: {}", address, error)),
+    }
+}
+
+/// Build the russh key pair from the stored Ed25519 seed
+pub(super) fn keypair(private_key_bytes: &[u8]) -> Result<russh_keys::key::KeyPair> {
+    // For Ed25519, the private key is 32 bytes (seed)
+    if private_key_bytes.len() != 32 {
+        return Err(SecureSshError::InvalidConfig(t!(
+            PRIVATE_KEY_LENGTH,
+            32,
+            private_key_bytes.len()
+        )));
+    }
+
+    // Create ed25519_dalek signing key from bytes
+    let key_bytes: [u8; 32] = private_key_bytes.try_into().map_err(|_| {
+        SecureSshError::KeyGenerationFailed(t!(PRIVATE_KEY_INVALID).into())
+    })?;
+
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&key_bytes);
+
+    // Convert to russh_keys format
+    // russh_keys 0.46 uses its own key types
+    Ok(russh_keys::key::KeyPair::Ed25519(signing_key))
+}
+
+/// Open a session channel (for a shell or a command)
+pub async fn open_session(session: &client::Handle<SshClient>) -> Result<Channel<Msg>> {
+    session
         .channel_open_session()
         .await
-        .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))?;
+        .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok((session, channel))
+    fn handler(specs: &[&str]) -> SshClient {
+        let forwards: Vec<_> = specs.iter().map(|s| Forward::parse_remote(s).unwrap()).collect();
+        SshClient::with_forwards(&forwards)
+    }
+
+    #[test]
+    fn test_remote_target_by_address_and_port() {
+        let client = handler(&["127.0.0.1:9000:localhost:80", "10.0.0.1:9000:localhost:81"]);
+
+        assert_eq!(client.remote_target("127.0.0.1", 9000), Some(&Endpoint::tcp("localhost", 80)));
+        assert_eq!(client.remote_target("10.0.0.1", 9000), Some(&Endpoint::tcp("localhost", 81)));
+        // Two forwards share the port, so an unknown address is ambiguous
+        assert_eq!(client.remote_target("0.0.0.0", 9000), None);
+        assert_eq!(client.remote_target("127.0.0.1", 9001), None);
+    }
+
+    #[test]
+    fn test_remote_target_resolved_address() {
+        let client = handler(&["9000:localhost:80"]);
+
+        assert_eq!(client.remote_target("localhost", 9000), Some(&Endpoint::tcp("localhost", 80)));
+        assert_eq!(client.remote_target("127.0.0.1", 9000), Some(&Endpoint::tcp("localhost", 80)));
+    }
 }
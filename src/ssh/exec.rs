@@ -0,0 +1,72 @@
+//! Выполнение команды на сервере без PTY
+
+use std::fmt;
+
+use russh::client;
+use russh::ChannelMsg;
+
+use crate::error::{Result, SecureSshError};
+
+use super::SshClient;
+
+/// Чем завершилась удалённая команда
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Код завершения процесса
+    Code(u32),
+    /// Процесс завершён сигналом
+    Signal(String),
+    /// Сервер закрыл канал, не сообщив статус
+    Unknown,
+}
+
+impl ExitStatus {
+    pub fn success(&self) -> bool {
+        *self == Self::Code(0)
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Code(code) => write!(f, "{}", code),
+            Self::Signal(signal) => f.write_str(&t!(EXIT_SIGNAL, signal)),
+            Self::Unknown => f.write_str(t!(EXIT_UNKNOWN)),
+        }
+    }
+}
+
+/// Выполнить команду и дождаться её завершения
+///
+/// Вывод передаётся в `output` по мере поступления (второй аргумент -
+/// `true` для stderr).
+pub async fn exec(
+    session: &client::Handle<SshClient>,
+    command: &str,
+    mut output: impl FnMut(&[u8], bool),
+) -> Result<ExitStatus> {
+    let mut channel = super::open_session(session).await?;
+
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))?;
+
+    let mut status = ExitStatus::Unknown;
+
+    // Статус может прийти раньше остатка вывода, поэтому читаем до закрытия канала
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            ChannelMsg::Data { data } => output(&data, false),
+            ChannelMsg::ExtendedData { data, ext: _ } => output(&data, true),
+            ChannelMsg::ExitStatus { exit_status } => status = ExitStatus::Code(exit_status),
+            ChannelMsg::ExitSignal { signal_name, .. } => {
+                status = ExitStatus::Signal(format!("{:?}", signal_name));
+            }
+            ChannelMsg::Close => break,
+            _ => {}
+        }
+    }
+
+    Ok(status)
+}
@@ -0,0 +1,321 @@
+//! Проброс TCP-портов и Unix-сокетов через SSH-соединение
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use russh::client::{self, Msg};
+use russh::Channel;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::{JoinHandle, JoinSet};
+
+use crate::config::{Endpoint, Forward};
+use crate::error::{Result, SecureSshError};
+
+use super::{socks, SshClient};
+
+/// Результат запуска одного проброса
+pub struct ForwardStatus {
+    pub forward: Forward,
+    /// Ошибка запуска (None - проброс активен)
+    pub error: Option<String>,
+}
+
+impl ForwardStatus {
+    pub fn is_active(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Набор активных пробросов одного SSH-соединения
+///
+/// Закрывается вместе с сессией (в том числе при срабатывании USB watchdog):
+/// слушающие сокеты закрываются, открытые соединения обрываются,
+/// созданные файлы Unix-сокетов удаляются.
+#[derive(Default)]
+pub struct ForwardSet {
+    /// Задачи слушателей (каждая владеет задачами своих соединений)
+    tasks: Vec<JoinHandle<()>>,
+    /// Созданные локальные Unix-сокеты для удаления при закрытии
+    sockets: Vec<PathBuf>,
+    /// Состояние каждого запрошенного проброса
+    statuses: Vec<ForwardStatus>,
+}
+
+impl ForwardSet {
+    /// Запустить пробросы на установленном соединении
+    ///
+    /// Удалённые пробросы (`-R`) запрашиваются до того, как сессия станет
+    /// общей, поэтому функция забирает её и возвращает в `Arc`.
+    /// Ошибка отдельного проброса не прерывает остальные - см. [`Self::statuses`].
+    pub async fn start(
+        mut session: client::Handle<SshClient>,
+        forwards: &[Forward],
+    ) -> (Arc<client::Handle<SshClient>>, Self) {
+        let mut set = Self::default();
+        let mut remote_results = Vec::new();
+
+        for forward in forwards {
+            let Forward::Remote { listen, .. } = forward else {
+                continue;
+            };
+            let result = match listen {
+                Endpoint::Tcp { host, port } => {
+                    session.tcpip_forward(host.as_str(), *port as u32).await.map(|_| ())
+                }
+                Endpoint::Unix { path } => session.streamlocal_forward(path.as_str()).await,
+            };
+            remote_results.push(result.map_err(|e| e.to_string()));
+        }
+
+        let session = Arc::new(session);
+        let mut remote_results = remote_results.into_iter();
+
+        for forward in forwards {
+            let result = match forward {
+                Forward::Local { listen, target } => {
+                    let handler = LocalHandler {
+                        session: session.clone(),
+                        target: target.clone(),
+                    };
+                    set.listen(listen, handler).await
+                }
+                Forward::Dynamic { listen } => {
+                    let handler = DynamicHandler {
+                        session: session.clone(),
+                    };
+                    set.listen(listen, handler).await
+                }
+                Forward::Remote { .. } => remote_results
+                    .next()
+                    .expect("one request result per remote forward"),
+            };
+
+            set.statuses.push(ForwardStatus {
+                forward: forward.clone(),
+                error: result.err(),
+            });
+        }
+
+        (session, set)
+    }
+
+    /// Состояние каждого запрошенного проброса
+    pub fn statuses(&self) -> &[ForwardStatus] {
+        &self.statuses
+    }
+
+    /// Проверить, активен ли хотя бы один проброс
+    pub fn any_active(&self) -> bool {
+        self.statuses.iter().any(ForwardStatus::is_active)
+    }
+
+    /// Закрыть все пробросы
+    pub fn close(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        for path in self.sockets.drain(..) {
+            std::fs::remove_file(path).ok();
+        }
+    }
+
+    /// Открыть локальный слушающий сокет и запустить на нём обработчик
+    async fn listen(
+        &mut self,
+        endpoint: &Endpoint,
+        handler: impl ConnectionHandler,
+    ) -> std::result::Result<(), String> {
+        let listener = LocalListener::bind(endpoint).await.map_err(|e| e.to_string())?;
+        if let Endpoint::Unix { path } = endpoint {
+            self.sockets.push(PathBuf::from(path));
+        }
+        self.tasks.push(tokio::spawn(accept_loop(listener, handler)));
+        Ok(())
+    }
+}
+
+impl Drop for ForwardSet {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Локальный слушающий сокет
+enum LocalListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl LocalListener {
+    async fn bind(endpoint: &Endpoint) -> Result<Self> {
+        match endpoint {
+            Endpoint::Tcp { host, port } => TcpListener::bind((host.as_str(), *port))
+                .await
+                .map(Self::Tcp)
+                .map_err(|e| SecureSshError::ForwardFailed(format!("{}: {}", endpoint, e))),
+            #[cfg(unix)]
+            Endpoint::Unix { path } => {
+                use std::os::unix::fs::PermissionsExt;
+
+                // Как и OpenSSH без StreamLocalBindUnlink, не перезаписываем чужой сокет
+                if std::path::Path::new(path).exists() {
+                    return Err(SecureSshError::ForwardFailed(t!(FORWARD_FILE_EXISTS, path)));
+                }
+
+                let listener = tokio::net::UnixListener::bind(path)
+                    .map_err(|e| SecureSshError::ForwardFailed(format!("{}: {}", path, e)))?;
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+                Ok(Self::Unix(listener))
+            }
+            #[cfg(not(unix))]
+            Endpoint::Unix { path } => Err(SecureSshError::ForwardFailed(format!(
+                "{}: {}",
+                path,
+                t!(FORWARD_UNIX_UNSUPPORTED)
+            ))),
+        }
+    }
+}
+
+/// Обработчик принятого локального соединения
+trait ConnectionHandler: Clone + Send + 'static {
+    fn handle<S>(self, stream: S, origin: (String, u32)) -> impl std::future::Future<Output = ()> + Send
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+}
+
+/// Принимать локальные соединения и передавать их обработчику
+async fn accept_loop(listener: LocalListener, handler: impl ConnectionHandler) {
+    // JoinSet прерывает все соединения, когда задача слушателя отменяется
+    let mut connections = JoinSet::new();
+
+    loop {
+        match &listener {
+            LocalListener::Tcp(l) => {
+                let Ok((stream, peer)) = l.accept().await else { break };
+                let origin = (peer.ip().to_string(), peer.port() as u32);
+                connections.spawn(handler.clone().handle(stream, origin));
+            }
+            #[cfg(unix)]
+            LocalListener::Unix(l) => {
+                let Ok((stream, _)) = l.accept().await else { break };
+                let origin = ("127.0.0.1".to_string(), 0);
+                connections.spawn(handler.clone().handle(stream, origin));
+            }
+        }
+
+        // Убрать завершившиеся соединения
+        while connections.try_join_next().is_some() {}
+    }
+}
+
+/// Локальный проброс (`-L`) к фиксированной цели на стороне сервера
+#[derive(Clone)]
+struct LocalHandler {
+    session: Arc<client::Handle<SshClient>>,
+    target: Endpoint,
+}
+
+impl ConnectionHandler for LocalHandler {
+    async fn handle<S>(self, stream: S, origin: (String, u32))
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        if let Ok(channel) = open_target(&self.session, &self.target, origin).await {
+            pipe(stream, channel).await;
+        }
+    }
+}
+
+/// Динамический проброс (`-D`): цель задаёт SOCKS5-клиент
+#[derive(Clone)]
+struct DynamicHandler {
+    session: Arc<client::Handle<SshClient>>,
+}
+
+impl ConnectionHandler for DynamicHandler {
+    async fn handle<S>(self, mut stream: S, origin: (String, u32))
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let Ok((host, port)) = socks::handshake(&mut stream).await else {
+            return;
+        };
+
+        let target = Endpoint::tcp(host, port);
+        match open_target(&self.session, &target, origin).await {
+            Ok(channel) => {
+                if socks::reply(&mut stream, socks::REPLY_SUCCEEDED).await.is_ok() {
+                    pipe(stream, channel).await;
+                }
+            }
+            Err(_) => {
+                socks::reply(&mut stream, socks::REPLY_HOST_UNREACHABLE).await.ok();
+            }
+        }
+    }
+}
+
+/// Удалённый проброс (`-R`): соединить канал от сервера с локальной целью
+pub(super) async fn serve_remote(channel: Channel<Msg>, target: Endpoint) {
+    let result = match &target {
+        Endpoint::Tcp { host, port } => match TcpStream::connect((host.as_str(), *port)).await {
+            Ok(stream) => {
+                pipe(stream, channel).await;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        },
+        #[cfg(unix)]
+        Endpoint::Unix { path } => match tokio::net::UnixStream::connect(path).await {
+            Ok(stream) => {
+                pipe(stream, channel).await;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        },
+        #[cfg(not(unix))]
+        Endpoint::Unix { .. } => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            t!(FORWARD_UNIX_UNSUPPORTED),
+        )),
+    };
+
+    if let Err(e) = result {
+        eprint!("\r\n[{}]\r\n", t!(FORWARD_TARGET_FAILED, target, e));
+    }
+}
+
+/// Открыть канал к цели проброса на стороне сервера
+async fn open_target(
+    session: &client::Handle<SshClient>,
+    target: &Endpoint,
+    (origin_host, origin_port): (String, u32),
+) -> Result<Channel<Msg>> {
+    let channel = match target {
+        Endpoint::Tcp { host, port } => {
+            session
+                .channel_open_direct_tcpip(host.as_str(), *port as u32, origin_host, origin_port)
+                .await
+        }
+        Endpoint::Unix { path } => session.channel_open_direct_streamlocal(path.as_str()).await,
+    };
+
+    channel.map_err(|e| {
+        eprint!("\r\n[{}]\r\n", t!(FORWARD_TARGET_FAILED, target, e));
+        SecureSshError::ForwardFailed(e.to_string())
+    })
+}
+
+/// Перекачивать данные между локальным потоком и SSH-каналом
+async fn pipe<S>(mut stream: S, channel: Channel<Msg>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut channel_stream = channel.into_stream();
+    tokio::io::copy_bidirectional(&mut stream, &mut channel_stream)
+        .await
+        .ok();
+}
@@ -1,7 +1,18 @@
 //! SSH client implementation using russh
 
+mod agent;
+mod broadcast;
 mod client;
+mod exec;
+mod forward;
+mod pane;
 mod session;
+mod socks;
 
-pub use client::{connect, SshClient};
-pub use session::run_interactive_session;
+pub use agent::{default_agent_socket, run_agent};
+pub use broadcast::run_broadcast;
+pub use client::{connect, open_session, SshClient};
+pub use exec::{exec, ExitStatus};
+pub use forward::{ForwardSet, ForwardStatus};
+pub use pane::InteractiveSession;
+pub use session::{run_sessions, run_tunnel};
@@ -0,0 +1,205 @@
+//! Интерактивный канал, отвязанный от терминала
+//!
+//! Каждый канал обслуживается отдельной задачей: ввод приходит через
+//! mpsc, а вывод уходит событиями в общий цикл, который решает, показать
+//! его сразу или сохранить в буфер неактивной сессии.
+
+use std::sync::Arc;
+
+use russh::client::{self, Msg};
+use russh::{Channel, ChannelMsg};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::error::{Result, SecureSshError};
+
+use super::{ForwardSet, SshClient};
+
+/// Максимальный размер буфера вывода одной сессии
+const BUFFER_LIMIT: usize = 256 * 1024;
+
+/// Ввод для канала
+pub(super) enum PaneInput {
+    /// Данные с клавиатуры
+    Data(Vec<u8>),
+    /// Новый размер PTY (колонки, строки)
+    Resize(u16, u16),
+    /// Закрыть канал
+    Close,
+}
+
+/// Событие от канала
+pub(super) enum PaneEvent {
+    /// Вывод удалённой стороны (`stderr` - расширенные данные)
+    Output {
+        index: usize,
+        data: Vec<u8>,
+        stderr: bool,
+    },
+    /// Канал закрыт; `reason` - сообщение для пользователя, если есть
+    Closed { index: usize, reason: Option<String> },
+}
+
+/// Интерактивная сессия, готовая к запуску в [`super::run_sessions`]
+pub struct InteractiveSession {
+    /// Имя сервера для строки состояния
+    pub name: String,
+    pub session: Arc<client::Handle<SshClient>>,
+    pub channel: Channel<Msg>,
+    /// Пробросы, закрываемые вместе с сессией
+    pub forwards: ForwardSet,
+}
+
+/// Запущенная сессия внутри цикла
+pub(super) struct Pane {
+    pub name: String,
+    pub session: Arc<client::Handle<SshClient>>,
+    pub forwards: ForwardSet,
+    input: mpsc::UnboundedSender<PaneInput>,
+    task: JoinHandle<()>,
+    /// Последний вывод (для показа при переключении)
+    buffer: Vec<u8>,
+    /// Есть ли непросмотренный вывод
+    pub unseen: bool,
+    pub closed: bool,
+}
+
+impl Pane {
+    /// Запросить PTY и shell, затем запустить задачу обслуживания канала
+    pub async fn start(
+        index: usize,
+        session: InteractiveSession,
+        (cols, rows): (u16, u16),
+        events: mpsc::UnboundedSender<PaneEvent>,
+    ) -> Result<Self> {
+        let InteractiveSession {
+            name,
+            session,
+            channel,
+            forwards,
+        } = session;
+
+        // Запросить PTY
+        channel
+            .request_pty(false, "xterm-256color", cols as u32, rows as u32, 0, 0, &[])
+            .await
+            .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))?;
+
+        // Запросить shell
+        channel
+            .request_shell(false)
+            .await
+            .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))?;
+
+        let (input, input_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(pump(index, channel, input_rx, events));
+
+        Ok(Self {
+            name,
+            session,
+            forwards,
+            input,
+            task,
+            buffer: Vec::new(),
+            unseen: false,
+            closed: false,
+        })
+    }
+
+    /// Отправить ввод в канал
+    pub fn send(&self, input: PaneInput) {
+        if !self.closed {
+            self.input.send(input).ok();
+        }
+    }
+
+    /// Запомнить вывод, отбрасывая самое старое сверх лимита
+    pub fn record(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+
+        if self.buffer.len() > BUFFER_LIMIT {
+            // Обрезать по границе строки, чтобы не начинать с середины escape-последовательности
+            let excess = self.buffer.len() - BUFFER_LIMIT / 2;
+            let cut = self.buffer[excess..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(excess, |p| excess + p + 1);
+            self.buffer.drain(..cut);
+        }
+    }
+
+    /// Сохранённый вывод
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Закрыть канал, пробросы и соединение
+    pub async fn shutdown(mut self) {
+        self.forwards.close();
+        self.input.send(PaneInput::Close).ok();
+        self.task.await.ok();
+
+        self.session
+            .disconnect(russh::Disconnect::ByApplication, "User disconnected", "en")
+            .await
+            .ok();
+    }
+}
+
+/// Обслуживать канал: передавать ввод и сообщать о выводе
+async fn pump(
+    index: usize,
+    mut channel: Channel<Msg>,
+    mut input: mpsc::UnboundedReceiver<PaneInput>,
+    events: mpsc::UnboundedSender<PaneEvent>,
+) {
+    let reason = loop {
+        tokio::select! {
+            // Сообщения от сервера
+            msg = channel.wait() => {
+                match msg {
+                    Some(ChannelMsg::Data { data }) => {
+                        events.send(PaneEvent::Output { index, data: data.to_vec(), stderr: false }).ok();
+                    }
+                    Some(ChannelMsg::ExtendedData { data, ext: _ }) => {
+                        events.send(PaneEvent::Output { index, data: data.to_vec(), stderr: true }).ok();
+                    }
+                    Some(ChannelMsg::ExitSignal { signal_name, .. }) => {
+                        break Some(t!(SESSION_SIGNAL, format!("{:?}", signal_name)));
+                    }
+                    // Shell завершился или сервер закрыл канал
+                    Some(ChannelMsg::Eof)
+                    | Some(ChannelMsg::ExitStatus { .. })
+                    | Some(ChannelMsg::Close)
+                    | None => break None,
+                    _ => {}
+                }
+            }
+
+            // Ввод пользователя
+            cmd = input.recv() => {
+                match cmd {
+                    Some(PaneInput::Data(data)) => {
+                        // Ctrl+D (EOF)
+                        if data.contains(&4) {
+                            break None;
+                        }
+                        if let Err(e) = channel.data(&data[..]).await {
+                            break Some(e.to_string());
+                        }
+                    }
+                    Some(PaneInput::Resize(cols, rows)) => {
+                        channel.window_change(cols as u32, rows as u32, 0, 0).await.ok();
+                    }
+                    Some(PaneInput::Close) | None => break None,
+                }
+            }
+        }
+    };
+
+    // Корректно закрыть канал
+    channel.eof().await.ok();
+    channel.close().await.ok();
+
+    events.send(PaneEvent::Closed { index, reason }).ok();
+}
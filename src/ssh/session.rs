@@ -1,50 +1,147 @@
-//! Обработка интерактивной SSH-сессии
+//! Обработка интерактивных SSH-сессий
 
+use std::io::Write;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use tokio::sync::mpsc;
 use crossterm::terminal::{self, enable_raw_mode, disable_raw_mode};
-use russh::{client, Channel, ChannelMsg, Disconnect};
+use russh::{client, Disconnect};
 
 use crate::error::{Result, SecureSshError};
 use crate::watchdog::UsbWatchdog;
 
-/// Запустить интерактивную SSH-сессию с PTY
-pub async fn run_interactive_session(
-    session: client::Handle<super::SshClient>,
-    mut channel: Channel<russh::client::Msg>,
+use super::pane::{InteractiveSession, Pane, PaneEvent, PaneInput};
+use super::ForwardSet;
+
+/// Префикс команд переключателя сессий (Ctrl+])
+const PREFIX_KEY: u8 = 0x1d;
+
+/// Запустить одну или несколько интерактивных сессий с PTY в одном терминале
+///
+/// Пробросы каждой сессии закрываются вместе с ней, в том числе
+/// при извлечении USB-накопителя. При нескольких сессиях нижняя строка
+/// терминала отдаётся под строку состояния, а переключение выполняется
+/// по префиксу Ctrl+]. Вывод неактивных сессий накапливается в их буферах.
+pub async fn run_sessions(
+    sessions: Vec<InteractiveSession>,
     watchdog: Option<Box<dyn UsbWatchdog>>,
 ) -> Result<()> {
-    // Запросить PTY
+    let multi = sessions.len() > 1;
     let (cols, rows) = terminal::size().unwrap_or((80, 24));
 
-    channel
-        .request_pty(
-            false,
-            "xterm-256color",
-            cols as u32,
-            rows as u32,
-            0,
-            0,
-            &[],
-        )
-        .await
-        .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))?;
+    // Raw mode включается до запуска каналов: если он недоступен,
+    // закрывать ещё нечего
+    enable_raw_mode().map_err(|e| SecureSshError::Other(e.to_string()))?;
 
-    // Запросить shell
-    channel
-        .request_shell(false)
-        .await
-        .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))?;
+    // Запустить каналы
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    let sizes = vec![pty_size(cols, rows, multi); sessions.len()];
+    let panes = match start_panes(sessions, &sizes, events_tx).await {
+        Ok(panes) => panes,
+        Err(e) => {
+            disable_raw_mode().ok();
+            return Err(e);
+        }
+    };
 
     // Флаг для завершения
     let shutdown = Arc::new(AtomicBool::new(false));
 
-    // Текущий размер терминала для отслеживания изменений
-    let current_cols = Arc::new(AtomicU16::new(cols));
-    let current_rows = Arc::new(AtomicU16::new(rows));
-
     // Запустить USB watchdog если есть
+    spawn_watchdog(watchdog, &shutdown);
+
+    let TerminalInput {
+        stdin: mut stdin_rx,
+        resize: mut resize_rx,
+    } = spawn_terminal_input(&shutdown, (cols, rows));
+
+    // Основной цикл обработки событий
+    let mut switcher = Switcher::new(panes, multi, (cols, rows));
+    switcher.enter();
+    let result = switcher
+        .run(&mut events_rx, &mut stdin_rx, &mut resize_rx, &shutdown)
+        .await;
+
+    // Очистка
+    shutdown.store(true, Ordering::Relaxed);
+    switcher.leave();
+    disable_raw_mode().ok();
+
+    for pane in switcher.panes {
+        pane.shutdown().await;
+    }
+
+    result
+}
+
+/// Держать соединение только ради пробросов (аналог `ssh -N`)
+///
+/// Завершается по Ctrl+C, при разрыве соединения или извлечении
+/// USB-накопителя; пробросы закрываются в любом случае.
+pub async fn run_tunnel(
+    session: Arc<client::Handle<super::SshClient>>,
+    watchdog: Option<Box<dyn UsbWatchdog>>,
+    mut forwards: ForwardSet,
+) -> Result<()> {
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    let result = loop {
+        tokio::select! {
+            _ = &mut ctrl_c => break Ok(()),
+            _ = tokio::time::sleep(std::time::Duration::from_millis(500)) => {
+                if watchdog.as_ref().is_some_and(|wd| !wd.is_present()) {
+                    break Err(SecureSshError::UsbRemoved);
+                }
+                if session.is_closed() {
+                    break Err(SecureSshError::SshConnectionFailed(t!(SESSION_CLOSED_BY_SERVER).into()));
+                }
+            }
+        }
+    };
+
+    forwards.close();
+
+    session
+        .disconnect(Disconnect::ByApplication, "User disconnected", "en")
+        .await
+        .ok();
+
+    result
+}
+
+/// Ввод с терминала: нажатия клавиш и изменения размера
+pub(super) struct TerminalInput {
+    pub stdin: mpsc::Receiver<Vec<u8>>,
+    pub resize: mpsc::Receiver<(u16, u16)>,
+}
+
+/// Запросить PTY и shell для каждой сессии
+///
+/// `sizes` - размер PTY для каждой сессии по порядку.
+/// При ошибке уже запущенные сессии закрываются.
+pub(super) async fn start_panes(
+    sessions: Vec<InteractiveSession>,
+    sizes: &[(u16, u16)],
+    events_tx: mpsc::UnboundedSender<PaneEvent>,
+) -> Result<Vec<Pane>> {
+    let mut panes = Vec::with_capacity(sessions.len());
+    for (index, (session, &size)) in sessions.into_iter().zip(sizes).enumerate() {
+        match Pane::start(index, session, size, events_tx.clone()).await {
+            Ok(pane) => panes.push(pane),
+            Err(e) => {
+                for pane in panes {
+                    pane.shutdown().await;
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(panes)
+}
+
+/// Следить за USB-накопителем и выставить флаг завершения при извлечении
+pub(super) fn spawn_watchdog(watchdog: Option<Box<dyn UsbWatchdog>>, shutdown: &Arc<AtomicBool>) {
     if let Some(wd) = watchdog {
         let shutdown_wd = shutdown.clone();
         tokio::spawn(async move {
@@ -56,22 +153,26 @@ pub async fn run_interactive_session(
                 }
 
                 if !wd.is_present() {
-                    eprintln!("\n\r[USB-накопитель извлечён - отключение...]");
+                    eprintln!("\n\r[{}]", t!(SESSION_USB_REMOVED));
                     shutdown_wd.store(true, Ordering::Relaxed);
                     break;
                 }
             }
         });
     }
+}
 
-    // Включить raw mode для корректной работы терминала
-    enable_raw_mode().map_err(|e| SecureSshError::Other(e.to_string()))?;
+/// Запустить потоки чтения stdin и отслеживания размера терминала
+pub(super) fn spawn_terminal_input(shutdown: &Arc<AtomicBool>, (cols, rows): (u16, u16)) -> TerminalInput {
+    // Текущий размер терминала для отслеживания изменений
+    let current_cols = Arc::new(AtomicU16::new(cols));
+    let current_rows = Arc::new(AtomicU16::new(rows));
 
     // Канал для stdin
-    let (stdin_tx, mut stdin_rx) = mpsc::channel::<Vec<u8>>(100);
+    let (stdin_tx, stdin_rx) = mpsc::channel::<Vec<u8>>(100);
 
     // Канал для событий resize
-    let (resize_tx, mut resize_rx) = mpsc::channel::<(u16, u16)>(10);
+    let (resize_tx, resize_rx) = mpsc::channel::<(u16, u16)>(10);
 
     // Поток чтения stdin
     let shutdown_stdin = shutdown.clone();
@@ -99,8 +200,6 @@ pub async fn run_interactive_session(
 
     // Поток отслеживания resize
     let shutdown_resize = shutdown.clone();
-    let cols_clone = current_cols.clone();
-    let rows_clone = current_rows.clone();
     std::thread::spawn(move || {
         loop {
             if shutdown_resize.load(Ordering::Relaxed) {
@@ -110,104 +209,326 @@ pub async fn run_interactive_session(
             std::thread::sleep(std::time::Duration::from_millis(250));
 
             if let Ok((new_cols, new_rows)) = terminal::size() {
-                let old_cols = cols_clone.load(Ordering::Relaxed);
-                let old_rows = rows_clone.load(Ordering::Relaxed);
+                let old_cols = current_cols.load(Ordering::Relaxed);
+                let old_rows = current_rows.load(Ordering::Relaxed);
 
                 if new_cols != old_cols || new_rows != old_rows {
-                    cols_clone.store(new_cols, Ordering::Relaxed);
-                    rows_clone.store(new_rows, Ordering::Relaxed);
+                    current_cols.store(new_cols, Ordering::Relaxed);
+                    current_rows.store(new_rows, Ordering::Relaxed);
                     let _ = resize_tx.blocking_send((new_cols, new_rows));
                 }
             }
         }
     });
 
-    // Основной цикл обработки событий
-    let result = run_event_loop(&mut channel, &mut stdin_rx, &mut resize_rx, &shutdown).await;
-
-    // Очистка
-    shutdown.store(true, Ordering::Relaxed);
-    disable_raw_mode().ok();
-
-    // Корректно закрыть канал
-    channel.eof().await.ok();
-    channel.close().await.ok();
+    TerminalInput {
+        stdin: stdin_rx,
+        resize: resize_rx,
+    }
+}
 
-    // Отключиться от сессии
-    session
-        .disconnect(Disconnect::ByApplication, "User disconnected", "en")
-        .await
-        .ok();
+/// Размер PTY: при нескольких сессиях последняя строка занята строкой состояния
+fn pty_size(cols: u16, rows: u16, multi: bool) -> (u16, u16) {
+    if multi {
+        (cols, rows.saturating_sub(1).max(1))
+    } else {
+        (cols, rows)
+    }
+}
 
-    result
+/// Состояние терминала с одной или несколькими сессиями
+struct Switcher {
+    panes: Vec<Pane>,
+    active: usize,
+    /// Показывать строку состояния и обрабатывать префикс
+    multi: bool,
+    size: (u16, u16),
+    /// Был нажат префикс, ждём команду
+    prefix_pending: bool,
+    /// Временное сообщение в строке состояния
+    notice: Option<String>,
 }
 
-/// Основной цикл обработки stdin и данных канала
-async fn run_event_loop(
-    channel: &mut Channel<russh::client::Msg>,
-    stdin_rx: &mut mpsc::Receiver<Vec<u8>>,
-    resize_rx: &mut mpsc::Receiver<(u16, u16)>,
-    shutdown: &Arc<AtomicBool>,
-) -> Result<()> {
-    use std::io::Write;
+impl Switcher {
+    fn new(panes: Vec<Pane>, multi: bool, size: (u16, u16)) -> Self {
+        Self {
+            panes,
+            active: 0,
+            multi,
+            size,
+            prefix_pending: false,
+            notice: None,
+        }
+    }
 
-    loop {
-        if shutdown.load(Ordering::Relaxed) {
-            return Err(SecureSshError::UsbRemoved);
+    /// Подготовить экран: область прокрутки над строкой состояния
+    fn enter(&mut self) {
+        if self.multi {
+            let mut out = std::io::stdout();
+            write!(out, "\x1b[2J\x1b[H").ok();
+            self.set_scroll_region(&mut out);
+            self.draw_status(&mut out);
+            out.flush().ok();
         }
+    }
 
-        tokio::select! {
-            // Сообщения от сервера
-            msg = channel.wait() => {
-                match msg {
-                    Some(ChannelMsg::Data { data }) => {
-                        std::io::stdout().write_all(&data).ok();
-                        std::io::stdout().flush().ok();
-                    }
-                    Some(ChannelMsg::ExtendedData { data, ext: _ }) => {
-                        std::io::stderr().write_all(&data).ok();
-                        std::io::stderr().flush().ok();
-                    }
-                    Some(ChannelMsg::Eof) => {
-                        // Сервер закрыл канал
-                        break;
-                    }
-                    Some(ChannelMsg::ExitStatus { exit_status: _ }) => {
-                        // Shell завершился
-                        break;
-                    }
-                    Some(ChannelMsg::ExitSignal { signal_name, .. }) => {
-                        eprintln!("\r\n[Процесс завершён сигналом: {:?}]", signal_name);
-                        break;
+    /// Вернуть экран в обычное состояние
+    fn leave(&mut self) {
+        if self.multi {
+            let mut out = std::io::stdout();
+            write!(out, "\x1b[r\x1b[{};1H\x1b[2K", self.size.1).ok();
+            out.flush().ok();
+        }
+    }
+
+    /// Основной цикл обработки stdin и событий каналов
+    async fn run(
+        &mut self,
+        events_rx: &mut mpsc::UnboundedReceiver<PaneEvent>,
+        stdin_rx: &mut mpsc::Receiver<Vec<u8>>,
+        resize_rx: &mut mpsc::Receiver<(u16, u16)>,
+        shutdown: &Arc<AtomicBool>,
+    ) -> Result<()> {
+        loop {
+            if shutdown.load(Ordering::Relaxed) {
+                return Err(SecureSshError::UsbRemoved);
+            }
+
+            tokio::select! {
+                // События от сессий
+                Some(event) = events_rx.recv() => {
+                    match event {
+                        PaneEvent::Output { index, data, stderr } => self.output(index, &data, stderr),
+                        PaneEvent::Closed { index, reason } => {
+                            if !self.closed(index, reason) {
+                                return Ok(());
+                            }
+                        }
                     }
-                    Some(ChannelMsg::Close) => {
-                        break;
+                }
+
+                // Ввод пользователя
+                Some(data) = stdin_rx.recv() => self.input(&data),
+
+                // Изменение размера терминала
+                Some((cols, rows)) = resize_rx.recv() => {
+                    self.size = (cols, rows);
+                    for pane in &self.panes {
+                        let (c, r) = pty_size(cols, rows, self.multi);
+                        pane.send(PaneInput::Resize(c, r));
                     }
-                    None => {
-                        // Канал закрыт
-                        break;
+                    if self.multi {
+                        let mut out = std::io::stdout();
+                        self.set_scroll_region(&mut out);
+                        self.draw_status(&mut out);
+                        out.flush().ok();
                     }
-                    _ => {}
                 }
+
+                // Периодическая проверка флага завершения
+                _ = tokio::time::sleep(std::time::Duration::from_millis(250)) => {}
+            }
+        }
+    }
+
+    /// Вывод сессии: показать, если она активна, иначе только запомнить
+    fn output(&mut self, index: usize, data: &[u8], stderr: bool) {
+        let pane = &mut self.panes[index];
+        pane.record(data);
+
+        if index != self.active {
+            pane.unseen = true;
+            self.redraw_status();
+            return;
+        }
+
+        if stderr && !self.multi {
+            let mut err = std::io::stderr();
+            err.write_all(data).ok();
+            err.flush().ok();
+            return;
+        }
+
+        let mut out = std::io::stdout();
+        out.write_all(data).ok();
+        if self.multi {
+            self.draw_status(&mut out);
+        }
+        out.flush().ok();
+    }
+
+    /// Сессия закрыта; возвращает false, если открытых сессий не осталось
+    fn closed(&mut self, index: usize, reason: Option<String>) -> bool {
+        self.panes[index].closed = true;
+
+        if let Some(reason) = reason {
+            if self.multi {
+                self.notice = Some(format!("{}: {}", self.panes[index].name, reason));
+            } else {
+                eprintln!("\r\n[{}]", reason);
             }
+        }
 
-            // Ввод пользователя
-            Some(data) = stdin_rx.recv() => {
-                // Ctrl+D (EOF)
-                if data.contains(&4) {
-                    break;
+        match self.next_open(index) {
+            Some(next) => {
+                if index == self.active {
+                    self.switch_to(next);
+                } else {
+                    self.redraw_status();
                 }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Обработать ввод с клавиатуры
+    fn input(&mut self, data: &[u8]) {
+        if !self.multi {
+            self.panes[self.active].send(PaneInput::Data(data.to_vec()));
+            return;
+        }
 
-                channel.data(&data[..]).await
-                    .map_err(|e| SecureSshError::SshConnectionFailed(e.to_string()))?;
+        let mut forward = Vec::with_capacity(data.len());
+
+        for &byte in data {
+            if self.prefix_pending {
+                self.prefix_pending = false;
+
+                if byte == PREFIX_KEY {
+                    forward.push(PREFIX_KEY);
+                    continue;
+                }
+
+                // Отправить накопленное текущей сессии до переключения
+                if !forward.is_empty() {
+                    self.panes[self.active].send(PaneInput::Data(std::mem::take(&mut forward)));
+                }
+                self.command(byte);
+            } else if byte == PREFIX_KEY {
+                self.prefix_pending = true;
+            } else {
+                forward.push(byte);
             }
+        }
 
-            // Изменение размера терминала
-            Some((cols, rows)) = resize_rx.recv() => {
-                channel.window_change(cols as u32, rows as u32, 0, 0).await.ok();
+        if !forward.is_empty() {
+            self.panes[self.active].send(PaneInput::Data(forward));
+        }
+    }
+
+    /// Выполнить команду переключателя
+    fn command(&mut self, key: u8) {
+        match key {
+            b'1'..=b'9' => {
+                let index = (key - b'1') as usize;
+                match self.panes.get(index) {
+                    Some(pane) if !pane.closed => self.switch_to(index),
+                    Some(_) => self.notify(t!(SESSION_CLOSED_NUMBER, index + 1)),
+                    None => self.notify(t!(SESSION_NO_NUMBER, index + 1)),
+                }
             }
+            b'n' | b'\t' => {
+                if let Some(next) = self.next_open(self.active) {
+                    self.switch_to(next);
+                }
+            }
+            b'p' => {
+                if let Some(prev) = self.prev_open(self.active) {
+                    self.switch_to(prev);
+                }
+            }
+            b'x' => self.panes[self.active].send(PaneInput::Close),
+            _ => self.notify(t!(SESSION_SWITCHER_HELP).to_string()),
         }
     }
 
-    Ok(())
+    /// Переключиться на сессию: показать её сохранённый вывод
+    fn switch_to(&mut self, index: usize) {
+        self.active = index;
+        let pane = &mut self.panes[index];
+        pane.unseen = false;
+
+        let mut out = std::io::stdout();
+        write!(out, "\x1b[2J\x1b[H").ok();
+        out.write_all(pane.buffer()).ok();
+
+        // Заставить полноэкранные программы перерисоваться
+        let (cols, rows) = pty_size(self.size.0, self.size.1, self.multi);
+        pane.send(PaneInput::Resize(cols, rows.saturating_sub(1).max(1)));
+        pane.send(PaneInput::Resize(cols, rows));
+
+        self.draw_status(&mut out);
+        out.flush().ok();
+    }
+
+    /// Следующая открытая сессия после `index` (по кругу, может вернуть `index`)
+    fn next_open(&self, index: usize) -> Option<usize> {
+        let len = self.panes.len();
+        (1..=len)
+            .map(|step| (index + step) % len)
+            .find(|&i| !self.panes[i].closed)
+    }
+
+    /// Предыдущая открытая сессия перед `index` (по кругу)
+    fn prev_open(&self, index: usize) -> Option<usize> {
+        let len = self.panes.len();
+        (1..=len)
+            .map(|step| (index + len - step) % len)
+            .find(|&i| !self.panes[i].closed)
+    }
+
+    fn notify(&mut self, message: String) {
+        self.notice = Some(message);
+        self.redraw_status();
+    }
+
+    fn redraw_status(&mut self) {
+        if self.multi {
+            let mut out = std::io::stdout();
+            self.draw_status(&mut out);
+            out.flush().ok();
+        }
+    }
+
+    fn set_scroll_region(&self, out: &mut impl Write) {
+        write!(out, "\x1b[1;{}r", self.size.1.saturating_sub(1).max(1)).ok();
+    }
+
+    /// Нарисовать строку состояния в последней строке терминала
+    fn draw_status(&mut self, out: &mut impl Write) {
+        let mut line = String::new();
+
+        for (i, pane) in self.panes.iter().enumerate() {
+            let marker = if i == self.active {
+                "*"
+            } else if pane.closed {
+                "x"
+            } else if pane.unseen {
+                "+"
+            } else {
+                " "
+            };
+            line.push_str(&format!(" {}:{}{}", i + 1, pane.name, marker));
+        }
+
+        if let Some(notice) = self.notice.take() {
+            line.push_str(" | ");
+            line.push_str(&notice);
+        }
+
+        let width = self.size.0 as usize;
+        let line: String = line.chars().take(width).collect();
+        let padding = width.saturating_sub(line.chars().count());
+
+        // Сохранить курсор, нарисовать инверсную строку, восстановить курсор
+        write!(
+            out,
+            "\x1b7\x1b[{};1H\x1b[2K\x1b[7m{}{}\x1b[0m\x1b8",
+            self.size.1,
+            line,
+            " ".repeat(padding)
+        )
+        .ok();
+    }
 }
@@ -0,0 +1,136 @@
+//! Минимальный SOCKS5-сервер для динамического проброса (`-D`)
+//!
+//! Поддерживается только метод без аутентификации и команда CONNECT
+//! (RFC 1928), чего достаточно для браузеров и `curl --socks5-hostname`.
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const VERSION: u8 = 5;
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_UNACCEPTABLE: u8 = 0xFF;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+/// Код ответа: успех
+pub const REPLY_SUCCEEDED: u8 = 0x00;
+/// Код ответа: хост недоступен
+pub const REPLY_HOST_UNREACHABLE: u8 = 0x04;
+const REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+
+/// Выполнить согласование SOCKS5 и вернуть запрошенный адрес (хост, порт)
+pub async fn handshake<S>(stream: &mut S) -> io::Result<(String, u16)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Приветствие: VER NMETHODS METHODS...
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).await?;
+    if head[0] != VERSION {
+        return Err(invalid(t!(SOCKS_VERSION)));
+    }
+
+    let mut methods = vec![0u8; head[1] as usize];
+    stream.read_exact(&mut methods).await?;
+
+    if !methods.contains(&METHOD_NO_AUTH) {
+        stream.write_all(&[VERSION, METHOD_UNACCEPTABLE]).await?;
+        return Err(invalid(t!(SOCKS_AUTH_REQUIRED)));
+    }
+    stream.write_all(&[VERSION, METHOD_NO_AUTH]).await?;
+
+    // Запрос: VER CMD RSV ATYP DST.ADDR DST.PORT
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await?;
+    if request[0] != VERSION {
+        return Err(invalid(t!(SOCKS_VERSION)));
+    }
+
+    let host = match request[3] {
+        ATYP_IPV4 => {
+            let mut addr = [0u8; 4];
+            stream.read_exact(&mut addr).await?;
+            Ipv4Addr::from(addr).to_string()
+        }
+        ATYP_IPV6 => {
+            let mut addr = [0u8; 16];
+            stream.read_exact(&mut addr).await?;
+            Ipv6Addr::from(addr).to_string()
+        }
+        ATYP_DOMAIN => {
+            let len = stream.read_u8().await?;
+            let mut name = vec![0u8; len as usize];
+            stream.read_exact(&mut name).await?;
+            String::from_utf8(name).map_err(|_| invalid(t!(SOCKS_BAD_DOMAIN)))?
+        }
+        _ => return Err(invalid(t!(SOCKS_ADDRESS_TYPE))),
+    };
+
+    let port = stream.read_u16().await?;
+
+    if request[1] != CMD_CONNECT {
+        reply(stream, REPLY_COMMAND_NOT_SUPPORTED).await?;
+        return Err(invalid(t!(SOCKS_CONNECT_ONLY)));
+    }
+
+    Ok((host, port))
+}
+
+/// Отправить ответ на запрос CONNECT
+pub async fn reply<S>(stream: &mut S, code: u8) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    // Адрес привязки не имеет смысла для туннеля - отвечаем 0.0.0.0:0
+    stream
+        .write_all(&[VERSION, code, 0, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+        .await
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_handshake_domain() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+
+        let request = [
+            &[VERSION, 1, METHOD_NO_AUTH][..],
+            &[VERSION, CMD_CONNECT, 0, ATYP_DOMAIN, 9],
+            b"localhost",
+            &8080u16.to_be_bytes(),
+        ]
+        .concat();
+        client.write_all(&request).await.unwrap();
+
+        let target = handshake(&mut server).await.unwrap();
+        assert_eq!(target, ("localhost".to_string(), 8080));
+
+        let mut method = [0u8; 2];
+        client.read_exact(&mut method).await.unwrap();
+        assert_eq!(method, [VERSION, METHOD_NO_AUTH]);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_rejects_auth_only_clients() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+
+        // Только username/password (0x02)
+        client.write_all(&[VERSION, 1, 0x02]).await.unwrap();
+
+        assert!(handshake(&mut server).await.is_err());
+
+        let mut method = [0u8; 2];
+        client.read_exact(&mut method).await.unwrap();
+        assert_eq!(method, [VERSION, METHOD_UNACCEPTABLE]);
+    }
+}
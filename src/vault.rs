@@ -0,0 +1,261 @@
+//! Library API for embedding secure-ssh
+//!
+//! [`Vault`] gives programs (GUI launchers, tests) the same encrypted key
+//! and server list the CLI uses, without prompting on the terminal or
+//! printing anything: passwords are passed in and errors are returned.
+//!
+//! ```no_run
+//! use secure_ssh::{Server, Vault};
+//!
+//! # async fn example() -> secure_ssh::Result<()> {
+//! let mut vault = Vault::open("/media/usb/data")?;
+//! println!("{}", vault.public_key()?);
+//!
+//! vault.unlock(b"correct horse battery")?;
+//! vault.add_server(Server::new("web", "192.0.2.10", 22, "deploy"))?;
+//!
+//! let session = vault.connect("web").await?;
+//! let output = session.exec("uptime").await?;
+//! println!("{}", String::from_utf8_lossy(&output.stdout));
+//! session.disconnect().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use russh::client;
+
+use crate::config::{self, FsStorage, Server, ServerList, Storage, UnlockedVault};
+use crate::error::{Result, SecureSshError};
+use crate::ssh::{self, ExitStatus, SshClient};
+
+/// Vault files (`key.enc`, `key.pub`, `servers.enc`, or a single `vault.enc`) in a storage backend
+///
+/// Opening does not need the password; [`unlock`](Self::unlock) derives the
+/// key once and keeps the decrypted key and servers until [`lock`](Self::lock)
+/// or drop. Everything that needs them returns
+/// [`SecureSshError::VaultLocked`] while the vault is locked.
+pub struct Vault {
+    storage: Arc<dyn Storage>,
+    unlocked: Option<UnlockedVault>,
+}
+
+impl Vault {
+    /// Open the vault in a data directory created by `secure-ssh init`
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::with_storage(Arc::new(FsStorage::new(path)))
+    }
+
+    /// Open the vault kept in another backend, e.g. [`MemoryStorage`](config::MemoryStorage)
+    pub fn with_storage(storage: Arc<dyn Storage>) -> Result<Self> {
+        if !config::has_vault(&*storage)? {
+            return Err(SecureSshError::NotInitialized);
+        }
+
+        Ok(Self {
+            storage,
+            unlocked: None,
+        })
+    }
+
+    /// Public key in OpenSSH format
+    ///
+    /// Available without the password, except for a container vault, where
+    /// the public key is encrypted too and this needs [`unlock`](Self::unlock).
+    pub fn public_key(&self) -> Result<String> {
+        match &self.unlocked {
+            Some(vault) => vault.public_key(),
+            None => config::read_public_key(&*self.storage),
+        }
+    }
+
+    /// Decrypt the key and servers with the master password
+    ///
+    /// A wrong password fails with [`SecureSshError::InvalidPassword`] (or
+    /// [`SecureSshError::DecryptionFailed`] for files written before format 4)
+    /// and leaves the vault locked.
+    pub fn unlock(&mut self, password: &[u8]) -> Result<()> {
+        self.unlocked = Some(UnlockedVault::unlock(self.storage.clone(), password)?);
+        Ok(())
+    }
+
+    /// Forget the decrypted key and servers (they are zeroed on drop)
+    pub fn lock(&mut self) {
+        self.unlocked = None;
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked.is_some()
+    }
+
+    /// Configured servers
+    pub fn servers(&self) -> Result<&ServerList> {
+        Ok(self.unlocked()?.servers())
+    }
+
+    /// Add a server and save the server list
+    ///
+    /// The fields are checked the same way `secure-ssh add` checks them;
+    /// an invalid one is [`SecureSshError::InvalidConfig`].
+    pub fn add_server(&mut self, mut server: Server) -> Result<()> {
+        let vault = self.unlocked.as_mut().ok_or(SecureSshError::VaultLocked)?;
+
+        if vault.servers().get(&server.name).is_some() {
+            return Err(SecureSshError::ServerAlreadyExists(server.name));
+        }
+        config::validate_name(&server.name).map_err(SecureSshError::InvalidConfig)?;
+        server.host = config::parse_host(&server.host).map_err(SecureSshError::InvalidConfig)?;
+        config::validate_port(server.port).map_err(SecureSshError::InvalidConfig)?;
+        config::validate_user(&server.user).map_err(SecureSshError::InvalidConfig)?;
+        for jump in &server.proxy_jump {
+            config::validate_name(jump).map_err(SecureSshError::InvalidConfig)?;
+        }
+
+        vault
+            .servers_mut()
+            .add(server)
+            .map_err(|e| SecureSshError::InvalidConfig(e.to_string()))?;
+        vault.save()
+    }
+
+    /// Connect to a configured server, through its jump hosts if it has any
+    pub async fn connect(&self, name: &str) -> Result<Session> {
+        let vault = self.unlocked()?;
+        let servers = vault.servers();
+
+        let server = servers
+            .get(name)
+            .ok_or_else(|| SecureSshError::ServerNotFound(name.to_string()))?;
+        let route = servers.route(server).map_err(|e| {
+            SecureSshError::InvalidConfig(t!(SERVER_CONTEXT, server.name, e))
+        })?;
+
+        let handle = ssh::connect(&route, vault.private_key(), SshClient::default()).await?;
+        Ok(Session { handle })
+    }
+
+    fn unlocked(&self) -> Result<&UnlockedVault> {
+        self.unlocked.as_ref().ok_or(SecureSshError::VaultLocked)
+    }
+}
+
+/// Authenticated SSH connection returned by [`Vault::connect`]
+pub struct Session {
+    handle: client::Handle<SshClient>,
+}
+
+/// Result of [`Session::exec`]
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl Session {
+    /// Run a command without a PTY and collect its output
+    pub async fn exec(&self, command: &str) -> Result<Output> {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
+        let status = ssh::exec(&self.handle, command, |data, is_stderr| {
+            let buffer = if is_stderr { &mut stderr } else { &mut stdout };
+            buffer.extend_from_slice(data);
+        })
+        .await?;
+
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Underlying russh handle, for channels the API does not cover
+    pub fn handle(&self) -> &client::Handle<SshClient> {
+        &self.handle
+    }
+
+    /// Close the connection
+    pub async fn disconnect(self) {
+        self.handle
+            .disconnect(russh::Disconnect::ByApplication, "", "en")
+            .await
+            .ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MemoryStorage;
+    use crate::crypto::{self, KeyPair};
+
+    #[test]
+    fn test_open_requires_initialized_storage() {
+        assert!(matches!(
+            Vault::with_storage(Arc::new(MemoryStorage::new())),
+            Err(SecureSshError::NotInitialized)
+        ));
+    }
+
+    #[test]
+    fn test_unlock_add_server_and_reopen() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let keypair = KeyPair::generate().unwrap();
+        let public_key = keypair.public_key_openssh("test");
+        let derived_key = crypto::test_password_key(b"password");
+        UnlockedVault::create(storage.clone(), derived_key, keypair.into_private_key(), &public_key)
+            .unwrap();
+
+        let mut vault = Vault::with_storage(storage.clone()).unwrap();
+        assert_eq!(vault.public_key().unwrap(), public_key);
+        assert!(matches!(vault.servers(), Err(SecureSshError::VaultLocked)));
+        assert!(vault.unlock(b"wrong").is_err());
+        assert!(!vault.is_unlocked());
+
+        vault.unlock(b"password").unwrap();
+        vault.add_server(Server::new("web", "192.0.2.10", 22, "deploy")).unwrap();
+        assert!(matches!(
+            vault.add_server(Server::new("web", "192.0.2.11", 22, "deploy")),
+            Err(SecureSshError::ServerAlreadyExists(_))
+        ));
+
+        let mut reopened = Vault::with_storage(storage).unwrap();
+        reopened.unlock(b"password").unwrap();
+        assert_eq!(reopened.servers().unwrap().get("web").unwrap().host, "192.0.2.10");
+    }
+
+    #[test]
+    fn test_add_server_validates_fields() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let keypair = KeyPair::generate().unwrap();
+        let public_key = keypair.public_key_openssh("test");
+        let derived_key = crypto::test_password_key(b"password");
+        UnlockedVault::create(storage.clone(), derived_key, keypair.into_private_key(), &public_key)
+            .unwrap();
+
+        let mut vault = Vault::with_storage(storage).unwrap();
+        vault.unlock(b"password").unwrap();
+
+        let mut bad_jump = Server::new("jumped", "192.0.2.10", 22, "deploy");
+        bad_jump.proxy_jump = vec!["bastion\nProxyCommand sh".to_string()];
+        for server in [
+            Server::new("web 1", "192.0.2.10", 22, "deploy"),
+            Server::new("web", "bad host", 22, "deploy"),
+            Server::new("web", "192.0.2.10", 0, "deploy"),
+            Server::new("web", "192.0.2.10", 22, "de ploy"),
+            Server::new("web", "192.0.2.10", 22, ""),
+            bad_jump,
+        ] {
+            let name = server.name.clone();
+            assert!(
+                matches!(vault.add_server(server), Err(SecureSshError::InvalidConfig(_))),
+                "{name} was accepted"
+            );
+        }
+        assert!(vault.servers().unwrap().is_empty());
+    }
+}